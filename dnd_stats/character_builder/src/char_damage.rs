@@ -4,8 +4,7 @@ use std::fmt::Debug;
 use combat_core::CCError;
 use combat_core::damage::{DamageDice, DamageExpression, DamageFeature, DamageManager, ExtendedDamageDice, ExtendedDamageType};
 use combat_core::damage::dice_expr::{DiceExpr, DiceExpression, DiceExprTerm};
-use rand_var::num_rand_var::NumRandVar;
-use rand_var::vec_rand_var::VecRandVar;
+use rand_var::lazy_rv::{ExprId, LazyRV};
 use rand_var::rand_var::prob_type::RVProb;
 
 use crate::{CBError, Character};
@@ -113,18 +112,14 @@ impl DiceExpr for CharDiceExpr {
         };
     }
 
-    fn get_dice_rv<P: RVProb>(&self, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<VecRandVar<P>, CCError> {
+    fn get_dice_expr<P: RVProb>(&self, lazy: &mut LazyRV<P>, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<ExprId, CCError> {
         let gwf = dmg_feats.contains(&DamageFeature::GWF);
-        let mut rv: VecRandVar<P> = VecRandVar::new_constant(0).unwrap();
+        let mut terms = Vec::with_capacity(self.dice_terms.len());
         for ext_dice in self.dice_terms.iter() {
             let dice = CharDiceExpr::get_die(ext_dice, weapon_dmg)?;
-            if gwf {
-                rv = rv.add_rv(&dice.get_rv_gwf());
-            } else {
-                rv = rv.add_rv(&dice.get_rv());
-            }
+            terms.push(dice.get_expr(lazy, gwf));
         }
-        Ok(rv)
+        Ok(lazy.sum(terms))
     }
 
     fn get_const(&self) -> isize {
@@ -91,7 +91,7 @@ impl ClassName {
             ClassName::Ranger => Ok(Box::new(VariantRangerClass)),
             ClassName::Rogue => Ok(Box::new(RogueClass)),
             ClassName::Wizard => Ok(Box::new(WizardClass)),
-            _ => Err(CBError::NotImplemented)
+            _ => Err(CBError::NotImplemented(format!("the {:?} class", self)))
         }
     }
 
@@ -38,7 +38,7 @@ impl Class for FighterClass {
             18 => Ok(self.get_subclass_features(level)),
             19 => Ok(Vec::new()),
             20 => Ok(vec!(Box::new(ExtraAttack(4)))),
-            _ => Err(CBError::InvalidLevel)
+            _ => Err(CBError::InvalidLevel(level))
         }
     }
 }
@@ -57,7 +57,7 @@ impl SubClass for ChampionFighter {
             10 => Ok(Vec::new()),
             15 => Ok(vec!(Box::new(ImprovedCritical(18)))),
             18 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel),
+            _ => Err(CBError::InvalidLevel(level)),
         }
     }
 }
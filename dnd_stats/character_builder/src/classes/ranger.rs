@@ -40,7 +40,7 @@ impl Class for VariantRangerClass {
             18 => Ok(Vec::new()),
             19 => Ok(Vec::new()),
             20 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel),
+            _ => Err(CBError::InvalidLevel(level)),
         }
     }
 }
@@ -58,7 +58,7 @@ impl SubClass for HorizonWalkerRanger {
             7 => Ok(Vec::new()),
             11 => Ok(vec!(Box::new(PlanarWarrior(2)))), // TODO: situational third attack. Validation ?
             15 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel),
+            _ => Err(CBError::InvalidLevel(level)),
         }
     }
 }
@@ -1,8 +1,9 @@
 use combat_core::ability_scores::Ability;
 use combat_core::attack::AttackResult;
-use combat_core::damage::{DamageDice, DamageTerm, ExtendedDamageDice, ExtendedDamageType};
+use combat_core::conditions::AttackDistance;
+use combat_core::damage::{DamageDice, DamageReduction, DamageTerm, ExtendedDamageDice, ExtendedDamageType};
 use combat_core::damage::dice_expr::DiceExprTerm;
-use combat_core::resources::{RefreshTiming, Resource, ResourceName};
+use combat_core::resources::{RefreshTiming, Resource, ResourceActionType, ResourceName};
 use combat_core::resources::resource_amounts::{RefreshBy, ResourceCap};
 use combat_core::triggers::{TriggerAction, TriggerContext, TriggerInfo, TriggerName, TriggerType};
 
@@ -31,7 +32,7 @@ impl Class for RogueClass {
                 Ok(v)
             },
             4 => Ok(Vec::new()),
-            5 => Ok(vec!(self.sneak_attack(level))), // TODO: uncanny dodge: when reactions get impl'd
+            5 => Ok(vec!(self.sneak_attack(level), Box::new(UncannyDodge))),
             6 => Ok(Vec::new()),
             7 => Ok(vec!(self.sneak_attack(level))), // TODO: impl Evasion: How?
             8 => Ok(Vec::new()),
@@ -59,7 +60,7 @@ impl Class for RogueClass {
             18 => Ok(Vec::new()), // TODO: impl Elusive: as permanent condition ?
             19 => Ok(vec!(self.sneak_attack(level))),
             20 => Ok(Vec::new()), // TODO: impl stroke of luck: OnMiss ActionType ?
-            _ => Err(CBError::InvalidLevel)
+            _ => Err(CBError::InvalidLevel(level))
         }
     }
 }
@@ -78,7 +79,7 @@ impl SubClass for ScoutRogue {
             9 => Ok(Vec::new()),
             13 => Ok(Vec::new()),
             17 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel),
+            _ => Err(CBError::InvalidLevel(level)),
         }
     }
 }
@@ -97,7 +98,7 @@ impl SubClass for ArcaneTricksterRogue {
             9 => Ok(Vec::new()),
             13 => Ok(Vec::new()),
             17 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel),
+            _ => Err(CBError::InvalidLevel(level)),
         }
     }
 
@@ -132,6 +133,20 @@ impl Feature for SneakAttack {
     }
 }
 
+// this is an auto trigger, so it always gets used on the first hit
+pub struct UncannyDodge;
+impl Feature for UncannyDodge {
+    fn apply(&self, character: &mut Character) -> Result<(), CBError> {
+        let response = (TriggerAction::ReduceDamage(AttackDistance::Any, DamageReduction::Half), ResourceName::RAT(ResourceActionType::Reaction)).into();
+        let on_hit = TriggerInfo::new(TriggerType::WasHit, TriggerContext::AR(AttackResult::Hit));
+        let on_crit = TriggerInfo::new(TriggerType::WasHit, TriggerContext::AR(AttackResult::Crit));
+        character.trigger_manager.add_auto_trigger(on_hit, TriggerName::UncannyDodge);
+        character.trigger_manager.add_auto_trigger(on_crit, TriggerName::UncannyDodge);
+        character.trigger_manager.set_response(TriggerName::UncannyDodge, response);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::rc::Rc;
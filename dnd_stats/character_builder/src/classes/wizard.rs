@@ -31,7 +31,7 @@ impl Class for WizardClass {
             18 => Ok(Vec::new()),
             19 => Ok(Vec::new()),
             20 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel)
+            _ => Err(CBError::InvalidLevel(level))
         }
     }
 }
@@ -50,7 +50,7 @@ impl SubClass for ConjurationWizard {
             6 => Ok(Vec::new()),
             10 => Ok(Vec::new()),
             14 => Ok(Vec::new()),
-            _ => Err(CBError::InvalidLevel),
+            _ => Err(CBError::InvalidLevel(level)),
         }
     }
 }
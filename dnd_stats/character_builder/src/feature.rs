@@ -1,6 +1,7 @@
 use combat_core::ability_scores::Ability;
 use combat_core::actions::{ActionName, ActionType, CombatAction, CombatOption};
-use combat_core::resources::{ResourceActionType, ResourceName};
+use combat_core::resources::{RefreshTiming, Resource, ResourceActionType, ResourceName};
+use combat_core::resources::resource_amounts::{RefreshBy, ResourceCap};
 
 use crate::{CBError, Character};
 
@@ -65,3 +66,18 @@ impl Feature for ExtraAttack {
         Ok(())
     }
 }
+
+// there's no paladin yet, so the size of the pool is given directly
+pub struct LayOnHands(pub usize);
+impl Feature for LayOnHands {
+    fn apply(&self, character: &mut Character) -> Result<(), CBError> {
+        character.combat_actions.insert(
+            ActionName::LayOnHands,
+            CombatOption::new_target(ActionType::Action, CombatAction::HealFromPool(ResourceName::LayOnHands), true)
+        );
+        let mut res = Resource::from(ResourceCap::Hard(self.0));
+        res.add_refresh(RefreshTiming::LongRest, RefreshBy::ToFull);
+        character.resource_manager.add_perm(ResourceName::LayOnHands, res);
+        Ok(())
+    }
+}
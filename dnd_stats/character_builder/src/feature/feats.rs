@@ -3,7 +3,8 @@ use std::clone::Clone;
 use combat_core::ability_scores::Ability;
 use combat_core::actions::{ActionName, ActionType, AttackType, CombatAction, CombatOption};
 use combat_core::attack::AttackResult;
-use combat_core::damage::{DamageTerm, ExtendedDamageType};
+use combat_core::conditions::{Condition, ConditionEffect, ConditionLifetime, ConditionName};
+use combat_core::damage::{DamageReduction, DamageTerm, ExtendedDamageType, ReducedTypes};
 use combat_core::damage::dice_expr::DiceExprTerm;
 use combat_core::resources::{RefreshTiming, Resource, ResourceActionType, ResourceName};
 use combat_core::resources::resource_amounts::{RefreshBy, ResourceCap, ResourceCount};
@@ -11,7 +12,7 @@ use combat_core::triggers::{TriggerAction, TriggerContext, TriggerInfo, TriggerN
 
 use crate::{CBError, Character, CharacterCO};
 use crate::attributed_bonus::{BonusTerm, BonusType};
-use crate::equipment::{Weapon, WeaponProperty};
+use crate::equipment::{ArmorType, Weapon, WeaponProperty};
 use crate::feature::Feature;
 
 pub struct GreatWeaponMaster;
@@ -182,7 +183,7 @@ impl Feature for ShieldMaster {
 
         let shield = character.get_equipment().get_shield();
         if shield.is_none() {
-            return Err(CBError::RequirementsNotMet);
+            return Err(CBError::RequirementsNotMet(String::from("shield master needs a shield equipped")));
         }
         let bonus = shield.unwrap().get_base_ac() + shield.unwrap().get_magic_bonus().unwrap_or(0);
         // TODO: this will get messy if you change out the shield
@@ -194,6 +195,22 @@ impl Feature for ShieldMaster {
     }
 }
 
+pub struct HeavyArmorMaster;
+impl Feature for HeavyArmorMaster {
+    fn apply(&self, character: &mut Character) -> Result<(), CBError> {
+        if character.get_equipment().get_armor().get_armor_type() != &ArmorType::HeavyArmor {
+            return Err(CBError::RequirementsNotMet(String::from("heavy armor master needs heavy armor equipped")));
+        }
+        character.ability_scores.get_score_mut(&Ability::STR).increase();
+        let cond = Condition {
+            effects: vec!(ConditionEffect::DmgReduction(DamageReduction::Flat(3, ReducedTypes::NonmagicalBPS))),
+            lifetimes: vec!(ConditionLifetime::Permanent),
+        };
+        character.condition_manager.add_condition(ConditionName::HeavyArmorMaster, cond);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -205,6 +222,7 @@ mod tests {
     use combat_core::actions::{ActionName, AttackType, CombatAction};
     use combat_core::attack::AccMRV64;
     use combat_core::D20RollType;
+    use combat_core::damage::DamageDefenses;
     use rand_var::num_rand_var::NumRandVar;
     use rand_var::rand_var::RandVar;
     use rand_var::rand_var::sequential::Pair;
@@ -254,7 +272,7 @@ mod tests {
         let acc: AccMRV64 = gwm_attack.get_accuracy_rv(D20RollType::Normal).unwrap();
         assert_eq!(Pair(1, 1), acc.lower_bound());
         assert_eq!(Pair(20, 20), acc.upper_bound());
-        let dmg: VRVBig = gwm_attack.get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = gwm_attack.get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(15, dmg.lower_bound());
         assert_eq!(25, dmg.upper_bound());
         assert_eq!(BigRational::from_isize(20).unwrap(), dmg.expected_value());
@@ -274,7 +292,7 @@ mod tests {
         let acc: AccMRV64 = ss_attack.get_accuracy_rv(D20RollType::Normal).unwrap();
         assert_eq!(Pair(1, 1), acc.lower_bound());
         assert_eq!(Pair(20, 20), acc.upper_bound());
-        let dmg: VRVBig = ss_attack.get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = ss_attack.get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(14, dmg.lower_bound());
         assert_eq!(21, dmg.upper_bound());
         assert_eq!(BigRational::new(BigInt::from_isize(35).unwrap(), BigInt::from_isize(2).unwrap()), dmg.expected_value());
@@ -294,7 +312,7 @@ mod tests {
         let acc: AccMRV64 = pam_attack.get_accuracy_rv(D20RollType::Normal).unwrap();
         assert_eq!(Pair(1, 6), acc.lower_bound());
         assert_eq!(Pair(20, 25), acc.upper_bound());
-        let dmg: VRVBig = pam_attack.get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = pam_attack.get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(4, dmg.lower_bound());
         assert_eq!(7, dmg.upper_bound());
         assert_eq!(BigRational::new(BigInt::from_isize(11).unwrap(), BigInt::from_isize(2).unwrap()), dmg.expected_value());
@@ -321,7 +339,7 @@ mod tests {
         let acc: AccMRV64 = gwm_pam_attack.get_accuracy_rv(D20RollType::Normal).unwrap();
         assert_eq!(Pair(1, 1), acc.lower_bound());
         assert_eq!(Pair(20, 20), acc.upper_bound());
-        let dmg: VRVBig = gwm_pam_attack.get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = gwm_pam_attack.get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(14, dmg.lower_bound());
         assert_eq!(17, dmg.upper_bound());
         assert_eq!(BigRational::new(BigInt::from_isize(31).unwrap(), BigInt::from_isize(2).unwrap()), dmg.expected_value());
@@ -348,7 +366,7 @@ mod tests {
         let acc: AccMRV64 = pam_gwm_attack.get_accuracy_rv(D20RollType::Normal).unwrap();
         assert_eq!(Pair(1, 1), acc.lower_bound());
         assert_eq!(Pair(20, 20), acc.upper_bound());
-        let dmg: VRVBig = pam_gwm_attack.get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = pam_gwm_attack.get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(14, dmg.lower_bound());
         assert_eq!(17, dmg.upper_bound());
         assert_eq!(BigRational::new(BigInt::from_isize(31).unwrap(), BigInt::from_isize(2).unwrap()), dmg.expected_value());
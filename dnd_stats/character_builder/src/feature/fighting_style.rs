@@ -105,6 +105,7 @@ mod tests {
 
     use combat_core::attack::AccMRV64;
     use combat_core::D20RollType;
+    use combat_core::damage::DamageDefenses;
     use rand_var::num_rand_var::NumRandVar;
     use rand_var::rand_var::RandVar;
     use rand_var::rand_var::sequential::Pair;
@@ -153,7 +154,7 @@ mod tests {
         );
         let mut fighter = Character::new(String::from("duelist"), get_dex_based(), equipment);
         fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::Dueling)))).unwrap();
-        let dmg: VRVBig = fighter.get_weapon_attack().unwrap().get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = fighter.get_weapon_attack().unwrap().get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(6, dmg.lower_bound());
         assert_eq!(13, dmg.upper_bound());
         assert_eq!(BigRational::new(BigInt::from(19), BigInt::from(2)), dmg.expected_value());
@@ -168,7 +169,7 @@ mod tests {
         );
         let mut fighter = Character::new(String::from("gwf"), get_str_based(), equipment);
         fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
-        let dmg: VRVBig = fighter.get_weapon_attack().unwrap().get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let dmg: VRVBig = fighter.get_weapon_attack().unwrap().get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(5, dmg.lower_bound());
         assert_eq!(15, dmg.upper_bound());
         let rv: VRVBig = VecRandVar::new_dice_reroll(6, 2).unwrap().multiple(2).add_const(3);
@@ -184,8 +185,8 @@ mod tests {
         );
         let mut fighter = Character::new(String::from("kirito"), get_dex_based(), equipment);
         fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::TwoWeaponFighting)))).unwrap();
-        let main_dmg: VRVBig = fighter.get_weapon_attack().unwrap().get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
-        let off_dmg: VRVBig = fighter.get_offhand_attack().unwrap().get_damage().cdm.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let main_dmg: VRVBig = fighter.get_weapon_attack().unwrap().get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
+        let off_dmg: VRVBig = fighter.get_offhand_attack().unwrap().get_damage().cdm.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(main_dmg, off_dmg);
     }
 }
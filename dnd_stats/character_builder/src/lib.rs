@@ -1,14 +1,16 @@
 use std::cmp::min;
 use std::collections::{HashMap, HashSet};
-use std::fmt::Debug;
-use std::fmt::Write;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
 use std::rc::Rc;
+use std::sync::Arc;
 
 use combat_core::ability_scores::AbilityScores;
 use combat_core::actions::{ActionBuilder, ActionName, ActionType, AttackType, CombatAction, CombatOption};
 use combat_core::CCError;
 use combat_core::conditions::ConditionManager;
-use combat_core::damage::DamageType;
+use combat_core::damage::{DamageDefenses, DamageType};
 use combat_core::resources::{ResourceActionType, ResourceManager, ResourceName};
 use combat_core::skills::SkillManager;
 use combat_core::spells::SpellManager;
@@ -35,16 +37,43 @@ pub mod weapon_attack;
 pub enum CBError {
     NoCache,
     NoWeaponSet,
-    NotImplemented,
+    NotImplemented(String),
     NoClassSet,
     NoSubClassSet,
-    InvalidLevel,
-    RequirementsNotMet,
+    InvalidLevel(u8),
+    RequirementsNotMet(String),
     RVError(RVError),
     CCE(CCError),
     Other(String),
 }
 
+impl Display for CBError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CBError::NoCache => write!(f, "the character has to be cached first"),
+            CBError::NoWeaponSet => write!(f, "weapon damage was used, but no weapon is equipped"),
+            CBError::NotImplemented(what) => write!(f, "{} is not implemented yet", what),
+            CBError::NoClassSet => write!(f, "the character has no class to level up in"),
+            CBError::NoSubClassSet => write!(f, "the character has no subclass set"),
+            CBError::InvalidLevel(lvl) => write!(f, "level {} is not a valid level for this class", lvl),
+            CBError::RequirementsNotMet(what) => write!(f, "requirements not met: {}", what),
+            CBError::RVError(_) => write!(f, "random variable error"),
+            CBError::CCE(_) => write!(f, "combat error"),
+            CBError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for CBError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CBError::RVError(err) => Some(err),
+            CBError::CCE(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl From<RVError> for CBError {
     fn from(value: RVError) -> Self {
         CBError::RVError(value)
@@ -57,11 +86,13 @@ impl From<CCError> for CBError {
     }
 }
 
+// the original error is kept, so it can still be downcast back into a CBError
 impl From<CBError> for CCError {
     fn from(value: CBError) -> Self {
-        let mut s = String::new();
-        write!(s, "{:?}", value).unwrap();
-        CCError::Other(s)
+        match value {
+            CBError::CCE(err) => err,
+            other => CCError::External(Arc::new(other)),
+        }
     }
 }
 
@@ -79,7 +110,7 @@ pub struct Character {
     armor_class: AttributedBonus,
     combat_actions: ActionBuilder<WeaponAttack, CharDiceExpr>,
     resource_manager: ResourceManager,
-    resistances: HashSet<DamageType>,
+    dmg_defenses: DamageDefenses,
     condition_manager: ConditionManager,
     trigger_manager: TriggerManager,
     spell_manager: SpellManager,
@@ -98,7 +129,7 @@ impl Character {
             armor_class: AttributedBonus::new(String::from("AC")),
             combat_actions: ActionBuilder::new(),
             resource_manager: ResourceManager::just_action_types(),
-            resistances: HashSet::new(),
+            dmg_defenses: DamageDefenses::new(),
             condition_manager: ConditionManager::new(),
             trigger_manager: TriggerManager::new(),
             spell_manager: SpellManager::new(),
@@ -295,7 +326,11 @@ impl Character {
     }
 
     pub fn get_resistances(&self) -> &HashSet<DamageType> {
-        &self.resistances
+        &self.dmg_defenses.resistances
+    }
+
+    pub fn get_dmg_defenses(&self) -> &DamageDefenses {
+        &self.dmg_defenses
     }
 
     pub fn get_condition_manager(&self) -> &ConditionManager {
@@ -358,6 +393,26 @@ mod tests {
         fighter
     }
 
+    #[test]
+    fn error_chain_test() {
+        let cce: CCError = CBError::InvalidLevel(21).into();
+        assert_eq!("level 21 is not a valid level for this class", cce.to_string());
+        if let CCError::External(err) = &cce {
+            assert!(matches!(err.downcast_ref::<CBError>(), Some(CBError::InvalidLevel(21))));
+        } else {
+            panic!("CBError should be kept as is");
+        }
+
+        // round trips don't add layers
+        let cce: CCError = CBError::CCE(CCError::NoWeaponSet).into();
+        assert!(matches!(cce, CCError::NoWeaponSet));
+
+        let cbe = CBError::CCE(CCError::RVE(RVError::Overflow));
+        let cause = cbe.source().unwrap();
+        assert_eq!("random variable error", cause.to_string());
+        assert!(cause.source().unwrap().to_string().starts_with("overflow"));
+    }
+
     #[test]
     fn basic_character_test() {
         let fighter = get_test_fighter();
@@ -8,10 +8,11 @@ use crate::classes::ranger::HorizonWalkerRanger;
 use crate::classes::rogue::{ArcaneTricksterRogue, ScoutRogue};
 use crate::classes::wizard::ConjurationWizard;
 use crate::equipment::{ACSource, Armor, ArmorName, Equipment, OffHand, Weapon, WeaponName};
-use crate::feature::{AbilityScoreIncrease, ExtraAttack, Feature, SaveProficiencies};
-use crate::feature::feats::{GreatWeaponMaster, PolearmMaster, Resilient, SharpShooter, ShieldMaster};
+use crate::feature::{AbilityScoreIncrease, ExtraAttack, Feature, LayOnHands, SaveProficiencies};
+use crate::feature::feats::{GreatWeaponMaster, HeavyArmorMaster, PolearmMaster, Resilient, SharpShooter, ShieldMaster};
 use crate::feature::fighting_style::{FightingStyle, FightingStyles};
 use crate::spellcasting::cantrips::FireBoltCantrip;
+use crate::spellcasting::first_lvl_spells::{CureWoundsSpell, HealingWordSpell};
 use crate::spellcasting::fourth_lvl_spells::GreaterInvisibilitySpell;
 use crate::spellcasting::third_lvl_spells::{FireBallSpell, HasteSpell};
 
@@ -151,12 +152,16 @@ pub enum FeatureName {
     ExtraAttack(usize),
     FightingStyle(FightingStyles),
     GreatWeaponMaster,
+    HeavyArmorMaster,
+    LayOnHands(usize),
     PolearmMaster,
     Resilient(Ability),
     SharpShooter,
     ShieldMaster,
     Subclass(SubClassName),
     FireBolt(Ability),
+    CureWounds(Ability),
+    HealingWord(Ability),
     Fireball(Ability),
     Haste,
     GreaterInvisibility,
@@ -170,12 +175,16 @@ impl FeatureName {
             FeatureName::ExtraAttack(aa) => Box::new(ExtraAttack(*aa)),
             FeatureName::FightingStyle(fs) => Box::new(FightingStyle(*fs)),
             FeatureName::GreatWeaponMaster => Box::new(GreatWeaponMaster),
+            FeatureName::HeavyArmorMaster => Box::new(HeavyArmorMaster),
+            FeatureName::LayOnHands(pool) => Box::new(LayOnHands(*pool)),
             FeatureName::PolearmMaster => Box::new(PolearmMaster),
             FeatureName::Resilient(ab) => Box::new(Resilient(*ab)),
             FeatureName::SharpShooter => Box::new(SharpShooter),
             FeatureName::ShieldMaster => Box::new(ShieldMaster),
             FeatureName::Subclass(scn) => Box::new(ChooseSubClass(scn.to_subclass())),
             FeatureName::FireBolt(ab) => Box::new(FireBoltCantrip(*ab)),
+            FeatureName::CureWounds(ab) => Box::new(CureWoundsSpell(*ab)),
+            FeatureName::HealingWord(ab) => Box::new(HealingWordSpell(*ab)),
             FeatureName::Fireball(ab) => Box::new(FireBallSpell(*ab)),
             FeatureName::Haste => Box::new(HasteSpell),
             FeatureName::GreaterInvisibility => Box::new(GreaterInvisibilitySpell),
@@ -2,6 +2,7 @@ use crate::Character;
 use crate::classes::SpellCasterType;
 
 pub mod cantrips;
+pub mod first_lvl_spells;
 pub mod third_lvl_spells;
 pub mod fourth_lvl_spells;
 
@@ -34,7 +34,8 @@ impl Feature for FireBoltCantrip {
             ExtendedDamageType::Basic(DamageType::Fire)
         ));
         let bonus = (character.get_prof_bonus() as isize) + (character.get_ability_scores().get_score(&self.0).get_mod() as isize);
-        let atk = BasicAttack::prebuilt(dmg, bonus, 20);
+        let mut atk = BasicAttack::prebuilt(dmg, bonus, 20);
+        atk.set_magical(true);
         let spell_effect = SpellEffect::SpellAttack(atk);
         let spell = Spell::new(SpellSlot::Cantrip, spell_effect);
         character.spell_manager.insert(SpellName::FireBolt, spell);
@@ -0,0 +1,42 @@
+use combat_core::ability_scores::Ability;
+use combat_core::actions::{ActionName, ActionType, CombatAction, CombatOption};
+use combat_core::damage::{DamageDice, ExtendedDamageDice};
+use combat_core::damage::dice_expr::{DiceExpr, DiceExpression, DiceExprTerm};
+use combat_core::spells::{Spell, SpellEffect, SpellName, SpellSlot};
+use crate::{CBError, Character};
+use crate::feature::Feature;
+
+fn heal_expr(character: &Character, die: DamageDice, ability: Ability) -> DiceExpression {
+    let mut heal = DiceExpression::from(DiceExprTerm::Die(ExtendedDamageDice::Basic(die)));
+    heal.add_term(DiceExprTerm::Const(character.get_ability_scores().get_score(&ability).get_mod() as isize));
+    heal
+}
+
+// TODO: upcasting
+pub struct CureWoundsSpell(pub Ability);
+impl Feature for CureWoundsSpell {
+    fn apply(&self, character: &mut Character) -> Result<(), CBError> {
+        let co = CombatOption::new_spell(ActionType::Action, CombatAction::CastSpell, true, true);
+        character.combat_actions.insert(ActionName::CastSpell(SpellName::CureWounds), co);
+
+        let spell_effect = SpellEffect::Heal(heal_expr(character, DamageDice::D8, self.0));
+        let spell = Spell::new(SpellSlot::First, spell_effect);
+        character.spell_manager.insert(SpellName::CureWounds, spell);
+
+        Ok(())
+    }
+}
+
+pub struct HealingWordSpell(pub Ability);
+impl Feature for HealingWordSpell {
+    fn apply(&self, character: &mut Character) -> Result<(), CBError> {
+        let co = CombatOption::new_spell(ActionType::BonusAction, CombatAction::CastSpell, true, true);
+        character.combat_actions.insert(ActionName::CastSpell(SpellName::HealingWord), co);
+
+        let spell_effect = SpellEffect::Heal(heal_expr(character, DamageDice::D4, self.0));
+        let spell = Spell::new(SpellSlot::First, spell_effect);
+        character.spell_manager.insert(SpellName::HealingWord, spell);
+
+        Ok(())
+    }
+}
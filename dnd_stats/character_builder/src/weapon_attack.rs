@@ -5,7 +5,7 @@ use combat_core::ability_scores::Ability;
 use combat_core::attack::{AccMRV, AoMRV, ArMRV, AtkDmgMap, Attack, AttackResult};
 use combat_core::attack::basic_attack::BasicAttack;
 use combat_core::conditions::AttackDistance;
-use combat_core::damage::{DamageDice, DamageFeature, DamageTerm, DamageType, ExtendedDamageDice, ExtendedDamageType};
+use combat_core::damage::{DamageDefenses, DamageDice, DamageFeature, DamageTerm, ExtendedDamageDice, ExtendedDamageType};
 use combat_core::movement::Feet;
 use rand_var::vec_rand_var::VecRandVar;
 use rand_var::rand_var::prob_type::RVProb;
@@ -187,8 +187,8 @@ impl WeaponAttack {
         &mut self.damage
     }
 
-    pub fn get_dmg_map<P: RVProb>(&self, resistances: &HashSet<DamageType>) -> Result<AtkDmgMap<P>, CBError> {
-        Ok(self.damage.cdm.get_attack_dmg_map(resistances)?)
+    pub fn get_dmg_map<P: RVProb>(&self, defenses: &DamageDefenses) -> Result<AtkDmgMap<P>, CBError> {
+        Ok(self.damage.cdm.get_attack_dmg_map(defenses)?)
     }
 
     pub fn get_accuracy_rv<P: RVProb>(&self, hit_type: D20RollType) -> Result<AccMRV<P>, CBError> {
@@ -205,15 +205,15 @@ impl WeaponAttack {
         Ok(hit_rv.map_keys(|hit| AttackResult::from(hit, target_ac, self.get_crit_lb())))
     }
 
-    pub fn get_attack_dmg_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, resistances: &HashSet<DamageType>) -> Result<VecRandVar<P>, CBError> {
+    pub fn get_attack_dmg_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, defenses: &DamageDefenses) -> Result<VecRandVar<P>, CBError> {
         let attack_result_rv = self.get_attack_result_rv(hit_type, target_ac)?;
-        let dmg_map = self.get_dmg_map(resistances)?;
+        let dmg_map = self.get_dmg_map(defenses)?;
         Ok(attack_result_rv.consolidate(&dmg_map.into_ar_map())?.into())
     }
 
-    pub fn get_attack_outcome_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, resistances: &HashSet<DamageType>) -> Result<AoMRV<P>, CBError> {
+    pub fn get_attack_outcome_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, defenses: &DamageDefenses) -> Result<AoMRV<P>, CBError> {
         let attack_result_rv = self.get_attack_result_rv(hit_type, target_ac)?;
-        let dmg_map = self.get_dmg_map(resistances)?;
+        let dmg_map = self.get_dmg_map(defenses)?;
         Ok(attack_result_rv.projection(&dmg_map.into_ar_map())?)
     }
 }
@@ -226,16 +226,16 @@ impl From<WeaponAttack> for BasicAttack {
 }
 
 impl Attack for WeaponAttack {
-    fn get_miss_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.damage.cdm.get_miss_dmg(resistances, bonus_dmg, dmg_feats)?)
+    fn get_miss_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.damage.cdm.get_miss_dmg(defenses, bonus_dmg, dmg_feats)?)
     }
 
-    fn get_hit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.damage.cdm.get_base_dmg(resistances, bonus_dmg, dmg_feats)?)
+    fn get_hit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.damage.cdm.get_base_dmg(defenses, bonus_dmg, dmg_feats)?)
     }
 
-    fn get_crit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.damage.cdm.get_crit_dmg(resistances, bonus_dmg, dmg_feats)?)
+    fn get_crit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.damage.cdm.get_crit_dmg(defenses, bonus_dmg, dmg_feats)?)
     }
 
     fn get_acc_rv<P: RVProb>(&self, hit_type: D20RollType) -> Result<AccMRV<P>, CCError> {
@@ -269,8 +269,13 @@ impl Attack for WeaponAttack {
         self.hit_bonus.get_saved_value().unwrap_or(0) as isize
     }
 
-    fn get_dmg_map<P: RVProb>(&self, resistances: &HashSet<DamageType>) -> Result<AtkDmgMap<P>, CCError> {
-        Ok(self.get_damage().cdm.get_attack_dmg_map(resistances)?)
+    fn is_magical(&self) -> bool {
+        // there aren't any magic weapons yet
+        false
+    }
+
+    fn get_dmg_map<P: RVProb>(&self, defenses: &DamageDefenses) -> Result<AtkDmgMap<P>, CCError> {
+        Ok(self.get_damage().cdm.get_attack_dmg_map(defenses)?)
     }
 }
 
@@ -281,6 +286,7 @@ mod tests {
     use num::{BigRational, FromPrimitive};
 
     use combat_core::attack::{ArMRVBig, AttackResult};
+    use combat_core::damage::DamageDefenses;
     use combat_core::D20RollType;
     use rand_var::map_rand_var::MRVBig;
     use rand_var::num_rand_var::NumRandVar;
@@ -296,7 +302,7 @@ mod tests {
     fn weapon_atk_test() {
         let fighter = get_test_fighter();
         assert_eq!(3, fighter.get_ability_scores().strength.get_mod());
-        let no_resist = HashSet::new();
+        let no_resist = DamageDefenses::new();
 
         let mut attack = WeaponAttack::new(fighter.get_equipment().get_primary_weapon(), HandType::MainHand, NumHands::TwoHand);
         attack.cache_char_vals(&fighter);
@@ -331,7 +337,7 @@ mod tests {
     fn validate_weapon_atk() {
         let fighter = get_test_fighter();
         let attack = fighter.get_weapon_attack().unwrap();
-        let no_resist = HashSet::new();
+        let no_resist = DamageDefenses::new();
         let ac = 13;
         // greatsword attack: d20 + 5 vs 13 @ 2d6 + 3
         let result_rv: ArMRVBig = attack.get_attack_result_rv(D20RollType::Normal, ac).unwrap();
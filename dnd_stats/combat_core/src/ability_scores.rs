@@ -5,6 +5,7 @@ use rand_var::map_rand_var::MapRandVar;
 use rand_var::vec_rand_var::VecRandVar;
 use rand_var::num_rand_var::NumRandVar;
 use rand_var::rand_var::prob_type::RVProb;
+use rand_var::RVError;
 
 use crate::{BinaryOutcome, D20RollType, D20Type};
 use crate::combat_event::CombatEvent;
@@ -35,6 +36,12 @@ impl Display for Ability {
     }
 }
 
+impl Ability {
+    pub fn all() -> [Ability; 6] {
+        [Ability::STR, Ability::DEX, Ability::CON, Ability::INT, Ability::WIS, Ability::CHA]
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct AbilityScore {
     score: u8,
@@ -115,6 +122,13 @@ impl AbilityScore {
         &self.default_roll_type
     }
 
+    // the distribution of a score rolled as the highest num_kept of num_dice d6s,
+    // so the standard 4d6 drop lowest is rolled_score_rv(4, 3).
+    pub fn rolled_score_rv<P: RVProb>(num_dice: u32, num_kept: u32) -> Result<VecRandVar<P>, RVError> {
+        let d6: VecRandVar<P> = VecRandVar::new_dice(6)?;
+        d6.keep_highest(num_dice, num_kept)
+    }
+
     pub fn get_save_rv<P: RVProb>(&self, prof: isize, save_mod: D20RollType) -> VecRandVar<P> {
         let mut bonus = (self.get_mod() + self.save_bonus) as isize;
         if self.prof_save {
@@ -227,6 +241,10 @@ impl ForceSave {
 
 #[cfg(test)]
 mod tests {
+    use num::Rational64;
+    use rand_var::rand_var::RandVar;
+    use rand_var::vec_rand_var::VRV64;
+
     use super::*;
 
     #[test]
@@ -273,4 +291,17 @@ mod tests {
         let other_scores = AbilityScores::new(16, 12, 16, 8, 13, 10);
         assert_eq!(scores, other_scores);
     }
+
+    #[test]
+    fn rolled_score_test() {
+        let four_d6_drop_lowest: VRV64 = AbilityScore::rolled_score_rv(4, 3).unwrap();
+        assert_eq!(3, four_d6_drop_lowest.lower_bound());
+        assert_eq!(18, four_d6_drop_lowest.upper_bound());
+        assert_eq!(Rational64::new(1, 1296), four_d6_drop_lowest.pdf(3));
+        assert_eq!(Rational64::new(21, 1296), four_d6_drop_lowest.pdf(18));
+        assert_eq!(Rational64::new(15869, 1296), four_d6_drop_lowest.expected_value());
+
+        let three_d6: VRV64 = AbilityScore::rolled_score_rv(3, 3).unwrap();
+        assert_eq!(VecRandVar::new_dice(6).unwrap().multiple(3), three_d6);
+    }
 }
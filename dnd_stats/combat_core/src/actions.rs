@@ -36,6 +36,8 @@ pub enum CombatAction<A, DE> {
     Attack(A),
     SelfHeal(DE),
     GainResource(ResourceName, usize),
+    GainTempHP(usize),
+    HealFromPool(ResourceName), // heals as much as is needed (or is left in the pool)
     ApplyBasicCondition(ConditionName),
     ApplyComplexCondition(ConditionName, Condition),
     CastSpell,
@@ -102,6 +104,7 @@ pub enum ActionName {
     FavoredFoeUse,
     CastSpell(SpellName),
     HasteAction,
+    LayOnHands,
 }
 
 pub type ActionBuilder<A, DE> = HashMap<ActionName, CombatOption<A, DE>>;
@@ -1,23 +1,22 @@
-use std::cmp;
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
 
 use num::{BigRational, Rational64};
 
 use rand_var::map_rand_var::MapRandVar;
 use rand_var::rand_var::prob_type::RVProb;
-use rand_var::rand_var::sequential;
-use rand_var::rand_var::sequential::{Pair, Seq, SeqIter};
+use rand_var::rand_var::RandVar;
+use rand_var::rand_var::sequential::{Nested, Pair, Seq};
 use rand_var::vec_rand_var::VecRandVar;
 
 use crate::{CCError, D20RollType};
 use crate::combat_event::CombatEvent;
 use crate::conditions::AttackDistance;
-use crate::damage::{DamageFeature, DamageTerm, DamageType};
+use crate::damage::{DamageDefenses, DamageFeature, DamageTerm};
 
 pub mod basic_attack;
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Seq, Nested)]
 pub enum AttackResult {
     Miss,
     Hit,
@@ -61,26 +60,6 @@ impl Display for AttackResult {
     }
 }
 
-impl Seq for AttackResult {
-    // I'm sure there's a better way to do this, but idk
-    fn gen_seq(&self, other: &Self) -> SeqIter<Self> {
-        let first = *cmp::min(self, other);
-        let second = *cmp::max(self, other);
-        let arr = [AttackResult::Miss, AttackResult::Hit, AttackResult::Crit];
-        let iter= arr.iter().filter(|ar| (*ar >= &first) && (*ar <= &second));
-        let items: BTreeSet<AttackResult> = iter.copied().collect();
-        SeqIter { items }
-    }
-
-    fn always_convex() -> bool {
-        true
-    }
-
-    fn convex_bounds(iter: SeqIter<Self>) -> Option<(Self, Self)> {
-        sequential::always_convex_bounds(iter)
-    }
-}
-
 pub type AccMRV<P> = MapRandVar<RollPair, P>;
 pub type AccMRV64 = MapRandVar<RollPair, Rational64>;
 pub type AccMRVBig = MapRandVar<RollPair, BigRational>;
@@ -89,6 +68,14 @@ pub type ArMRV<P> = MapRandVar<AttackResult, P>;
 pub type ArMRV64 = MapRandVar<AttackResult, Rational64>;
 pub type ArMRVBig = MapRandVar<AttackResult, BigRational>;
 
+// the number of attacks that hit (or crit), assuming they're independent.
+pub fn hit_count_rv<P: RVProb>(ar_rvs: &[ArMRV<P>]) -> Result<VecRandVar<P>, CCError> {
+    let hit_chances: Vec<P> = ar_rvs.iter()
+        .map(|ar_rv| ar_rv.pdf(AttackResult::Hit) + ar_rv.pdf(AttackResult::Crit))
+        .collect();
+    Ok(VecRandVar::new_poisson_binomial(&hit_chances)?)
+}
+
 pub type AoMRV<P> = MapRandVar<Pair<AttackResult, isize>, P>;
 pub type AoMRV64 = MapRandVar<Pair<AttackResult, isize>, Rational64>;
 pub type AoMRVBig = MapRandVar<Pair<AttackResult, isize>, BigRational>;
@@ -127,9 +114,9 @@ impl<P: RVProb> AtkDmgMap<P> {
 }
 
 pub trait Attack : Debug {
-    fn get_miss_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError>;
-    fn get_hit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError>;
-    fn get_crit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError>;
+    fn get_miss_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError>;
+    fn get_hit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError>;
+    fn get_crit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError>;
 
     fn get_acc_rv<P: RVProb>(&self, hit_type: D20RollType) -> Result<AccMRV<P>, CCError>;
 
@@ -138,20 +125,22 @@ pub trait Attack : Debug {
     fn get_atk_range(&self) -> AttackDistance;
     fn get_crit_lb(&self) -> isize;
     fn get_hit_bonus(&self) -> isize;
+    // magical attacks get past things like heavy armor master
+    fn is_magical(&self) -> bool;
 
-    fn get_ar_dmg<P: RVProb>(&self, ar: AttackResult, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+    fn get_ar_dmg<P: RVProb>(&self, ar: AttackResult, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
         match ar {
-            AttackResult::Miss => self.get_miss_dmg(resistances, bonus_dmg, dmg_feats),
-            AttackResult::Hit => self.get_hit_dmg(resistances, bonus_dmg, dmg_feats),
-            AttackResult::Crit => self.get_crit_dmg(resistances, bonus_dmg, dmg_feats),
+            AttackResult::Miss => self.get_miss_dmg(defenses, bonus_dmg, dmg_feats),
+            AttackResult::Hit => self.get_hit_dmg(defenses, bonus_dmg, dmg_feats),
+            AttackResult::Crit => self.get_crit_dmg(defenses, bonus_dmg, dmg_feats),
         }
     }
 
-    fn get_dmg_map<P: RVProb>(&self, resistances: &HashSet<DamageType>) -> Result<AtkDmgMap<P>, CCError> {
+    fn get_dmg_map<P: RVProb>(&self, defenses: &DamageDefenses) -> Result<AtkDmgMap<P>, CCError> {
         Ok(AtkDmgMap::new(
-            self.get_miss_dmg(resistances, vec!(), HashSet::new())?,
-            self.get_hit_dmg(resistances, vec!(), HashSet::new())?,
-            self.get_crit_dmg(resistances, vec!(), HashSet::new())?
+            self.get_miss_dmg(defenses, vec!(), HashSet::new())?,
+            self.get_hit_dmg(defenses, vec!(), HashSet::new())?,
+            self.get_crit_dmg(defenses, vec!(), HashSet::new())?
         ))
     }
 
@@ -165,15 +154,62 @@ pub trait Attack : Debug {
         Ok(ar_rv.map_keys(|ar| ar.into()))
     }
 
-    fn get_dmg_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, resistances: &HashSet<DamageType>) -> Result<VecRandVar<P>, CCError> {
+    fn get_dmg_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, defenses: &DamageDefenses) -> Result<VecRandVar<P>, CCError> {
         let attack_result_rv = self.get_ar_rv(hit_type, target_ac)?;
-        let dmg_map = self.get_dmg_map(resistances)?;
+        let dmg_map = self.get_dmg_map(defenses)?;
         Ok(attack_result_rv.consolidate(&dmg_map.into_ar_map())?.into())
     }
 
-    fn get_ao_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, resistances: &HashSet<DamageType>) -> Result<AoMRV<P>, CCError> {
+    fn get_ao_rv<P: RVProb>(&self, hit_type: D20RollType, target_ac: isize, defenses: &DamageDefenses) -> Result<AoMRV<P>, CCError> {
         let attack_result_rv = self.get_ar_rv(hit_type, target_ac)?;
-        let dmg_map = self.get_dmg_map(resistances)?;
+        let dmg_map = self.get_dmg_map(defenses)?;
         Ok(attack_result_rv.projection(&dmg_map.into_ar_map())?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use num::Rational64;
+
+    use rand_var::map_rand_var::MapRandVar;
+    use rand_var::num_rand_var::NumRandVar;
+    use rand_var::rand_var::RandVar;
+
+    use rand_var::rand_var::sequential::{FlatCmp, Nested, Pair, Seq};
+
+    use crate::attack::{ArMRV64, AttackResult, hit_count_rv};
+
+    fn ar_rv(miss: i64, hit: i64, crit: i64) -> ArMRV64 {
+        let mut pdf = BTreeMap::new();
+        pdf.insert(AttackResult::Miss, Rational64::new(miss, 20));
+        pdf.insert(AttackResult::Hit, Rational64::new(hit, 20));
+        pdf.insert(AttackResult::Crit, Rational64::new(crit, 20));
+        MapRandVar::from_map(pdf).unwrap()
+    }
+
+    #[test]
+    fn hit_count_test() {
+        let ar_rvs = vec!(ar_rv(10, 9, 1), ar_rv(10, 9, 1), ar_rv(15, 4, 1));
+        let hits = hit_count_rv(&ar_rvs).unwrap();
+        assert_eq!(0, hits.lower_bound());
+        assert_eq!(3, hits.upper_bound());
+        assert_eq!(Rational64::new(3, 16), hits.pdf(0));
+        assert_eq!(Rational64::new(1, 16), hits.pdf(3));
+        assert_eq!(Rational64::new(5, 4), hits.expected_value());
+    }
+
+    #[test]
+    fn derived_seq_test() {
+        let all: Vec<AttackResult> = AttackResult::Crit.gen_seq(&AttackResult::Miss).collect();
+        assert_eq!(vec!(AttackResult::Miss, AttackResult::Hit, AttackResult::Crit), all);
+        let upper: Vec<AttackResult> = AttackResult::Hit.gen_seq(&AttackResult::Crit).collect();
+        assert_eq!(vec!(AttackResult::Hit, AttackResult::Crit), upper);
+        assert!(AttackResult::always_convex());
+
+        let best = Pair(AttackResult::Hit, AttackResult::Miss);
+        assert_eq!(AttackResult::Hit, best.flat_cmp(FlatCmp::Max));
+        assert_eq!(AttackResult::Miss, best.flat_cmp(FlatCmp::Min));
+    }
+}
@@ -7,7 +7,7 @@ use rand_var::rand_var::sequential::Pair;
 use crate::{CCError, D20RollType, D20Type};
 use crate::attack::{AccMRV, AtkDmgMap, Attack};
 use crate::conditions::AttackDistance;
-use crate::damage::{BasicDamageManager, DamageDice, DamageFeature, DamageManager, DamageTerm, DamageType, ExtendedDamageDice, ExtendedDamageType};
+use crate::damage::{BasicDamageManager, DamageDice, DamageDefenses, DamageFeature, DamageManager, DamageTerm, DamageType, ExtendedDamageDice, ExtendedDamageType};
 use crate::damage::dice_expr::DiceExprTerm;
 
 #[derive(Debug, Clone)]
@@ -15,6 +15,7 @@ pub struct BasicAttack {
     damage: BasicDamageManager,
     hit_bonus: isize,
     crit_lb: isize,
+    magical: bool,
 }
 
 impl BasicAttack {
@@ -28,6 +29,7 @@ impl BasicAttack {
             damage,
             hit_bonus,
             crit_lb: 20,
+            magical: false,
         }
     }
 
@@ -36,6 +38,7 @@ impl BasicAttack {
             damage,
             hit_bonus,
             crit_lb,
+            magical: false,
         }
     }
 
@@ -45,23 +48,27 @@ impl BasicAttack {
         }
     }
 
+    pub fn set_magical(&mut self, magical: bool) {
+        self.magical = magical;
+    }
+
     pub fn get_damage(&self) -> &BasicDamageManager {
         &self.damage
     }
 }
 
 impl Attack for BasicAttack {
-    fn get_miss_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.damage.get_miss_dmg(resistances, bonus_dmg, dmg_feats)?)
+    fn get_miss_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.damage.get_miss_dmg(defenses, bonus_dmg, dmg_feats)?)
     }
 
     // TODO: also add a possible list of damage features ? or just specific ones...
-    fn get_hit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.damage.get_base_dmg(resistances, bonus_dmg, dmg_feats)?)
+    fn get_hit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.damage.get_base_dmg(defenses, bonus_dmg, dmg_feats)?)
     }
 
-    fn get_crit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.damage.get_crit_dmg(resistances, bonus_dmg, dmg_feats)?)
+    fn get_crit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, bonus_dmg: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.damage.get_crit_dmg(defenses, bonus_dmg, dmg_feats)?)
     }
 
     fn get_acc_rv<P: RVProb>(&self, hit_type: D20RollType) -> Result<AccMRV<P>, CCError> {
@@ -82,7 +89,11 @@ impl Attack for BasicAttack {
         self.hit_bonus
     }
 
-    fn get_dmg_map<P: RVProb>(&self, resistances: &HashSet<DamageType>) -> Result<AtkDmgMap<P>, CCError> {
-        Ok(self.damage.get_attack_dmg_map(resistances)?)
+    fn is_magical(&self) -> bool {
+        self.magical
+    }
+
+    fn get_dmg_map<P: RVProb>(&self, defenses: &DamageDefenses) -> Result<AtkDmgMap<P>, CCError> {
+        Ok(self.damage.get_attack_dmg_map(defenses)?)
     }
 }
@@ -3,7 +3,7 @@ use crate::actions::ActionName;
 use crate::attack::AttackResult;
 use crate::BinaryOutcome;
 use crate::conditions::ConditionName;
-use crate::health::Health;
+use crate::health::{DeathSaveResult, Health};
 use crate::participant::ParticipantId;
 use crate::resources::RefreshTiming;
 use crate::skills::{ContestResult, SkillName};
@@ -80,6 +80,7 @@ pub enum CombatEvent {
     SkCR(ContestResult),
     ForceSave(ParticipantId, ParticipantId, Ability),
     SaveResult(BinaryOutcome),
+    DeathSave(DeathSaveResult),
 }
 
 impl From<AttackResult> for CombatEvent {
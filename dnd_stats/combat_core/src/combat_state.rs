@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use crate::combat_event::{CombatEvent, CombatTiming};
 use crate::combat_state::combat_log::CombatLog;
 use crate::conditions::{ConditionLifetime, ConditionManager, CondUndoEffect};
-use crate::health::Health;
+use crate::health::{DeathSaves, Health};
 use crate::participant::ParticipantId;
 use crate::resources::ResourceManager;
 use crate::transposition::Transposition;
@@ -16,6 +16,7 @@ pub struct CombatState {
     resources: Vec<ResourceManager>,
     conditions: Vec<ConditionManager>,
     healthiness: Vec<Health>,
+    death_saves: Vec<DeathSaves>,
     deaths: HashSet<ParticipantId>,
     last_combat_timing: Option<CombatTiming>,
 }
@@ -23,11 +24,13 @@ pub struct CombatState {
 impl CombatState {
     pub fn new(resources: Vec<ResourceManager>, conditions: Vec<ConditionManager>) -> Self {
         let health = vec![Health::Healthy; resources.len()];
+        let death_saves = vec![DeathSaves::new(); resources.len()];
         Self {
             logs: CombatLog::new(),
             resources,
             conditions,
             healthiness: health,
+            death_saves,
             deaths: HashSet::new(),
             last_combat_timing: None,
         }
@@ -64,15 +67,35 @@ impl CombatState {
         if h == Health::Dead {
             self.deaths.insert(pid);
         }
+        if h < Health::ZeroHP {
+            // getting any hp back resets your death saves
+            self.death_saves[pid.0] = DeathSaves::new();
+        }
         self.push(CombatEvent::HP(pid, h));
     }
 
+    pub fn get_death_saves(&self, pid: ParticipantId) -> &DeathSaves {
+        self.death_saves.get(pid.0).unwrap()
+    }
+
+    pub fn add_death_save_success(&mut self, pid: ParticipantId) {
+        self.death_saves[pid.0].add_success();
+    }
+
+    pub fn add_death_save_failures(&mut self, pid: ParticipantId, count: u8) {
+        self.death_saves[pid.0].add_failures(count);
+        if self.death_saves[pid.0].is_dead() {
+            self.set_health(pid, Health::Dead);
+        }
+    }
+
     pub fn into_child(self) -> Self {
         Self {
             logs: self.logs.into_child(),
             resources: self.resources,
             conditions: self.conditions,
             healthiness: self.healthiness,
+            death_saves: self.death_saves,
             deaths: self.deaths,
             last_combat_timing: self.last_combat_timing,
         }
@@ -110,7 +133,9 @@ impl CombatState {
         for cue in cues {
             match cue {
                 CondUndoEffect::SetResourceLock(rn, lock) => {
-                    self.get_rm_mut(pid).set_res_lock(*rn, *lock);
+                    // other conditions might still be setting the same lock
+                    let lock = self.get_cm(pid).get_resource_lock(*rn).unwrap_or(*lock);
+                    self.get_rm_mut(pid).set_res_lock(*rn, lock);
                 }
             }
         }
@@ -125,7 +150,7 @@ impl Transposition for CombatState {
                     if self.healthiness == other.healthiness {
                         if self.deaths == other.deaths {
                             if self.last_combat_timing == other.last_combat_timing {
-                                return true;
+                                return self.death_saves == other.death_saves;
                             }
                         }
                     }
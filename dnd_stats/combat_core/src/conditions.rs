@@ -5,7 +5,7 @@ use crate::{CCError, D20RollType};
 use crate::ability_scores::Ability;
 use crate::actions::ActionType;
 use crate::combat_event::CombatTiming;
-use crate::damage::{DamageFeature, DamageTerm};
+use crate::damage::{DamageFeature, DamageReduction, DamageTerm};
 use crate::participant::ParticipantId;
 use crate::resources::ResourceName;
 
@@ -20,6 +20,19 @@ pub enum ConditionName {
     HasteLethargy,
     CastBASpell,
     CastActionSpell,
+    HeavyArmorMaster,
+    Blinded,
+    Charmed,
+    Frightened,
+    Grappled,
+    Incapacitated,
+    Paralyzed,
+    Petrified,
+    Poisoned,
+    Restrained,
+    Stunned,
+    Unconscious,
+    Exhaustion(u8),
 }
 
 impl ConditionName {
@@ -42,9 +55,79 @@ impl ConditionName {
                     lifetimes: vec!(ConditionLifetime::FailConcSave)
                 })
             },
-            _ => Err(CCError::UnknownCondition)
+            ConditionName::Blinded => {
+                Ok(Condition::permanent(vec!(
+                    ConditionEffect::AttackerMod(AttackDistance::Any, D20RollType::Disadvantage),
+                    ConditionEffect::AtkTargetedMod(AttackDistance::Any, D20RollType::Advantage),
+                )))
+            },
+            ConditionName::Charmed => {
+                // can't attack the charmer, but nothing targets a specific creature yet
+                Ok(Condition::permanent(vec!()))
+            },
+            ConditionName::Frightened | ConditionName::Poisoned => {
+                // frightened assumes the source is always in sight
+                let mut effects = vec!(ConditionEffect::AttackerMod(AttackDistance::Any, D20RollType::Disadvantage));
+                effects.extend(Self::check_disadvantage());
+                Ok(Condition::permanent(effects))
+            },
+            ConditionName::Grappled => Ok(Condition::permanent(vec!(Self::speed_zero()))),
+            ConditionName::Incapacitated => Ok(Condition::permanent(vec!(ConditionEffect::Incapacitated))),
+            ConditionName::Paralyzed | ConditionName::Unconscious => {
+                // unconscious creatures also fall prone, that's left to whoever applies this
+                let mut effects = Self::helpless_effects();
+                effects.push(ConditionEffect::AutoCritWithin5Ft);
+                Ok(Condition::permanent(effects))
+            },
+            ConditionName::Petrified => {
+                let mut effects = Self::helpless_effects();
+                effects.push(ConditionEffect::ResistAllDmg);
+                Ok(Condition::permanent(effects))
+            },
+            ConditionName::Restrained => {
+                Ok(Condition::permanent(vec!(
+                    Self::speed_zero(),
+                    ConditionEffect::AttackerMod(AttackDistance::Any, D20RollType::Disadvantage),
+                    ConditionEffect::AtkTargetedMod(AttackDistance::Any, D20RollType::Advantage),
+                    ConditionEffect::SaveMod(Ability::DEX, D20RollType::Disadvantage),
+                )))
+            },
+            ConditionName::Stunned => Ok(Condition::permanent(Self::helpless_effects())),
+            // level 6 is death, which is up to whoever applies this. Halved speed (2) and halved hp max (4) aren't modeled
+            ConditionName::Exhaustion(level) if (1..=6).contains(level) => {
+                let mut effects = Self::check_disadvantage();
+                if *level >= 3 {
+                    effects.push(ConditionEffect::AttackerMod(AttackDistance::Any, D20RollType::Disadvantage));
+                    effects.extend(Ability::all().map(|ab| ConditionEffect::SaveMod(ab, D20RollType::Disadvantage)));
+                }
+                if *level >= 5 {
+                    effects.push(Self::speed_zero());
+                }
+                Ok(Condition::permanent(effects))
+            },
+            ConditionName::Exhaustion(level) => Err(CCError::InvalidExhaustion(*level)),
+            _ => Err(CCError::UnknownCondition(*self))
         }
     }
+
+    fn speed_zero() -> ConditionEffect {
+        ConditionEffect::SetResourceLock(ResourceName::Movement, true)
+    }
+
+    fn check_disadvantage() -> Vec<ConditionEffect> {
+        Ability::all().map(|ab| ConditionEffect::RollActionMod(RollAction::Skills, ab, D20RollType::Disadvantage)).to_vec()
+    }
+
+    // paralyzed, petrified, stunned and unconscious all share these
+    fn helpless_effects() -> Vec<ConditionEffect> {
+        vec!(
+            ConditionEffect::Incapacitated,
+            Self::speed_zero(),
+            ConditionEffect::AutoFailSave(Ability::STR),
+            ConditionEffect::AutoFailSave(Ability::DEX),
+            ConditionEffect::AtkTargetedMod(AttackDistance::Any, D20RollType::Advantage),
+        )
+    }
 }
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
@@ -76,6 +159,11 @@ pub enum ConditionEffect {
     ACBonus(isize),
     SaveMod(Ability, D20RollType),
     SetResourceLock(ResourceName, bool),
+    DmgReduction(DamageReduction), // ~ "damage you take from attacks is reduced by 3"
+    AutoFailSave(Ability), // ~ "you automatically fail STR saves"
+    Incapacitated, // no actions, bonus actions or reactions
+    AutoCritWithin5Ft, // ~ "hits against you from within 5 feet are crits"
+    ResistAllDmg, // ~ "you have resistance to all damage"
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -104,6 +192,13 @@ pub struct Condition {
 }
 
 impl Condition {
+    pub fn permanent(effects: Vec<ConditionEffect>) -> Self {
+        Self {
+            effects,
+            lifetimes: vec!(ConditionLifetime::Permanent),
+        }
+    }
+
     pub fn until_end_turn(pid: ParticipantId) -> Self {
         Self {
             effects: vec!(),
@@ -185,6 +280,14 @@ impl ConditionManager {
         self.conditions.get(cn).unwrap()
     }
 
+    // there's only ever one level of exhaustion at a time
+    pub fn get_exhaustion(&self) -> Option<u8> {
+        self.conditions.keys().find_map(|cn| match cn {
+            ConditionName::Exhaustion(level) => Some(*level),
+            _ => None,
+        })
+    }
+
     pub fn get_death_notices(&self) -> Vec<ParticipantId> {
         let mut v = Vec::new();
         for (cl, _) in self.by_lifetime.iter() {
@@ -276,6 +379,71 @@ impl ConditionManager {
         ac_boost
     }
 
+    pub fn get_dmg_reductions(&self) -> Vec<DamageReduction> {
+        let mut reductions = Vec::new();
+        for (_, cond) in &self.conditions {
+            for effect in &cond.effects {
+                if let ConditionEffect::DmgReduction(dr) = effect {
+                    reductions.push(*dr);
+                }
+            }
+        }
+        reductions
+    }
+
+    // the lock the remaining conditions put on a resource, if any of them do. Locks win,
+    // so e.g.: a grappled creature waking up from unconscious still can't move.
+    pub fn get_resource_lock(&self, rn: ResourceName) -> Option<bool> {
+        let mut res_lock = None;
+        for cond in self.conditions.values() {
+            for effect in &cond.effects {
+                if let ConditionEffect::SetResourceLock(cond_rn, lock) = effect {
+                    if *cond_rn == rn {
+                        res_lock = Some(*lock || res_lock.unwrap_or(false));
+                    }
+                }
+            }
+        }
+        res_lock
+    }
+
+    pub fn is_incapacitated(&self) -> bool {
+        for cond in self.conditions.values() {
+            for effect in &cond.effects {
+                if let ConditionEffect::Incapacitated = effect {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn resists_all_dmg(&self) -> bool {
+        self.conditions.values().any(|cond| cond.effects.contains(&ConditionEffect::ResistAllDmg))
+    }
+
+    pub fn auto_fails_save(&self, ability: Ability) -> bool {
+        for cond in self.conditions.values() {
+            for effect in &cond.effects {
+                if let ConditionEffect::AutoFailSave(ab) = effect {
+                    if ability == *ab {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    // turns hits into crits (paralyzed, unconscious). An attack that could be made from
+    // either range (reach, thrown) isn't known to be within 5 feet, so it doesn't count.
+    pub fn auto_crits(&self, dist: AttackDistance) -> bool {
+        if dist != AttackDistance::Within5Ft {
+            return false;
+        }
+        self.conditions.values().any(|cond| cond.effects.contains(&ConditionEffect::AutoCritWithin5Ft))
+    }
+
     pub fn get_save_mod(&self, ability: Ability) -> D20RollType {
         let mut save_mod = D20RollType::Normal;
         for (_, cond) in &self.conditions {
@@ -1,11 +1,13 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
 
 use dice_expr::{DiceExpr, DiceExprTerm};
+use rand_var::lazy_rv::{ExprId, LazyRV};
 use rand_var::num_rand_var::NumRandVar;
 use rand_var::vec_rand_var::VecRandVar;
 use rand_var::rand_var::RandVar;
 use rand_var::rand_var::prob_type::RVProb;
+use rand_var::RVError;
 
 use crate::attack::AtkDmgMap;
 use crate::CCError;
@@ -45,6 +47,46 @@ impl DamageDice {
             DamageDice::TwoD6 => VecRandVar::new_dice_reroll(6, 2).unwrap().multiple(2)
         }
     }
+
+    pub fn get_expr<P: RVProb>(&self, lazy: &mut LazyRV<P>, gwf: bool) -> ExprId {
+        if *self == DamageDice::TwoD6 {
+            let d6 = DamageDice::D6.get_expr(lazy, gwf);
+            lazy.multiple(d6, 2)
+        } else if gwf {
+            lazy.leaf(self.get_rv_gwf())
+        } else {
+            lazy.leaf(self.get_rv())
+        }
+    }
+
+    // explodes each die separately, see NumRandVar::explode
+    pub fn get_rv_explode<P: RVProb>(&self, max_depth: u32) -> Result<VecRandVar<P>, CCError> {
+        self.per_die_rv(|die| Ok(die.explode(max_depth)?.0))
+    }
+
+    // each die is rolled num_rerolls extra times, keeping the highest for that die.
+    // note this isn't savage attacker, which rerolls all the damage dice together.
+    pub fn get_rv_keep_max<P: RVProb>(&self, num_rerolls: u32) -> Result<VecRandVar<P>, CCError> {
+        self.per_die_rv(|die| Ok(die.reroll_keep_max(num_rerolls)))
+    }
+
+    // elemental adept treats 1s as 2s, the 2024 version of great weapon fighting treats 1s and 2s as 3s
+    pub fn get_rv_min_roll<P: RVProb>(&self, min_roll: isize) -> Result<VecRandVar<P>, CCError> {
+        self.per_die_rv(|die| die.cap_lb(min_roll))
+    }
+
+    fn per_die_rv<P, F>(&self, f: F) -> Result<VecRandVar<P>, CCError>
+    where
+        P: RVProb,
+        F: Fn(VecRandVar<P>) -> Result<VecRandVar<P>, RVError>
+    {
+        let rv = f(ExtendedDamageDice::get_single_die(*self).get_rv())?;
+        if *self == DamageDice::TwoD6 {
+            Ok(rv.try_multiple(2)?)
+        } else {
+            Ok(rv)
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -75,7 +117,7 @@ pub enum DamageFeature {
     DmgTypeConversion(DamageType),
 }
 
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash, PartialOrd, Ord)]
 pub enum DamageType {
     Acid,
     Bludgeoning,
@@ -92,6 +134,16 @@ pub enum DamageType {
     Thunder,
 }
 
+impl DamageType {
+    pub fn all() -> [DamageType; 13] {
+        [
+            DamageType::Acid, DamageType::Bludgeoning, DamageType::Cold, DamageType::Fire, DamageType::Force,
+            DamageType::Lightning, DamageType::Necrotic, DamageType::Piercing, DamageType::Poison,
+            DamageType::Psychic, DamageType::Radiant, DamageType::Slashing, DamageType::Thunder,
+        ]
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
 pub enum ExtendedDamageType {
     Basic(DamageType),
@@ -127,6 +179,153 @@ impl DamageTerm {
     }
 }
 
+// how a target takes damage, by type. Immunity wins outright, otherwise
+// resistance is applied before vulnerability (so having both rounds down).
+// reductions only last for a single hit, and they come off before any of that.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DamageDefenses {
+    pub resistances: HashSet<DamageType>,
+    pub immunities: HashSet<DamageType>,
+    pub vulnerabilities: HashSet<DamageType>,
+    pub reductions: Vec<DamageReduction>,
+}
+
+impl DamageDefenses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resistant(resistances: HashSet<DamageType>) -> Self {
+        Self {
+            resistances,
+            immunities: HashSet::new(),
+            vulnerabilities: HashSet::new(),
+            reductions: Vec::new(),
+        }
+    }
+
+    pub fn add_resistance(&mut self, dmg_type: DamageType) {
+        self.resistances.insert(dmg_type);
+    }
+
+    // resistance doesn't stack, so this is safe on top of any other resistances
+    pub fn resist_all(&mut self) {
+        self.resistances.extend(DamageType::all());
+    }
+
+    pub fn add_immunity(&mut self, dmg_type: DamageType) {
+        self.immunities.insert(dmg_type);
+    }
+
+    pub fn add_vulnerability(&mut self, dmg_type: DamageType) {
+        self.vulnerabilities.insert(dmg_type);
+    }
+
+    pub fn add_reduction(&mut self, dr: DamageReduction) {
+        self.reductions.push(dr);
+    }
+
+    pub fn is_resistant(&self, dmg_type: DamageType) -> bool {
+        self.resistances.contains(&dmg_type)
+    }
+
+    pub fn is_immune(&self, dmg_type: DamageType) -> bool {
+        self.immunities.contains(&dmg_type)
+    }
+
+    pub fn is_vulnerable(&self, dmg_type: DamageType) -> bool {
+        self.vulnerabilities.contains(&dmg_type)
+    }
+
+    pub fn apply<P: RVProb>(&self, lazy: &mut LazyRV<P>, dmg: ExprId, dmg_type: DamageType) -> ExprId {
+        if self.is_immune(dmg_type) {
+            return lazy.constant(0);
+        }
+        let mut dmg = dmg;
+        if self.is_resistant(dmg_type) {
+            dmg = lazy.half(dmg);
+        }
+        if self.is_vulnerable(dmg_type) {
+            dmg = lazy.scale(dmg, 2);
+        }
+        dmg
+    }
+}
+
+// reductions to the damage of a single hit, which happen before resistance and vulnerability
+// (heavy armor master, uncanny dodge, deflect missiles). Halving applies to every type,
+// the others only come off the types they match (each of them separately).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DamageReduction {
+    Flat(isize, ReducedTypes),
+    Half,
+    Dice(DamageDice, isize, ReducedTypes),
+}
+
+// which damage types a (non-halving) reduction applies to
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ReducedTypes {
+    All,
+    // bludgeoning, piercing and slashing from nonmagical attacks
+    NonmagicalBPS,
+}
+
+impl ReducedTypes {
+    pub fn matches(&self, dmg_type: DamageType) -> bool {
+        match self {
+            ReducedTypes::All => true,
+            ReducedTypes::NonmagicalBPS => matches!(dmg_type, DamageType::Bludgeoning | DamageType::Piercing | DamageType::Slashing),
+        }
+    }
+}
+
+impl DamageReduction {
+    pub fn reduces(&self, dmg_type: DamageType) -> bool {
+        match self {
+            DamageReduction::Flat(_, rt) => rt.matches(dmg_type),
+            DamageReduction::Half => true,
+            DamageReduction::Dice(_, _, rt) => rt.matches(dmg_type),
+        }
+    }
+
+    // whether a magical attack gets through this entirely
+    pub fn nonmagical_only(&self) -> bool {
+        match self {
+            DamageReduction::Flat(_, rt) => *rt == ReducedTypes::NonmagicalBPS,
+            DamageReduction::Half => false,
+            DamageReduction::Dice(_, _, rt) => *rt == ReducedTypes::NonmagicalBPS,
+        }
+    }
+
+    pub fn apply<P: RVProb>(&self, dmg: &VecRandVar<P>) -> Result<VecRandVar<P>, CCError> {
+        let reduced = match self {
+            DamageReduction::Flat(amount, _) => dmg.add_const(-amount),
+            DamageReduction::Half => dmg.half()?,
+            DamageReduction::Dice(dd, bonus, _) => dmg.minus_rv(&dd.get_rv::<P>().add_const(*bonus)),
+        };
+        // damage can't be reduced below 0
+        if reduced.upper_bound() <= 0 {
+            Ok(VecRandVar::new_constant(0)?)
+        } else {
+            Ok(reduced.cap_lb(0)?)
+        }
+    }
+
+    pub fn apply_lazy<P: RVProb>(&self, lazy: &mut LazyRV<P>, dmg: ExprId) -> ExprId {
+        let reduced = match self {
+            DamageReduction::Flat(amount, _) => lazy.add_const(dmg, -amount),
+            DamageReduction::Half => lazy.half(dmg),
+            DamageReduction::Dice(dd, bonus, _) => {
+                let die = dd.get_expr(lazy, false);
+                let soaked = lazy.add_const(die, *bonus);
+                let soaked = lazy.scale(soaked, -1);
+                lazy.add(dmg, soaked)
+            }
+        };
+        lazy.cap(reduced, Some(0), None)
+    }
+}
+
 pub type DamageExpression<DE> = HashMap<ExtendedDamageType, DE>;
 
 pub type BasicDamageManager = DamageManager<DiceExpression>;
@@ -238,8 +437,9 @@ impl<DE: DiceExpr + Clone> DamageManager<DE> {
         }
     }
 
-    fn get_total_dmg<P: RVProb>(&self, dmg_expr: &DamageExpression<DE>, resistances: &HashSet<DamageType>, double_dice: bool, mut extra_dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
-        let mut rv = VecRandVar::new_constant(0).unwrap();
+    // each part is a damage expression and whether its dice are doubled. All the damage of one type
+    // is added up before reductions and defenses, since those apply to the whole hit.
+    fn get_total_dmg<P: RVProb>(&self, parts: &[(&DamageExpression<DE>, bool)], defenses: &DamageDefenses, mut extra_dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
         extra_dmg_feats.extend(self.damage_features.iter());
         let mut dmg_convert: Option<DamageType> = None;
         for df in extra_dmg_feats.iter() {
@@ -247,73 +447,77 @@ impl<DE: DiceExpr + Clone> DamageManager<DE> {
                 dmg_convert = Some(*dt);
             }
         }
-        for (k, de) in dmg_expr.iter() {
-            let mut dice_rv = de.get_dice_rv(&extra_dmg_feats, self.weapon_die)?;
-            if double_dice {
-                dice_rv = dice_rv.multiple(2);
+        let mut lazy = LazyRV::new();
+        let mut by_type: BTreeMap<DamageType, Vec<ExprId>> = BTreeMap::new();
+        for (dmg_expr, double_dice) in parts {
+            for (k, de) in dmg_expr.iter() {
+                let mut dice = de.get_dice_expr(&mut lazy, &extra_dmg_feats, self.weapon_die)?;
+                if *double_dice {
+                    dice = lazy.multiple(dice, 2);
+                }
+                let typed_dmg = lazy.add_const(dice, de.get_const());
+                let dmg_type = dmg_convert.unwrap_or(self.get_dmg_type(k)?);
+                by_type.entry(dmg_type).or_default().push(typed_dmg);
             }
-            dice_rv = dice_rv.add_const(de.get_const());
-            let dmg_type = dmg_convert.unwrap_or(self.get_dmg_type(k)?);
-            if resistances.contains(&dmg_type) {
-                rv = rv.add_rv(&dice_rv.half().unwrap());
-            } else {
-                rv = rv.add_rv(&dice_rv);
+        }
+        let mut terms = Vec::with_capacity(by_type.len());
+        for (dmg_type, type_terms) in by_type.into_iter() {
+            let mut typed_dmg = lazy.sum(type_terms);
+            for dr in defenses.reductions.iter() {
+                if dr.reduces(dmg_type) {
+                    typed_dmg = dr.apply_lazy(&mut lazy, typed_dmg);
+                }
             }
+            terms.push(defenses.apply(&mut lazy, typed_dmg, dmg_type));
         }
+        let total = lazy.sum(terms);
         // damage is never negative
-        rv = rv.cap_lb(0).unwrap();
-        Ok(rv)
+        let capped = lazy.cap(total, Some(0), None);
+        Ok(lazy.eval(capped)?)
     }
 
-    pub fn get_base_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, dtv: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+    pub fn get_base_dmg<P: RVProb>(&self, defenses: &DamageDefenses, dtv: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
         if dtv.len() == 0 {
-            self.get_total_dmg(&self.base_dmg, resistances, false, dmg_feats)
+            self.get_total_dmg(&[(&self.base_dmg, false)], defenses, dmg_feats)
         } else {
             let mut base_dmg = self.base_dmg.clone();
             DamageManager::merge_dmg(&mut base_dmg, dtv);
-            self.get_total_dmg(&base_dmg, resistances, false, dmg_feats)
+            self.get_total_dmg(&[(&base_dmg, false)], defenses, dmg_feats)
         }
     }
 
-    pub fn get_crit_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, dtv: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+    pub fn get_crit_dmg<P: RVProb>(&self, defenses: &DamageDefenses, dtv: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+        // double base dice + base const, plus bonus crit dmg
         if dtv.len() == 0 {
-            // double base dice + base const
-            let mut rv = self.get_total_dmg(&self.base_dmg, resistances, true, dmg_feats.clone())?;
-            // bonus crit dmg
-            rv = rv.add_rv(&self.get_total_dmg(&self.bonus_crit_dmg, resistances, false, dmg_feats)?);
-            Ok(rv)
+            self.get_total_dmg(&[(&self.base_dmg, true), (&self.bonus_crit_dmg, false)], defenses, dmg_feats)
         } else {
             let mut base_dmg = self.base_dmg.clone();
             DamageManager::merge_dmg(&mut base_dmg, dtv);
-            // double base dice + base const
-            let mut rv = self.get_total_dmg(&base_dmg, resistances, true, dmg_feats.clone())?;
-            // bonus crit dmg
-            rv = rv.add_rv(&self.get_total_dmg(&self.bonus_crit_dmg, resistances, false, dmg_feats)?);
-            Ok(rv)
+            self.get_total_dmg(&[(&base_dmg, true), (&self.bonus_crit_dmg, false)], defenses, dmg_feats)
         }
     }
 
-    pub fn get_miss_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>, dtv: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
+    pub fn get_miss_dmg<P: RVProb>(&self, defenses: &DamageDefenses, dtv: Vec<DamageTerm>, dmg_feats: HashSet<DamageFeature>) -> Result<VecRandVar<P>, CCError> {
         if dtv.len() == 0 {
-            self.get_total_dmg(&self.miss_dmg, resistances, false, dmg_feats)
+            self.get_total_dmg(&[(&self.miss_dmg, false)], defenses, dmg_feats)
         } else {
             let mut miss_dmg = self.miss_dmg.clone();
             DamageManager::merge_dmg(&mut miss_dmg, dtv);
-            self.get_total_dmg(&miss_dmg, resistances, false, dmg_feats)
+            self.get_total_dmg(&[(&miss_dmg, false)], defenses, dmg_feats)
         }
     }
 
     // this is often easier for "half dmg on save" than building
     // an actual miss_dmg DamageExpression
-    pub fn get_half_base_dmg<P: RVProb>(&self, resistances: &HashSet<DamageType>) -> Result<VecRandVar<P>, CCError> {
-        Ok(self.get_base_dmg(resistances, vec!(), HashSet::new())?.half().unwrap())
+    pub fn get_half_base_dmg<P: RVProb>(&self, defenses: &DamageDefenses) -> Result<VecRandVar<P>, CCError> {
+        Ok(self.get_base_dmg(defenses, vec!(), HashSet::new())?.half().unwrap())
     }
 
-    pub fn get_attack_dmg_map<P: RVProb>(&self, resistances: &HashSet<DamageType>) -> Result<AtkDmgMap<P>, CCError> {
+    pub fn get_attack_dmg_map<P: RVProb>(&self, defenses: &DamageDefenses) -> Result<AtkDmgMap<P>, CCError> {
         let map = AtkDmgMap::new(
-            self.get_miss_dmg(resistances, vec!(), HashSet::new())?,
-            self.get_base_dmg(resistances, vec!(), HashSet::new())?,
-            self.get_crit_dmg(resistances, vec!(), HashSet::new())?
+            self.get_miss_dmg(defenses, vec!(), HashSet::new())?,
+            self.get_base_dmg(defenses, vec!(), HashSet::new())?,
+            self.get_crit_dmg(defenses, vec!(), HashSet::new())?
         );
         Ok(map)
     }
@@ -326,18 +530,25 @@ mod tests {
 
     use super::*;
 
+    fn doubled(rv: &VRV64) -> VRV64 {
+        let mut lazy = LazyRV::new();
+        let leaf = lazy.leaf(rv.clone());
+        let scaled = lazy.scale(leaf, 2);
+        lazy.eval(scaled).unwrap()
+    }
+
     #[test]
     fn test_simple_dmg() {
         let mut dmg: BasicDamageManager = DamageManager::new();
         dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Die(ExtendedDamageDice::Basic(DamageDice::D6)), ExtendedDamageType::Basic(DamageType::Bludgeoning)));
         dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(3), ExtendedDamageType::Basic(DamageType::Bludgeoning)));
-        let rv1: VRV64 = dmg.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let rv1: VRV64 = dmg.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
 
         let rv2: VRV64 = VecRandVar::new_dice(6).unwrap();
         let rv2 = rv2.add_const(3);
         assert_eq!(rv1, rv2);
 
-        let rv3: VRV64 = dmg.get_crit_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let rv3: VRV64 = dmg.get_crit_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
 
         let rv4: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(2);
         let rv4 = rv4.add_const(3);
@@ -353,32 +564,50 @@ mod tests {
         dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(5), ExtendedDamageType::WeaponDamage));
         let brutal_crit_dmg = DamageTerm::new(DiceExprTerm::Die(ExtendedDamageDice::SingleWeaponDie), ExtendedDamageType::WeaponDamage);
         dmg.add_bonus_crit_dmg(brutal_crit_dmg);
-        let rv1: VRV64 = dmg.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let rv1: VRV64 = dmg.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
 
         let d12: VRV64 = VecRandVar::new_dice(12).unwrap();
         let const_dmg = 5;
         let base_dmg = d12.add_const(const_dmg);
         assert_eq!(rv1, base_dmg);
 
-        let rv2: VRV64 = dmg.get_crit_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let rv2: VRV64 = dmg.get_crit_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         let crit_dmg = d12.multiple(3).add_const(const_dmg);
         assert_eq!(rv2, crit_dmg);
     }
 
+    #[test]
+    fn test_die_variants() {
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let ea: VRV64 = DamageDice::TwoD6.get_rv_min_roll(2).unwrap();
+        assert_eq!(d6.cap_lb(2).unwrap().multiple(2), ea);
+        assert_eq!(4, ea.lower_bound());
+
+        let keep_max: VRV64 = DamageDice::D8.get_rv_keep_max(1).unwrap();
+        assert_eq!(VecRandVar::new_dice_keep_max(8, 1).unwrap(), keep_max);
+        let keep_max_2d6: VRV64 = DamageDice::TwoD6.get_rv_keep_max(1).unwrap();
+        assert_eq!(VecRandVar::new_dice_keep_max(6, 1).unwrap().multiple(2), keep_max_2d6);
+        assert_ne!(d6.multiple(2).reroll_keep_max(1), keep_max_2d6);
+
+        let exploded: VRV64 = DamageDice::TwoD6.get_rv_explode(3).unwrap();
+        assert_eq!(d6.explode(3).unwrap().0.multiple(2), exploded);
+        assert_eq!(48, exploded.upper_bound());
+    }
+
     #[test]
     fn test_flame_strike() {
         let mut dmg: BasicDamageManager = DamageManager::new();
         dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Dice(4, DamageDice::D6.into()), DamageType::Fire.into()));
         dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Dice(4, DamageDice::D6.into()), DamageType::Radiant.into()));
-        let rv1: VRV64 = dmg.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
-        let rv2: VRV64 = dmg.get_half_base_dmg(&HashSet::new()).unwrap();
+        let rv1: VRV64 = dmg.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
+        let rv2: VRV64 = dmg.get_half_base_dmg(&DamageDefenses::new()).unwrap();
 
         let eight_d6: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(8);
         assert_eq!(rv1, eight_d6);
         let save_dmg = eight_d6.half().unwrap();
         assert_eq!(rv2, save_dmg);
 
-        let resist_fire = HashSet::from([DamageType::Fire]);
+        let resist_fire = DamageDefenses::resistant(HashSet::from([DamageType::Fire]));
 
         let rv3: VRV64 = dmg.get_base_dmg(&resist_fire, vec!(), HashSet::new()).unwrap();
         let four_d6: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(4);
@@ -390,19 +619,175 @@ mod tests {
         assert_eq!(rv4, resist_save_dmg);
     }
 
+    #[test]
+    fn test_immunity_vulnerability() {
+        let mut dmg: BasicDamageManager = DamageManager::new();
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Dice(2, DamageDice::D6.into()), DamageType::Fire.into()));
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(3), DamageType::Fire.into()));
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Die(DamageDice::D8.into()), DamageType::Piercing.into()));
+        let fire: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(2).add_const(3);
+        let piercing: VRV64 = VecRandVar::new_dice(8).unwrap();
+
+        let mut defenses = DamageDefenses::new();
+        defenses.add_immunity(DamageType::Fire);
+        let rv1: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(piercing, rv1);
+
+        let mut defenses = DamageDefenses::new();
+        defenses.add_vulnerability(DamageType::Fire);
+        let rv2: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(doubled(&fire).add_rv(&piercing), rv2);
+
+        // resistance goes first, so odd totals lose a point
+        defenses.add_resistance(DamageType::Fire);
+        let rv3: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(doubled(&fire.half().unwrap()).add_rv(&piercing), rv3);
+
+        // immunity beats everything else
+        defenses.add_immunity(DamageType::Fire);
+        let rv4: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(piercing, rv4);
+    }
+
+    #[test]
+    fn test_dmg_conversion_defenses() {
+        // e.g. planar warrior turns everything into force damage
+        let mut dmg: BasicDamageManager = DamageManager::new();
+        dmg.set_weapon(DamageDice::D8, DamageType::Piercing);
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Die(ExtendedDamageDice::WeaponDice), ExtendedDamageType::WeaponDamage));
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Die(DamageDice::D8.into()), DamageType::Fire.into()));
+        let force = HashSet::from([DamageFeature::DmgTypeConversion(DamageType::Force)]);
+        let two_d8: VRV64 = VecRandVar::new_dice(8).unwrap().multiple(2);
+
+        let mut defenses = DamageDefenses::new();
+        defenses.add_immunity(DamageType::Fire);
+        defenses.add_immunity(DamageType::Piercing);
+        let rv1: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VecRandVar::new_constant(0).unwrap(), rv1);
+        let rv2: VRV64 = dmg.get_base_dmg(&defenses, vec!(), force.clone()).unwrap();
+        assert_eq!(two_d8, rv2);
+
+        defenses.add_vulnerability(DamageType::Force);
+        let rv3: VRV64 = dmg.get_base_dmg(&defenses, vec!(), force).unwrap();
+        assert_eq!(doubled(&two_d8), rv3);
+    }
+
     #[test]
     fn test_save_fireball() {
         let mut dmg: BasicDamageManager = DamageManager::new();
         dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Dice(8, DamageDice::D6.into()), DamageType::Fire.into()));
-        let full_dmg: VRV64 = dmg.get_base_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+        let full_dmg: VRV64 = dmg.get_base_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
         assert_eq!(8, full_dmg.lower_bound());
         assert_eq!(48, full_dmg.upper_bound());
         assert_eq!(Rational64::new(28, 1), full_dmg.expected_value());
-        let half_dmg = dmg.get_half_base_dmg(&HashSet::new()).unwrap();
+        let half_dmg = dmg.get_half_base_dmg(&DamageDefenses::new()).unwrap();
         assert_eq!(4, half_dmg.lower_bound());
         assert_eq!(24, half_dmg.upper_bound());
         // note that the expected value of "half 8d6" is 13.75, NOT 14 (= 4 * EV(d6))!!
         // I thought this was wrong at first, but it is actually correct.
         assert_eq!(Rational64::new(55, 4), half_dmg.expected_value());
     }
+
+    #[test]
+    fn test_dmg_reduction() {
+        let d4: VRV64 = DamageDice::D4.get_rv();
+        let dmg = d4.add_const(2);
+        let reduced = DamageReduction::Flat(3, ReducedTypes::All).apply(&dmg).unwrap();
+        assert_eq!(0, reduced.lower_bound());
+        assert_eq!(3, reduced.upper_bound());
+        assert_eq!(Rational64::new(1, 4), reduced.pdf(0));
+
+        let halved = DamageReduction::Half.apply(&dmg).unwrap();
+        assert_eq!(dmg.half().unwrap(), halved);
+
+        // deflect missiles can soak up the whole hit
+        let d8: VRV64 = DamageDice::D8.get_rv();
+        let deflected = DamageReduction::Dice(DamageDice::D10, 3, ReducedTypes::All).apply(&d8.add_const(4)).unwrap();
+        assert_eq!(0, deflected.lower_bound());
+        assert_eq!(8, deflected.upper_bound());
+        assert_eq!(Rational64::new(11, 20), deflected.pdf(0));
+
+        let nothing_left = DamageReduction::Flat(10, ReducedTypes::All).apply(&dmg).unwrap();
+        assert_eq!(VRV64::new_constant(0).unwrap(), nothing_left);
+    }
+
+    #[test]
+    fn test_resist_all() {
+        let mut dmg: BasicDamageManager = DamageManager::new();
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(10), DamageType::Slashing.into()));
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(6), DamageType::Fire.into()));
+        // already resisting slashing doesn't make it a quarter
+        let mut defenses = DamageDefenses::resistant(HashSet::from([DamageType::Slashing]));
+        defenses.resist_all();
+        let rv: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(5 + 3).unwrap(), rv);
+    }
+
+    #[test]
+    fn test_reductions_before_defenses() {
+        let mut dmg: BasicDamageManager = DamageManager::new();
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(10), DamageType::Slashing.into()));
+        let mut defenses = DamageDefenses::resistant(HashSet::from([DamageType::Slashing]));
+        defenses.add_reduction(DamageReduction::Flat(3, ReducedTypes::All));
+        // (10 - 3) / 2, not 10 / 2 - 3
+        let rv: VRV64 = dmg.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(3).unwrap(), rv);
+
+        // heavy armor master only comes off the slashing, halving applies to all of it
+        dmg.set_weapon(DamageDice::D8, DamageType::Slashing);
+        let fire = DamageTerm::new(DiceExprTerm::Const(5), DamageType::Fire.into());
+        let mut defenses = DamageDefenses::resistant(HashSet::from([DamageType::Slashing]));
+        defenses.add_reduction(DamageReduction::Flat(3, ReducedTypes::NonmagicalBPS));
+        defenses.add_reduction(DamageReduction::Half);
+        let rv: VRV64 = dmg.get_base_dmg(&defenses, vec!(fire), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(1 + 2).unwrap(), rv);
+
+        // the crit dice are reduced along with the rest of the hit, not separately
+        let mut defenses = DamageDefenses::new();
+        defenses.add_reduction(DamageReduction::Dice(DamageDice::D10, 3, ReducedTypes::All));
+        let mut dmg: BasicDamageManager = DamageManager::new();
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Dice(1, DamageDice::D8.into()), DamageType::Piercing.into()));
+        dmg.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(4), DamageType::Piercing.into()));
+        let rv: VRV64 = dmg.get_crit_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        let d8: VRV64 = DamageDice::D8.get_rv();
+        let expected = DamageReduction::Dice(DamageDice::D10, 3, ReducedTypes::All).apply(&d8.multiple(2).add_const(4)).unwrap();
+        assert_eq!(expected, rv);
+    }
+
+    #[test]
+    fn test_reduction_types() {
+        let mut defenses = DamageDefenses::new();
+        defenses.add_reduction(DamageReduction::Flat(3, ReducedTypes::NonmagicalBPS));
+
+        // fire isn't reduced, even from a weapon
+        let mut flame_blade: BasicDamageManager = DamageManager::new();
+        flame_blade.set_weapon(DamageDice::D6, DamageType::Fire);
+        flame_blade.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(7), ExtendedDamageType::WeaponDamage));
+        let rv: VRV64 = flame_blade.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(7).unwrap(), rv);
+
+        let mut fire_bolt: BasicDamageManager = DamageManager::new();
+        fire_bolt.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(7), DamageType::Fire.into()));
+        let rv: VRV64 = fire_bolt.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(7).unwrap(), rv);
+
+        // but piercing is, even when it's converted from another type
+        let mut spear: BasicDamageManager = DamageManager::new();
+        spear.set_weapon(DamageDice::D6, DamageType::Piercing);
+        spear.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(7), ExtendedDamageType::WeaponDamage));
+        let rv: VRV64 = spear.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(4).unwrap(), rv);
+        let to_slashing = HashSet::from([DamageFeature::DmgTypeConversion(DamageType::Slashing)]);
+        let rv: VRV64 = flame_blade.get_base_dmg(&defenses, vec!(), to_slashing).unwrap();
+        assert_eq!(VRV64::new_constant(4).unwrap(), rv);
+
+        // only the slashing part of a flaming sword is reduced
+        let mut flame_tongue: BasicDamageManager = DamageManager::new();
+        flame_tongue.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(7), DamageType::Slashing.into()));
+        flame_tongue.add_base_dmg(DamageTerm::new(DiceExprTerm::Const(5), DamageType::Fire.into()));
+        let rv: VRV64 = flame_tongue.get_base_dmg(&defenses, vec!(), HashSet::new()).unwrap();
+        assert_eq!(VRV64::new_constant(4 + 5).unwrap(), rv);
+        assert!(DamageReduction::Flat(3, ReducedTypes::NonmagicalBPS).nonmagical_only());
+        assert!(!DamageReduction::Half.nonmagical_only());
+    }
 }
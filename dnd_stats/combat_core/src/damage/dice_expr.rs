@@ -1,9 +1,15 @@
 use std::collections::HashSet;
 use std::fmt::Debug;
+use std::str::FromStr;
 
+use serde::{Deserialize, Deserializer};
+use serde::de::Error;
+use rand_var::dice_notation::{DiceNotation, MAX_DICE, NotationTerm};
+use rand_var::lazy_rv::{ExprId, LazyRV};
 use rand_var::vec_rand_var::VecRandVar;
 use rand_var::num_rand_var::NumRandVar;
 use rand_var::rand_var::prob_type::RVProb;
+use rand_var::RVError;
 
 use crate::CCError;
 use crate::damage::{DamageDice, DamageFeature, ExtendedDamageDice};
@@ -15,7 +21,7 @@ pub enum DiceExprTerm {
     Const(isize),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DiceExpression {
     dice_terms: Vec<ExtendedDamageDice>,
     const_term: isize,
@@ -54,6 +60,67 @@ impl DiceExpression {
     }
 }
 
+// only plain sums of damage dice and constants are allowed here, e.g.: "2d6+1d4+3"
+impl FromStr for DiceExpression {
+    type Err = CCError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let notation = DiceNotation::parse(s).map_err(|err| match err {
+            RVError::ParseErr(pos, msg) => CCError::ParseErr(pos, msg),
+            other => CCError::RVE(other),
+        })?;
+        let mut de = DiceExpression::new();
+        // every die is its own term here, so the whole expression gets the per-term dice limit
+        let mut num_dice = 0;
+        for st in notation.get_terms().iter() {
+            match &st.term {
+                NotationTerm::Const(c) => {
+                    if st.negative {
+                        de.add_term(DiceExprTerm::Const(-c));
+                    } else {
+                        de.add_term(DiceExprTerm::Const(*c));
+                    }
+                },
+                NotationTerm::Dice { num, sides, modifiers, keep } => {
+                    if st.negative {
+                        return Err(CCError::ParseErr(st.pos, String::from("dice can't be subtracted")));
+                    }
+                    if !modifiers.is_empty() || keep.is_some() {
+                        return Err(CCError::ParseErr(st.pos, String::from("dice modifiers aren't supported here")));
+                    }
+                    let die = match sides {
+                        4 => DamageDice::D4,
+                        6 => DamageDice::D6,
+                        8 => DamageDice::D8,
+                        10 => DamageDice::D10,
+                        12 => DamageDice::D12,
+                        _ => return Err(CCError::ParseErr(st.pos, format!("d{} is not a damage die", sides))),
+                    };
+                    num_dice += *num;
+                    if num_dice > MAX_DICE {
+                        return Err(CCError::ParseErr(st.pos, format!("at most {} dice can be rolled at once", MAX_DICE)));
+                    }
+                    for _ in 0..*num {
+                        de.add_term(DiceExprTerm::Die(ExtendedDamageDice::Basic(die)));
+                    }
+                },
+            }
+        }
+        Ok(de)
+    }
+}
+
+// lets json files write damage as a string
+impl<'de> Deserialize<'de> for DiceExpression {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(|err| D::Error::custom(format!("invalid dice expression '{}': {:?}", s, err)))
+    }
+}
+
 impl From<DiceExprTerm> for DiceExpression {
     fn from(value: DiceExprTerm) -> Self {
         let mut de = DiceExpression::new();
@@ -73,17 +140,23 @@ impl From<(Vec<ExtendedDamageDice>, isize)> for DiceExpression {
 
 pub trait DiceExpr: Debug + From<DiceExprTerm> {
     fn add_term(&mut self, term: DiceExprTerm);
-    fn get_dice_rv<P: RVProb>(&self, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<VecRandVar<P>, CCError>;
+    fn get_dice_expr<P: RVProb>(&self, lazy: &mut LazyRV<P>, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<ExprId, CCError>;
     fn get_const(&self) -> isize;
 
+    fn get_dice_rv<P: RVProb>(&self, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<VecRandVar<P>, CCError> {
+        let mut lazy = LazyRV::new();
+        let id = self.get_dice_expr(&mut lazy, dmg_feats, weapon_dmg)?;
+        Ok(lazy.eval(id)?)
+    }
+
     fn get_base_dice_rv<P: RVProb>(&self) -> Result<VecRandVar<P>, CCError> {
         self.get_dice_rv(&HashSet::new(), None)
     }
 
     // healing is currently just negative damage
     fn get_heal_rv<P: RVProb> (&self) -> Result<VecRandVar<P>, CCError> {
-        let rv_base = self.get_base_dice_rv();
-        rv_base.map(|rv| rv.opposite_rv())
+        let rv_base: VecRandVar<P> = self.get_base_dice_rv()?;
+        Ok(rv_base.add_const(self.get_const()).opposite_rv())
     }
 }
 
@@ -100,21 +173,62 @@ impl DiceExpr for DiceExpression {
         };
     }
 
-    fn get_dice_rv<P: RVProb>(&self, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<VecRandVar<P>, CCError> {
+    fn get_dice_expr<P: RVProb>(&self, lazy: &mut LazyRV<P>, dmg_feats: &HashSet<DamageFeature>, weapon_dmg: Option<DamageDice>) -> Result<ExprId, CCError> {
         let gwf = dmg_feats.contains(&DamageFeature::GWF);
-        let mut rv: VecRandVar<P> = VecRandVar::new_constant(0).unwrap();
+        let mut terms = Vec::with_capacity(self.dice_terms.len());
         for ext_dice in self.dice_terms.iter() {
             let dice = DiceExpression::get_die(ext_dice, weapon_dmg)?;
-            if gwf {
-                rv = rv.add_rv(&dice.get_rv_gwf());
-            } else {
-                rv = rv.add_rv(&dice.get_rv());
-            }
+            terms.push(dice.get_expr(lazy, gwf));
         }
-        Ok(rv)
+        Ok(lazy.sum(terms))
     }
 
     fn get_const(&self) -> isize {
         self.const_term
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num::Rational64;
+    use rand_var::num_rand_var::NumRandVar;
+    use rand_var::rand_var::RandVar;
+    use rand_var::vec_rand_var::VRV64;
+
+    use crate::CCError;
+    use crate::damage::{DamageDice, ExtendedDamageDice};
+    use crate::damage::dice_expr::{DiceExpr, DiceExpression};
+
+    #[test]
+    fn parse_dice_expr() {
+        let de: DiceExpression = "2d6 + 1d4 + 3 - 1".parse().unwrap();
+        let d6 = ExtendedDamageDice::Basic(DamageDice::D6);
+        let d4 = ExtendedDamageDice::Basic(DamageDice::D4);
+        assert_eq!(DiceExpression::from((vec!(d6, d6, d4), 2)), de);
+        let rv: VRV64 = de.get_base_dice_rv().unwrap();
+        assert_eq!(Rational64::new(19, 2), rv.expected_value());
+
+        let de: DiceExpression = serde_json::from_str("\"1d8+4\"").unwrap();
+        assert_eq!(DiceExpression::from((vec!(ExtendedDamageDice::Basic(DamageDice::D8)), 4)), de);
+    }
+
+    #[test]
+    fn heal_rv() {
+        let de: DiceExpression = "1d4+3".parse().unwrap();
+        let heal: VRV64 = de.get_heal_rv().unwrap();
+        assert_eq!(-7, heal.lower_bound());
+        assert_eq!(-4, heal.upper_bound());
+    }
+
+    #[test]
+    fn parse_dice_expr_errors() {
+        assert!(matches!("1d8+1d20".parse::<DiceExpression>(), Err(CCError::ParseErr(4, _))));
+        assert!(matches!("1d8-1d4".parse::<DiceExpression>(), Err(CCError::ParseErr(4, _))));
+        assert!(matches!("4d6kh3".parse::<DiceExpression>(), Err(CCError::ParseErr(0, _))));
+        assert!(matches!("1d8+".parse::<DiceExpression>(), Err(CCError::ParseErr(4, _))));
+        assert!(serde_json::from_str::<DiceExpression>("\"1d7\"").is_err());
+        assert!(matches!("2147483647d6".parse::<DiceExpression>(), Err(CCError::ParseErr(0, _))));
+        assert!(matches!("600d6+600d6".parse::<DiceExpression>(), Err(CCError::ParseErr(6, _))));
+        assert!("1000d6".parse::<DiceExpression>().is_ok());
+    }
+}
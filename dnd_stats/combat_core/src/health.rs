@@ -1,8 +1,10 @@
+use rand_var::map_rand_var::MapRandVar;
 use rand_var::vec_rand_var::VecRandVar;
 use rand_var::rand_var::prob_type::RVProb;
 use rand_var::rand_var::RandVar;
+use rand_var::rand_var::sequential::Seq;
 
-#[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Seq)]
 pub enum Health {
     Healthy,
     Bloodied,
@@ -28,6 +30,26 @@ impl Health {
         }
     }
 
+    // like classify_hp, but dmg isn't capped at max_hp. Also gives the number of failed
+    // death saves this causes: taking damage while already at zero hp costs one (two on a crit),
+    // and dropping to zero with at least max_hp left over is instant death.
+    pub fn classify_dmg(dmg: &isize, old_health: Health, bloody_hp: isize, max_hp: isize, dead_at_zero: bool, crit: bool) -> (Health, u8) {
+        let health = Health::classify_hp(dmg, bloody_hp, max_hp, dead_at_zero);
+        if health != Health::ZeroHP {
+            (health, 0)
+        } else if *dmg >= 2 * max_hp {
+            (Health::Dead, 0)
+        } else if old_health == Health::ZeroHP && *dmg > max_hp {
+            if crit {
+                (Health::ZeroHP, 2)
+            } else {
+                (Health::ZeroHP, 1)
+            }
+        } else {
+            (Health::ZeroHP, 0)
+        }
+    }
+
     pub fn classify_hp(dmg: &isize, bloody_hp: isize, max_hp: isize, dead_at_zero: bool) -> Health {
         if dmg < &bloody_hp {
             Health::Healthy
@@ -43,6 +65,77 @@ impl Health {
     }
 }
 
+#[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Seq)]
+pub enum DeathSaveResult {
+    CritFail, // a natural 1 counts as two failures
+    Fail,
+    Pass,
+    CritPass, // a natural 20 gets you back up with 1 hp
+}
+
+impl DeathSaveResult {
+    pub fn from_roll(roll: isize) -> Self {
+        if roll <= 1 {
+            DeathSaveResult::CritFail
+        } else if roll < 10 {
+            DeathSaveResult::Fail
+        } else if roll < 20 {
+            DeathSaveResult::Pass
+        } else {
+            DeathSaveResult::CritPass
+        }
+    }
+
+    pub fn get_rv<P: RVProb>() -> MapRandVar<DeathSaveResult, P> {
+        let d20: VecRandVar<P> = VecRandVar::new_dice(20).unwrap();
+        d20.into_mrv().map_keys(DeathSaveResult::from_roll)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct DeathSaves {
+    successes: u8,
+    failures: u8,
+    stable: bool,
+}
+
+impl DeathSaves {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_successes(&self) -> u8 {
+        self.successes
+    }
+
+    pub fn get_failures(&self) -> u8 {
+        self.failures
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.stable
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.failures >= 3
+    }
+
+    pub fn add_success(&mut self) {
+        self.successes += 1;
+        if self.successes >= 3 {
+            self.successes = 0;
+            self.failures = 0;
+            self.stable = true;
+        }
+    }
+
+    // taking damage also means you aren't stable anymore
+    pub fn add_failures(&mut self, count: u8) {
+        self.stable = false;
+        self.failures += count;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum HitDice {
     D6,
@@ -70,3 +163,53 @@ impl HitDice {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num::Rational64;
+    use rand_var::rand_var::RandVar;
+
+    use super::*;
+
+    #[test]
+    fn death_save_rv_test() {
+        let rv: MapRandVar<DeathSaveResult, Rational64> = DeathSaveResult::get_rv();
+        assert_eq!(Rational64::new(1, 20), rv.pdf(DeathSaveResult::CritFail));
+        assert_eq!(Rational64::new(8, 20), rv.pdf(DeathSaveResult::Fail));
+        assert_eq!(Rational64::new(10, 20), rv.pdf(DeathSaveResult::Pass));
+        assert_eq!(Rational64::new(1, 20), rv.pdf(DeathSaveResult::CritPass));
+    }
+
+    #[test]
+    fn death_saves_test() {
+        let mut ds = DeathSaves::new();
+        ds.add_success();
+        ds.add_failures(2);
+        ds.add_success();
+        assert!(!ds.is_stable());
+        assert!(!ds.is_dead());
+        ds.add_success();
+        assert!(ds.is_stable());
+        assert_eq!(0, ds.get_failures());
+
+        ds.add_failures(2);
+        assert!(!ds.is_stable());
+        ds.add_failures(1);
+        assert!(ds.is_dead());
+    }
+
+    #[test]
+    fn classify_dmg_test() {
+        let max_hp = 20;
+        let bloody = Health::calc_bloodied(max_hp);
+        assert_eq!((Health::Bloodied, 0), Health::classify_dmg(&15, Health::Healthy, bloody, max_hp, false, false));
+        assert_eq!((Health::ZeroHP, 0), Health::classify_dmg(&39, Health::Healthy, bloody, max_hp, false, false));
+        // massive damage
+        assert_eq!((Health::Dead, 0), Health::classify_dmg(&40, Health::Healthy, bloody, max_hp, false, false));
+        // hit while down
+        assert_eq!((Health::ZeroHP, 0), Health::classify_dmg(&20, Health::ZeroHP, bloody, max_hp, false, false));
+        assert_eq!((Health::ZeroHP, 1), Health::classify_dmg(&21, Health::ZeroHP, bloody, max_hp, false, false));
+        assert_eq!((Health::ZeroHP, 2), Health::classify_dmg(&21, Health::ZeroHP, bloody, max_hp, false, true));
+        assert_eq!((Health::Dead, 0), Health::classify_dmg(&25, Health::Healthy, bloody, max_hp, true, false));
+    }
+}
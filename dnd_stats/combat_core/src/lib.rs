@@ -1,11 +1,19 @@
 use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::ops::{Add, AddAssign};
+use std::sync::Arc;
 
 use rand_var::RVError;
+use rand_var::num_rand_var::NumRandVar;
 use rand_var::rand_var::RandVar;
 use rand_var::rand_var::prob_type::RVProb;
+use rand_var::rand_var::sequential::Seq;
 use rand_var::vec_rand_var::VecRandVar;
 
+use crate::conditions::ConditionName;
+
 pub mod ability_scores;
 pub mod actions;
 pub mod attack;
@@ -30,12 +38,44 @@ pub enum CCError {
     PMNotCompiled,
     SMNotCompiled,
     SMPushAfterCompile,
-    UnknownCondition,
+    UnknownCondition(ConditionName),
+    InvalidExhaustion(u8),
     NoWeaponSet,
+    ParseErr(usize, String),
     RVE(RVError),
+    External(Arc<dyn Error + Send + Sync>), // errors from crates that depend on this one
     Other(String),
 }
 
+impl Display for CCError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CCError::ParticipantSizeErr => write!(f, "wrong number of participants"),
+            CCError::PMPushAfterCompile => write!(f, "participants can't be added after the participant manager is compiled"),
+            CCError::PMNotCompiled => write!(f, "the participant manager has to be compiled first"),
+            CCError::SMNotCompiled => write!(f, "the strategy manager has to be compiled first"),
+            CCError::SMPushAfterCompile => write!(f, "strategies can't be added after the strategy manager is compiled"),
+            CCError::UnknownCondition(cn) => write!(f, "{:?} has no default condition", cn),
+            CCError::InvalidExhaustion(level) => write!(f, "exhaustion level {} isn't between 1 and 6", level),
+            CCError::NoWeaponSet => write!(f, "weapon damage was used, but no weapon is set"),
+            CCError::ParseErr(pos, msg) => write!(f, "parse error at position {}: {}", pos, msg),
+            CCError::RVE(_) => write!(f, "random variable error"),
+            CCError::External(err) => write!(f, "{}", err),
+            CCError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for CCError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CCError::RVE(err) => Some(err),
+            CCError::External(err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
 impl From<RVError> for CCError {
     fn from(value: RVError) -> Self {
         CCError::RVE(value)
@@ -59,13 +99,13 @@ pub enum D20RollType {
 
 impl D20RollType {
     pub fn get_rv<P: RVProb>(&self, d20: &D20Type) -> VecRandVar<P> {
-        let rv = d20.get_rv();
+        // .unwrap() is fine here, since every roll type uses at least one d20.
         match self {
-            D20RollType::Disadvantage => rv.min_two_trials(),
-            D20RollType::FixedNormal => rv,
-            D20RollType::Normal => rv,
-            D20RollType::Advantage => rv.max_two_trials(),
-            D20RollType::SuperAdvantage => rv.max_three_trials(),
+            D20RollType::Disadvantage => d20.get_worst_of_rv(2).unwrap(),
+            D20RollType::FixedNormal => d20.get_rv(),
+            D20RollType::Normal => d20.get_rv(),
+            D20RollType::Advantage => d20.get_best_of_rv(2).unwrap(),
+            D20RollType::SuperAdvantage => d20.get_best_of_rv(3).unwrap(),
         }
     }
 
@@ -123,11 +163,20 @@ impl D20Type {
             D20Type::D20m10 => VecRandVar::new_dice(20).unwrap().cap_lb(10).unwrap(),
         }
     }
+
+    // roll num_rolls d20s and take the highest, i.e.: advantage to any depth
+    pub fn get_best_of_rv<P: RVProb>(&self, num_rolls: u32) -> Result<VecRandVar<P>, RVError> {
+        self.get_rv().kth_order_statistic(num_rolls, num_rolls)
+    }
+
+    pub fn get_worst_of_rv<P: RVProb>(&self, num_rolls: u32) -> Result<VecRandVar<P>, RVError> {
+        self.get_rv().kth_order_statistic(num_rolls, 1)
+    }
 }
 
 // I could just use a bool instead, but I want to a avoid
 // errors like "did true mean pass or fail in this context?"
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Seq)]
 pub enum BinaryOutcome {
     Pass,
     Fail,
@@ -135,9 +184,10 @@ pub enum BinaryOutcome {
 
 #[cfg(test)]
 mod tests {
-    use num::Rational64;
+    use num::{One, Rational64};
 
     use rand_var::rand_var::RandVar;
+    use rand_var::vec_rand_var::VRV64;
 
     use crate::{D20RollType, D20Type};
 
@@ -158,6 +208,19 @@ mod tests {
         assert_eq!(Rational64::new(10, 20), reliable_talent.pdf(10));
     }
 
+    #[test]
+    fn d20_best_of() {
+        let d20: VRV64 = D20Type::D20.get_rv();
+        assert_eq!(d20.max_two_trials(), D20RollType::Advantage.get_rv(&D20Type::D20));
+        assert_eq!(d20.min_two_trials(), D20RollType::Disadvantage.get_rv(&D20Type::D20));
+        assert_eq!(d20.max_three_trials(), D20RollType::SuperAdvantage.get_rv(&D20Type::D20));
+
+        let elven_accuracy_plus: VRV64 = D20Type::D20.get_best_of_rv(4).unwrap();
+        assert_eq!(Rational64::new(1, 160000), elven_accuracy_plus.pdf(1));
+        assert_eq!(Rational64::one() - Rational64::new(19*19*19*19, 160000), elven_accuracy_plus.pdf(20));
+        assert!(D20Type::D20.get_best_of_rv::<Rational64>(0).is_err());
+    }
+
     #[test]
     fn better_d20_roll() {
         assert_eq!(D20RollType::Advantage, D20RollType::Disadvantage.choose_better(&D20RollType::Advantage));
@@ -5,7 +5,7 @@ use crate::ability_scores::AbilityScores;
 use crate::actions::ActionManager;
 use crate::CCError;
 use crate::conditions::ConditionManager;
-use crate::damage::DamageType;
+use crate::damage::{DamageDefenses, DamageType};
 use crate::resources::ResourceManager;
 use crate::skills::SkillManager;
 use crate::spells::SpellManager;
@@ -15,13 +15,23 @@ pub trait Participant : Debug {
     fn get_ac(&self) -> isize;
     fn get_max_hp(&self) -> isize;
     fn get_prof(&self) -> isize;
-    fn get_resistances(&self) -> &HashSet<DamageType>;
+    fn get_dmg_defenses(&self) -> &DamageDefenses;
     fn get_ability_scores(&self) -> &AbilityScores;
     fn get_skill_manager(&self) -> &SkillManager;
     fn get_action_manager(&self) -> &ActionManager;
     fn get_resource_manager(&self) -> &ResourceManager;
     fn get_condition_manager(&self) -> &ConditionManager;
 
+    fn get_resistances(&self) -> &HashSet<DamageType> {
+        &self.get_dmg_defenses().resistances
+    }
+    fn get_immunities(&self) -> &HashSet<DamageType> {
+        &self.get_dmg_defenses().immunities
+    }
+    fn get_vulnerabilities(&self) -> &HashSet<DamageType> {
+        &self.get_dmg_defenses().vulnerabilities
+    }
+
     fn has_triggers(&self) -> bool {
         false
     }
@@ -68,7 +78,7 @@ pub struct ParticipantData {
     pub team: Team,
     pub ac: isize,
     pub max_hp: isize,
-    pub resistances: HashSet<DamageType>,
+    pub defenses: DamageDefenses,
 }
 
 impl From<&TeamMember> for ParticipantData {
@@ -77,7 +87,7 @@ impl From<&TeamMember> for ParticipantData {
             team: value.team,
             ac: value.participant.get_ac(),
             max_hp: value.participant.get_max_hp(),
-            resistances: value.participant.get_resistances().clone(),
+            defenses: value.participant.get_dmg_defenses().clone(),
         }
     }
 }
@@ -27,6 +27,8 @@ pub enum ResourceName {
     AN(ActionName),
     TN(TriggerName),
     SS(SpellSlot),
+    TempHP,
+    LayOnHands,
 }
 
 impl From<ActionType> for ResourceName {
@@ -243,6 +245,17 @@ impl ResourceManager {
         }
     }
 
+    // temp hp don't stack, you just keep the larger amount
+    pub fn add_temp_hp(&mut self, amount: usize) {
+        if self.get_current(ResourceName::TempHP) < amount {
+            self.add_temp(ResourceName::TempHP, Resource::new(ResourceCap::Hard(amount), ResourceCount::Count(amount)));
+        }
+    }
+
+    pub fn get_temp_hp(&self) -> usize {
+        self.get_current(ResourceName::TempHP).count().unwrap_or(0)
+    }
+
     pub fn set_res_lock(&mut self, rn: ResourceName, lock: bool) {
         self.perm_resources.get_mut(&rn).map(|res| res.set_lock(lock));
         self.temp_resources.get_mut(&rn).map(|res| res.set_lock(lock));
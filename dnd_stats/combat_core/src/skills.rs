@@ -3,6 +3,7 @@ use num::{FromPrimitive, Rational64};
 use rand_var::map_rand_var::MapRandVar;
 use rand_var::num_rand_var::NumRandVar;
 use rand_var::rand_var::prob_type::RVProb;
+use rand_var::rand_var::sequential::Seq;
 use rand_var::vec_rand_var::{VRV64, VecRandVar};
 
 use crate::{BinaryOutcome, D20RollType, D20Type};
@@ -161,7 +162,7 @@ impl SkillManager {
     }
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Seq)]
 pub enum ContestResult {
     InitiatorWins,
     DefenderWins,
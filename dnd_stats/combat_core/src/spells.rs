@@ -3,6 +3,7 @@ use crate::ability_scores::ForceSave;
 use crate::attack::basic_attack::BasicAttack;
 use crate::conditions::{Condition, ConditionName};
 use crate::damage::BasicDamageManager;
+use crate::damage::dice_expr::DiceExpression;
 
 // yes I could just use numbers, no I don't feel like it
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
@@ -21,10 +22,12 @@ pub enum SpellSlot {
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum SpellName {
+    CureWounds,
     FireBolt,
     Fireball,
     GreaterInvis,
     Haste,
+    HealingWord,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +35,7 @@ pub enum SpellEffect {
     SpellAttack(BasicAttack),
     SaveDamage(SaveDmgSpell),
     ApplyCondition(ConditionName, Condition),
+    Heal(DiceExpression),
 }
 
 #[derive(Debug, Clone)]
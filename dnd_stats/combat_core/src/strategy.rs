@@ -4,6 +4,7 @@ use crate::actions::{ActionName, ActionType};
 use crate::CCError;
 use crate::combat_state::CombatState;
 use crate::conditions::ConditionName;
+use crate::health::Health;
 use crate::movement::Square;
 use crate::participant::{Participant, ParticipantId, ParticipantManager, TeamMember};
 use crate::spells::SpellSlot;
@@ -24,6 +25,7 @@ pub mod firebolt_str;
 pub mod greater_invis_str;
 pub mod gwm_str;
 pub mod haste_str;
+pub mod heal_str;
 pub mod linear_str;
 pub mod planar_warrior_str;
 pub mod second_wind_str;
@@ -151,6 +153,24 @@ pub trait Strategy : Debug {
         }
         None
     }
+
+    // the ally (maybe me) that needs healing the most: downed allies first, then bloodied ones
+    fn get_heal_target(&self, state: &CombatState) -> Option<Target> {
+        let participants = self.get_participants();
+        let my_team = participants.get(self.get_my_pid().0).unwrap().team;
+        let mut target: Option<(Health, ParticipantId)> = None;
+        for (i, tm) in participants.iter().enumerate() {
+            let pid = ParticipantId(i);
+            let health = state.get_health(pid);
+            if tm.team != my_team || health == Health::Healthy || health == Health::Dead {
+                continue;
+            }
+            if target.is_none_or(|(best, _)| health > best) {
+                target = Some((health, pid));
+            }
+        }
+        target.map(|(_, pid)| Target::Participant(pid))
+    }
 }
 
 pub struct StrategyManager<'pm> {
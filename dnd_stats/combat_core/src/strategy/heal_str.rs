@@ -0,0 +1,74 @@
+use crate::actions::ActionName;
+use crate::combat_state::CombatState;
+use crate::conditions::ConditionName;
+use crate::participant::{ParticipantId, TeamMember};
+use crate::resources::{ResourceActionType, ResourceName};
+use crate::spells::{SpellName, SpellSlot};
+use crate::strategy::{StrategicAction, Strategy, StrategyBuilder, StrategyDecision};
+use crate::triggers::{TriggerInfo, TriggerResponse};
+
+pub struct HealStrBuilder;
+impl StrategyBuilder for HealStrBuilder {
+    fn build_strategy<'pm>(&self, participants: &'pm Vec<TeamMember>, me: ParticipantId) -> Box<dyn Strategy + 'pm> {
+        let str = HealStr {
+            participants,
+            my_pid: me,
+        };
+        Box::new(str)
+    }
+}
+
+// heals whoever needs it most, preferring healing word since it's only a bonus action
+#[derive(Debug)]
+pub struct HealStr<'pm> {
+    participants: &'pm Vec<TeamMember>,
+    my_pid: ParticipantId,
+}
+
+impl<'pm> HealStr<'pm> {
+    fn knows_action(&self, an: ActionName) -> bool {
+        self.get_me().get_action_manager().contains_key(&an)
+    }
+}
+
+impl<'pm> Strategy for HealStr<'pm> {
+    fn get_participants(&self) -> &Vec<TeamMember> {
+        self.participants
+    }
+
+    fn get_my_pid(&self) -> ParticipantId {
+        self.my_pid
+    }
+
+    fn choose_action(&self, state: &CombatState) -> StrategyDecision {
+        let target = self.get_heal_target(state);
+        if target.is_none() {
+            return StrategyDecision::DoNothing;
+        }
+        let me = self.get_my_pid();
+        let my_rm = state.get_rm(me);
+        let cm = state.get_cm(me);
+        let has_slot = my_rm.get_current(ResourceName::SS(SpellSlot::First)) > 0;
+        let has_ba = my_rm.get_current(ResourceName::RAT(ResourceActionType::BonusAction)) > 0;
+        let has_action = my_rm.get_current(ResourceName::RAT(ResourceActionType::Action)) > 0;
+
+        let healing_word = ActionName::CastSpell(SpellName::HealingWord);
+        if self.knows_action(healing_word) && has_ba && has_slot && !cm.has_condition(&ConditionName::CastActionSpell) {
+            return StrategicAction::new(healing_word, target, Some(SpellSlot::First)).into();
+        }
+        if has_action {
+            if self.knows_action(ActionName::LayOnHands) && my_rm.get_current(ResourceName::LayOnHands) > 0 {
+                return StrategicAction::targeted(ActionName::LayOnHands, target).into();
+            }
+            let cure_wounds = ActionName::CastSpell(SpellName::CureWounds);
+            if self.knows_action(cure_wounds) && has_slot && !cm.has_condition(&ConditionName::CastBASpell) {
+                return StrategicAction::new(cure_wounds, target, Some(SpellSlot::First)).into();
+            }
+        }
+        StrategyDecision::DoNothing
+    }
+
+    fn choose_triggers(&self, _: TriggerInfo, _: &CombatState) -> Vec<TriggerResponse> {
+        Vec::new()
+    }
+}
@@ -11,6 +11,7 @@ use crate::strategy::firebolt_str::FireBoltStrBuilder;
 use crate::strategy::greater_invis_str::GreaterInvisStrBuilder;
 use crate::strategy::gwm_str::GWMStrBldr;
 use crate::strategy::haste_str::HasteStrBuilder;
+use crate::strategy::heal_str::HealStrBuilder;
 use crate::strategy::linear_str::LinearStrategy;
 use crate::strategy::planar_warrior_str::PlanarWarriorStrBldr;
 use crate::strategy::second_wind_str::SecondWindStrBuilder;
@@ -53,6 +54,7 @@ pub enum StrategyBuilderName {
     GreatWeaponMasterSB(bool),
     SharpShooterSB(bool),
     HasteSB,
+    HealSB,
     PlanarWarriorSB,
     SecondWindSB,
     ShieldMasterSB,
@@ -74,6 +76,7 @@ impl StrategyBuilder for StrategyBuilderName {
             StrategyBuilderName::GreatWeaponMasterSB(use_gwm) => GWMStrBldr::new(*use_gwm).build_strategy(participants, me),
             StrategyBuilderName::SharpShooterSB(use_ss) => SharpShooterStrBldr::new(*use_ss).build_strategy(participants, me),
             StrategyBuilderName::HasteSB => HasteStrBuilder.build_strategy(participants, me),
+            StrategyBuilderName::HealSB => HealStrBuilder.build_strategy(participants, me),
             StrategyBuilderName::PlanarWarriorSB => PlanarWarriorStrBldr.build_strategy(participants, me),
             StrategyBuilderName::SecondWindSB => SecondWindStrBuilder.build_strategy(participants, me),
             StrategyBuilderName::ShieldMasterSB => ShieldMasterStrBuilder.build_strategy(participants, me),
@@ -1,8 +1,8 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::attack::AttackResult;
-use crate::conditions::{Condition, ConditionName};
-use crate::damage::DamageTerm;
+use crate::conditions::{AttackDistance, Condition, ConditionName};
+use crate::damage::{DamageReduction, DamageTerm};
 use crate::participant::ParticipantId;
 use crate::resources::ResourceName;
 
@@ -50,6 +50,7 @@ pub enum TriggerName {
     GWMBonusAtk,
     FavoredFoeKill,
     HasteLethargy,
+    UncannyDodge,
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +60,8 @@ pub enum TriggerAction {
     AddResource(ResourceName, usize),
     SetResourceLock(ResourceName, bool),
     GiveCondition(ConditionName, Condition),
+    GainTempHP(usize),
+    ReduceDamage(AttackDistance, DamageReduction), // only makes sense for WasHit
 }
 
 #[derive(Debug, Clone)]
@@ -3,8 +3,8 @@ use std::collections::BTreeMap;
 use combat_core::participant::ParticipantId;
 use combat_core::resources::ResourceName;
 use rand_var::map_rand_var::MapRandVar;
+use rand_var::rand_var::mixture::Mixture;
 use rand_var::rand_var::prob_type::RVProb;
-use rand_var::rand_var::RandVar;
 use rand_var::vec_rand_var::VecRandVar;
 
 use crate::combat_result_rv::prob_combat_result::ProbCombatResult;
@@ -34,21 +34,21 @@ impl<P: RVProb> CombatResultRV<P> {
     }
 
     pub fn get_dmg(&self, target: ParticipantId) -> VecRandVar<P> {
-        let dmg_rvs = self.states.iter().map(|pcs| pcs.get_dmg(target));
-        let mut pdf_map: BTreeMap<isize, P> = BTreeMap::new();
-        for (i, rv) in dmg_rvs.enumerate() {
-            let prob = self.get_pcr(i).get_prob();
-            for dmg in rv.get_keys() {
-                let dmg_prob = prob.clone() * rv.pdf(dmg);
-                if pdf_map.contains_key(&dmg) {
-                    let old_prob = pdf_map.get(&dmg).unwrap().clone();
-                    pdf_map.insert(dmg, old_prob + dmg_prob);
-                } else {
-                    pdf_map.insert(dmg, dmg_prob);
-                }
+        let mut mixture = Mixture::new();
+        for pcs in self.states.iter() {
+            mixture.add(pcs.get_prob().clone(), pcs.get_dmg(target)).unwrap();
+        }
+        mixture.build().unwrap()
+    }
+
+    pub fn get_death_prob(&self, pid: ParticipantId) -> P {
+        let mut total = P::zero();
+        for pcr in self.states.iter() {
+            if pcr.is_dead(pid) {
+                total = total + pcr.get_prob().clone();
             }
         }
-        MapRandVar::from_map(pdf_map).unwrap().into_vrv()
+        total
     }
 
     pub fn get_resource_rv(&self, pid: ParticipantId, rn: ResourceName) -> Result<MapRandVar<isize, P>, CSError> {
@@ -59,7 +59,7 @@ impl<P: RVProb> CombatResultRV<P> {
             if rm.has_resource(rn) {
                 let rc = rm.get_current(rn);
                 if rc.is_uncapped() {
-                    return Err(CSError::UncappedResource);
+                    return Err(CSError::UncappedResource(pid, rn));
                 } else {
                     let count = rc.count().unwrap() as isize;
                     if pdf_map.contains_key(&count) {
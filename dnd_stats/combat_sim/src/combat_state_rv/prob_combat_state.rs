@@ -1,13 +1,14 @@
+use std::cmp;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ptr;
 use combat_core::ability_scores::Ability;
 
 use combat_core::actions::{ActionName, ActionType};
-use combat_core::BinaryOutcome;
+use combat_core::{BinaryOutcome, CCError};
 use combat_core::combat_event::{CombatEvent, CombatTiming};
 use combat_core::combat_state::CombatState;
 use combat_core::conditions::{Condition, ConditionEffect, ConditionLifetime, ConditionManager, ConditionName};
-use combat_core::health::Health;
+use combat_core::health::{DeathSaves, Health};
 use combat_core::participant::{ParticipantId, ParticipantManager};
 use combat_core::resources::{RefreshTiming, ResourceManager, ResourceName};
 use combat_core::resources::resource_amounts::ResourceCount;
@@ -17,8 +18,10 @@ use combat_core::triggers::{TriggerAction, TriggerContext, TriggerInfo, TriggerT
 use rand_var::map_rand_var::MapRandVar;
 use rand_var::num_rand_var::NumRandVar;
 use rand_var::rand_var::RandVar;
+use rand_var::rand_var::approximation::Approximation;
 use rand_var::rand_var::prob_type::RVProb;
 use rand_var::rand_var::rv_partition::RVPartition;
+use rand_var::RVError;
 use rand_var::vec_rand_var::VecRandVar;
 
 use crate::combat_result_rv::prob_combat_result::ProbCombatResult;
@@ -113,30 +116,52 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
     }
 
     pub fn apply_complex_condition(&mut self, pid: ParticipantId, cn: ConditionName, cond: Condition) {
+        let mut dies = false;
+        if let ConditionName::Exhaustion(level) = cn {
+            // a new exhaustion level replaces the old one
+            if let Some(old_level) = self.get_cm(pid).get_exhaustion() {
+                self.remove_if_present(pid, ConditionName::Exhaustion(old_level));
+            }
+            dies = level >= 6;
+        }
         self.handle_instant_cond_effects(pid, &cond);
         let cm = self.get_cm_mut(pid);
         cm.add_condition(cn, cond);
         self.push(CombatEvent::ApplyCond(cn, pid));
+        if dies && !self.is_dead(pid) {
+            self.set_health(pid, Health::Dead);
+        }
     }
 
-    pub fn apply_default_condition(&mut self, pid: ParticipantId, cn: ConditionName) {
-        let cm = self.get_cm_mut(pid);
-        cm.add_basic_condition(cn).unwrap();
-        self.push(CombatEvent::ApplyCond(cn, pid));
+    pub fn apply_default_condition(&mut self, pid: ParticipantId, cn: ConditionName) -> Result<(), CCError> {
+        self.apply_complex_condition(pid, cn, cn.get_basic_cond()?);
+        Ok(())
     }
 
     fn handle_instant_cond_effects(&mut self, pid: ParticipantId, cond: &Condition) {
         for effect in cond.effects.iter() {
             match effect {
                 ConditionEffect::SetResourceLock(rn, lock) => {
+                    // an unlock doesn't override a lock from another condition
+                    let lock = *lock || self.get_cm(pid).get_resource_lock(*rn) == Some(true);
                     let rm = self.get_rm_mut(pid);
-                    rm.set_res_lock(*rn, *lock);
+                    rm.set_res_lock(*rn, lock);
                 },
+                ConditionEffect::Incapacitated => self.drop_concentration(pid),
                 _ => {},
             }
         }
     }
 
+    fn drop_concentration(&mut self, pid: ParticipantId) {
+        self.remove_condition_by_lifetime(pid, &ConditionLifetime::FailConcSave);
+        let cns = self.remove_condition_by_lifetime(pid, &ConditionLifetime::DropConcentration);
+        // you can only concentrate on one thing, but that thing may be split into multiple conditions
+        for cn in cns {
+            self.handle_auto_triggers(pid, TriggerInfo::new(TriggerType::DropConc, TriggerContext::CondNotice(cn)));
+        }
+    }
+
     pub fn remove_condition(&mut self, pid: ParticipantId, cn: ConditionName, at: ActionType) {
         let cm = self.get_cm_mut(pid);
         let cues = cm.remove_condition_by_name(&cn);
@@ -164,6 +189,17 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
         self.dmg[pid.0] = rv;
     }
 
+    // approximates every dmg rv, and returns the total mass moved (relative to this state)
+    pub fn approximate_dmg(&mut self, approx: &Approximation<P>) -> Result<P, RVError> {
+        let mut moved = P::zero();
+        for rv in self.dmg.iter_mut() {
+            let (new_rv, rv_moved) = approx.apply(rv)?;
+            *rv = new_rv;
+            moved = moved.checked_add(&rv_moved).ok_or(RVError::Overflow)?;
+        }
+        Ok(moved)
+    }
+
     pub fn add_resource(&mut self, pid: ParticipantId, rn: ResourceName, amount: usize) {
         let rm = self.get_rm_mut(pid);
         if rm.has_resource(rn) {
@@ -171,6 +207,14 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
         }
     }
 
+    pub fn add_temp_hp(&mut self, pid: ParticipantId, amount: usize) {
+        self.get_rm_mut(pid).add_temp_hp(amount);
+    }
+
+    pub fn get_temp_hp(&self, pid: ParticipantId) -> usize {
+        self.get_rm(pid).get_temp_hp()
+    }
+
     pub fn spend_action_resources(&mut self, pid: ParticipantId, an: ActionName, at: ActionType) {
         let rm = self.get_rm_mut(pid);
         if rm.has_resource(ResourceName::AN(an)) {
@@ -250,6 +294,9 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
                         },
                         TriggerAction::GiveCondition(cn, cond) => {
                             self.apply_complex_condition(pid, cn, cond);
+                        },
+                        TriggerAction::GainTempHP(amount) => {
+                            self.add_temp_hp(pid, amount);
                         }
                         _ => {}
                     }
@@ -258,7 +305,7 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
         }
     }
 
-    pub fn split(self, rv: MapRandVar<CombatEvent, P>) -> Vec<Self> {
+    pub fn split(self, rv: MapRandVar<CombatEvent, P>) -> Result<Vec<Self>, RVError> {
         let mut vec = Vec::with_capacity(rv.len());
         let child_state = self.state.into_child();
         for ce in rv.get_keys() {
@@ -268,25 +315,66 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
                 participants: self.participants,
                 state: ce_state,
                 dmg: self.dmg.clone(),
-                prob: self.prob.clone() * rv.pdf(ce)
+                prob: self.prob.checked_mul(&rv.pdf(ce)).ok_or(RVError::Overflow)?
             })
         }
-        vec
+        Ok(vec)
     }
 
-    pub fn split_dmg(self, state_rv: MapRandVar<CombatEvent, P>, dmg_map: BTreeMap<CombatEvent, VecRandVar<P>>, target: ParticipantId, dead_at_zero: bool) -> Vec<Self> {
-        let children = self.split(state_rv);
+    pub fn split_dmg(self, state_rv: MapRandVar<CombatEvent, P>, dmg_map: BTreeMap<CombatEvent, VecRandVar<P>>, target: ParticipantId, dead_at_zero: bool) -> Result<Vec<Self>, RVError> {
+        let children = self.split(state_rv)?;
         let mut result = Vec::with_capacity(children.len());
         for pcs in children.into_iter() {
             let ce = pcs.get_last_event().unwrap();
-            result.extend(pcs.add_dmg(dmg_map.get(&ce).unwrap(), target, dead_at_zero).into_iter());
+            result.extend(pcs.add_dmg(dmg_map.get(&ce).unwrap(), target, dead_at_zero)?);
         }
-        result
+        Ok(result)
+    }
+
+    pub fn add_dmg(self, dmg: &VecRandVar<P>, target_pid: ParticipantId, dead_at_zero: bool) -> Result<Vec<Self>, RVError> {
+        self.add_dmg_from(dmg, target_pid, dead_at_zero, false)
     }
 
-    pub fn add_dmg(self, dmg: &VecRandVar<P>, target_pid: ParticipantId, dead_at_zero: bool) -> Vec<Self> {
+    // same as add_dmg, but a crit on someone at zero hp costs them two death saves
+    pub fn add_crit_dmg(self, dmg: &VecRandVar<P>, target_pid: ParticipantId, dead_at_zero: bool) -> Result<Vec<Self>, RVError> {
+        self.add_dmg_from(dmg, target_pid, dead_at_zero, true)
+    }
+
+    // heal is negative, like from DiceExpr::get_heal_rv. The dead stay dead.
+    pub fn heal(self, heal: &VecRandVar<P>, target_pid: ParticipantId, dead_at_zero: bool) -> Result<Vec<Self>, RVError> {
+        if self.is_dead(target_pid) {
+            Ok(vec!(self))
+        } else {
+            self.add_dmg(heal, target_pid, dead_at_zero)
+        }
+    }
+
+    // heals the target for as much as they need, or as much as the healer has left in the pool
+    pub fn heal_from_pool(self, healer_pid: ParticipantId, pool: ResourceName, target_pid: ParticipantId, dead_at_zero: bool) -> Result<Vec<Self>, RVError> {
+        let pool_left = self.get_rm(healer_pid).get_current(pool).count().unwrap_or(0) as isize;
+        if self.is_dead(target_pid) || pool_left == 0 {
+            return Ok(vec!(self));
+        }
+        let child_state = self.state.clone().into_child();
+        let partitions = self.get_dmg(target_pid).partitions(|d| cmp::min(*d, pool_left))?;
+        let mut result = Vec::new();
+        for (amount, partition) in partitions.into_iter() {
+            let mut child = Self {
+                participants: self.participants,
+                state: child_state.clone(),
+                dmg: self.dmg.clone(),
+                prob: self.prob.checked_mul(&partition.prob).ok_or(RVError::Overflow)?
+            };
+            child.set_dmg(target_pid, partition.rv.unwrap());
+            child.get_rm_mut(healer_pid).spend_many(pool, amount as usize);
+            result.extend(child.add_dmg(&VecRandVar::new_constant(-amount)?, target_pid, dead_at_zero)?);
+        }
+        Ok(result)
+    }
+
+    fn add_dmg_from(self, dmg: &VecRandVar<P>, target_pid: ParticipantId, dead_at_zero: bool, crit: bool) -> Result<Vec<Self>, RVError> {
         if self.get_cm(target_pid).has_condition(&ConditionName::Concentration) && dmg.upper_bound() > 0 {
-            let conc_outcome = self.get_conc_outcomes(dmg, target_pid);
+            let conc_outcome = self.get_conc_outcomes(dmg, target_pid)?;
             let child_state = self.state.into_child();
             let mut vec = Vec::with_capacity(2);
 
@@ -294,43 +382,39 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
                 participants: self.participants,
                 state: child_state.clone(),
                 dmg: self.dmg.clone(),
-                prob: self.prob.clone() * conc_outcome.pdf(BinaryOutcome::Pass)
+                prob: self.prob.checked_mul(&conc_outcome.pdf(BinaryOutcome::Pass)).ok_or(RVError::Overflow)?
             };
             if keep_conc.prob > P::zero() {
-                vec.extend(keep_conc.handle_dmg(dmg, target_pid, dead_at_zero).into_iter());
+                vec.extend(keep_conc.handle_dmg(dmg, target_pid, dead_at_zero, crit)?);
             }
 
             let mut drop_conc = Self {
                 participants: self.participants,
                 state: child_state,
                 dmg: self.dmg,
-                prob: self.prob * conc_outcome.pdf(BinaryOutcome::Fail)
+                prob: self.prob.checked_mul(&conc_outcome.pdf(BinaryOutcome::Fail)).ok_or(RVError::Overflow)?
             };
             if drop_conc.prob > P::zero() {
-                drop_conc.remove_condition_by_lifetime(target_pid, &ConditionLifetime::FailConcSave);
-                let cns = drop_conc.remove_condition_by_lifetime(target_pid, &ConditionLifetime::DropConcentration);
-                // you can only concentrate on one thing, but that thing may be split into multiple conditions
-                for cn in cns {
-                    drop_conc.handle_auto_triggers(target_pid, TriggerInfo::new(TriggerType::DropConc, TriggerContext::CondNotice(cn)));
-                }
-                vec.extend(drop_conc.handle_dmg(dmg, target_pid, dead_at_zero).into_iter());
+                drop_conc.drop_concentration(target_pid);
+                vec.extend(drop_conc.handle_dmg(dmg, target_pid, dead_at_zero, crit)?);
             }
-            vec
+            Ok(vec)
         } else {
-            self.handle_dmg(dmg, target_pid, dead_at_zero)
+            self.handle_dmg(dmg, target_pid, dead_at_zero, crit)
         }
     }
 
-    fn get_conc_outcomes(&self, dmg_rv: &VecRandVar<P>, pid: ParticipantId) -> MapRandVar<BinaryOutcome, P> {
+    fn get_conc_outcomes(&self, dmg_rv: &VecRandVar<P>, pid: ParticipantId) -> Result<MapRandVar<BinaryOutcome, P>, RVError> {
         let target = self.participants.get_participant(pid).participant.as_ref();
         // TODO: concentration save should be its own thing, since some features affect that specifically
+        let auto_fail = self.get_cm(pid).auto_fails_save(Ability::CON);
         let save_mod = self.get_cm(pid).get_save_mod(Ability::CON);
         let conc_save = target.get_ability_scores().constitution.get_save_rv(target.get_prof(), save_mod);
         let mut pass_total = P::zero();
         let mut fail_total = P::zero();
         for dmg in dmg_rv.get_keys() {
             if dmg <= 0 { // no save needed
-                pass_total = pass_total + dmg_rv.pdf(dmg);
+                pass_total = pass_total.checked_add(&dmg_rv.pdf(dmg)).ok_or(RVError::Overflow)?;
             } else {
                 let dc;
                 if dmg <= 21 {
@@ -338,63 +422,135 @@ impl<'pm, P: RVProb> ProbCombatState<'pm, P> {
                 } else {
                     dc = dmg/2;
                 }
-                let fail: P = conc_save.cdf_exclusive(dc);
-                let pass = P::one() - fail.clone();
+                let fail: P = if auto_fail { P::one() } else { conc_save.cdf_exclusive(dc) };
+                let pass = P::one().checked_sub(&fail).ok_or(RVError::Overflow)?;
 
-                pass_total = pass_total + pass * dmg_rv.pdf(dmg);
-                fail_total = fail_total + fail * dmg_rv.pdf(dmg);
+                let pass_prob = pass.checked_mul(&dmg_rv.pdf(dmg)).ok_or(RVError::Overflow)?;
+                let fail_prob = fail.checked_mul(&dmg_rv.pdf(dmg)).ok_or(RVError::Overflow)?;
+                pass_total = pass_total.checked_add(&pass_prob).ok_or(RVError::Overflow)?;
+                fail_total = fail_total.checked_add(&fail_prob).ok_or(RVError::Overflow)?;
             }
         }
         let mut map = BTreeMap::new();
         map.insert(BinaryOutcome::Pass, pass_total);
         map.insert(BinaryOutcome::Fail, fail_total);
-        MapRandVar::from_map(map).unwrap()
+        MapRandVar::from_map(map)
+    }
+
+    fn handle_dmg(self, dmg: &VecRandVar<P>, target: ParticipantId, dead_at_zero: bool, crit: bool) -> Result<Vec<Self>, RVError> {
+        let temp_hp = self.get_temp_hp(target) as isize;
+        if temp_hp == 0 || dmg.upper_bound() <= 0 || self.is_dead(target) {
+            return self.handle_hp_dmg(dmg, target, dead_at_zero, crit);
+        }
+        // temp hp soak up damage first, so split on how much of them got used
+        let child_state = self.state.clone().into_child();
+        let partitions = dmg.partitions(|d| cmp::min(cmp::max(*d, 0), temp_hp))?;
+        let mut result = Vec::new();
+        for (absorbed, partition) in partitions.into_iter() {
+            let mut child = Self {
+                participants: self.participants,
+                state: child_state.clone(),
+                dmg: self.dmg.clone(),
+                prob: self.prob.checked_mul(&partition.prob).ok_or(RVError::Overflow)?
+            };
+            let rm = child.get_rm_mut(target);
+            rm.drain(ResourceName::TempHP);
+            rm.add_temp_hp((temp_hp - absorbed) as usize);
+            let hp_dmg = partition.rv.unwrap().add_const(-absorbed);
+            result.extend(child.handle_hp_dmg(&hp_dmg, target, dead_at_zero, crit)?);
+        }
+        Ok(result)
     }
 
-    fn handle_dmg(mut self, dmg: &VecRandVar<P>, target: ParticipantId, dead_at_zero: bool) -> Vec<Self> {
+    fn handle_hp_dmg(mut self, dmg: &VecRandVar<P>, target: ParticipantId, dead_at_zero: bool, crit: bool) -> Result<Vec<Self>, RVError> {
         let old_health = self.get_health(target);
         let hp = self.get_max_hp(target);
         let bloody_hp = Health::calc_bloodied(hp);
         let old_dmg = self.get_dmg(target);
-        let uncapped_dmg = old_dmg.add_rv(dmg);
-        let new_dmg;
-        if hp < uncapped_dmg.lower_bound() {
-            // this hit always kills
-            new_dmg = VecRandVar::new_constant(hp).unwrap();
-        } else {
-            new_dmg = uncapped_dmg.cap_lb(0).unwrap().cap_ub(hp).unwrap();
-        }
-        let (new_hlb, new_hub) = Health::classify_bounds(&new_dmg, hp, dead_at_zero);
+        let uncapped_dmg = old_dmg.try_add_rv(dmg)?;
         let mut result = Vec::new();
-
-        if new_hlb == new_hub {
-            if new_hlb == old_health {
-                self.set_dmg(target, new_dmg);
-                result.push(self);
-            } else {
-                self.set_dmg(target, new_dmg);
-                self.set_health(target, new_hlb);
-                result.push(self);
-            }
+        if old_health == Health::Dead {
+            self.set_dmg(target, ProbCombatState::cap_dmg(uncapped_dmg, hp));
+            result.push(self);
+            return Ok(result);
+        }
+        let classify = |d: &isize| Health::classify_dmg(d, old_health, bloody_hp, hp, dead_at_zero, crit);
+        let lb_outcome = classify(&uncapped_dmg.lower_bound());
+        let ub_outcome = classify(&uncapped_dmg.upper_bound());
+
+        if lb_outcome == ub_outcome {
+            self.set_dmg(target, ProbCombatState::cap_dmg(uncapped_dmg, hp));
+            self.apply_dmg_outcome(target, old_health, lb_outcome);
+            result.push(self);
         } else {
             let child_state = self.state.clone().into_child();
-            let partitions = new_dmg.partitions(|p| Health::classify_hp(p, bloody_hp, hp, dead_at_zero));
-            for (new_health, partition) in partitions.into_iter() {
-                let mut state = child_state.clone();
-                if old_health != new_health {
-                    state.set_health(target, new_health);
-                }
+            let partitions = uncapped_dmg.partitions(classify)?;
+            for (outcome, partition) in partitions.into_iter() {
                 let mut child = Self {
                     participants: self.participants,
-                    state,
+                    state: child_state.clone(),
                     dmg: self.dmg.clone(),
-                    prob: self.prob.clone() * partition.prob
+                    prob: self.prob.checked_mul(&partition.prob).ok_or(RVError::Overflow)?
                 };
-                child.set_dmg(target, partition.rv.unwrap());
+                child.set_dmg(target, ProbCombatState::cap_dmg(partition.rv.unwrap(), hp));
+                child.apply_dmg_outcome(target, old_health, outcome);
                 result.push(child);
             }
         }
-        result
+        Ok(result)
+    }
+
+    // dmg is tracked between 0 and max hp
+    fn cap_dmg(dmg: VecRandVar<P>, hp: isize) -> VecRandVar<P> {
+        if hp <= dmg.lower_bound() {
+            VecRandVar::new_constant(hp).unwrap()
+        } else if dmg.upper_bound() <= 0 {
+            VecRandVar::new_constant(0).unwrap()
+        } else {
+            dmg.cap_lb(0).unwrap().cap_ub(hp).unwrap()
+        }
+    }
+
+    fn apply_dmg_outcome(&mut self, target: ParticipantId, old_health: Health, (new_health, fails): (Health, u8)) {
+        if new_health != old_health {
+            // done before the health change, so that stays the last event (for on-kill triggers)
+            if new_health == Health::ZeroHP {
+                // these always have a basic condition
+                self.apply_default_condition(target, ConditionName::Unconscious).unwrap();
+                self.apply_default_condition(target, ConditionName::Prone).unwrap();
+            } else if old_health == Health::ZeroHP && new_health < Health::ZeroHP {
+                self.wake_up(target);
+            }
+            self.set_health(target, new_health);
+        }
+        if fails > 0 {
+            self.state.add_death_save_failures(target, fails);
+        }
+    }
+
+    // you stay prone though
+    fn wake_up(&mut self, pid: ParticipantId) {
+        self.remove_if_present(pid, ConditionName::Unconscious);
+    }
+
+    fn remove_if_present(&mut self, pid: ParticipantId, cn: ConditionName) {
+        if self.get_cm(pid).has_condition(&cn) {
+            let cues = self.get_cm_mut(pid).remove_condition_by_name(&cn);
+            self.push(CombatEvent::RemoveCond(cn, pid));
+            self.state.handle_cues(pid, &cues);
+        }
+    }
+
+    pub fn get_death_saves(&self, pid: ParticipantId) -> &DeathSaves {
+        self.state.get_death_saves(pid)
+    }
+
+    pub fn add_death_save_success(&mut self, pid: ParticipantId) {
+        self.state.add_death_save_success(pid);
+    }
+
+    pub fn add_death_save_failures(&mut self, pid: ParticipantId, count: u8) {
+        self.state.add_death_save_failures(pid, count);
     }
 }
 
@@ -402,7 +558,8 @@ impl<'pm, P: RVProb> Transposition for ProbCombatState<'pm, P> {
     fn is_transposition(&self, other: &Self) -> bool {
         let valid_state = CombatState::is_transposition(&self.state, &other.state);
         let valid_dmg = self.dmg.len() == other.dmg.len();
-        let valid_prob = self.prob.clone() + other.prob.clone() <= P::one();
+        let total_prob = self.prob.clone() + other.prob.clone();
+        let valid_prob = total_prob < P::one() || total_prob.approx_eq(&P::one());
         let valid_part = ptr::eq(self.participants, other.participants);
         valid_state && valid_dmg && valid_prob && valid_part
     }
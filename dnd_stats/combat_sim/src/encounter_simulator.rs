@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use num::{BigRational, Rational64};
 
@@ -6,10 +6,10 @@ use combat_core::actions::{ActionName, ActionType, CombatAction};
 use combat_core::attack::{Attack, AttackResult};
 use combat_core::{BinaryOutcome, CCError};
 use combat_core::combat_event::{CombatEvent, CombatTiming};
-use combat_core::conditions::{Condition, ConditionLifetime, ConditionName};
-use combat_core::damage::DamageTerm;
+use combat_core::conditions::{AttackDistance, Condition, ConditionLifetime, ConditionName};
+use combat_core::damage::{DamageDefenses, DamageReduction, DamageTerm};
 use combat_core::damage::dice_expr::DiceExpr;
-use combat_core::health::Health;
+use combat_core::health::{DeathSaveResult, Health};
 use combat_core::participant::{Participant, ParticipantId, ParticipantManager, Team};
 use combat_core::resources::{ResourceActionType, ResourceName};
 use combat_core::resources::resource_amounts::ResourceCount;
@@ -18,8 +18,10 @@ use combat_core::spells::{SaveDmgSpell, SpellEffect, SpellSlot};
 use combat_core::strategy::{StrategicAction, Strategy, StrategyDecision, StrategyManager, Target};
 use combat_core::triggers::{TriggerAction, TriggerContext, TriggerInfo, TriggerResponse, TriggerType};
 use rand_var::map_rand_var::MapRandVar;
-use rand_var::rand_var::prob_type::RVProb;
+use rand_var::rand_var::approximation::Approximation;
+use rand_var::rand_var::prob_type::{F64Prob, RVProb};
 use rand_var::vec_rand_var::VecRandVar;
+use rand_var::RVError;
 
 use crate::combat_state_rv::CombatStateRV;
 use crate::combat_state_rv::prob_combat_state::ProbCombatState;
@@ -40,10 +42,13 @@ pub struct EncounterSimulator<'sm ,'pm, P: RVProb> {
     round_num: u8,
     cs_rv: CombatStateRV<'pm, P>,
     merge_transpositions: bool,
+    approximation: Option<Approximation<P>>,
+    approx_error: P,
 }
 
 pub type ES64<'sm, 'pm> = EncounterSimulator<'sm, 'pm, Rational64>;
 pub type ESBig<'sm, 'pm> = EncounterSimulator<'sm, 'pm, BigRational>;
+pub type ESF64<'sm, 'pm> = EncounterSimulator<'sm, 'pm, F64Prob>;
 
 impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
     pub fn new(sm: &'sm StrategyManager<'pm>) -> Result<Self, CSError> {
@@ -57,6 +62,8 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
             round_num: 0,
             cs_rv: CombatStateRV::new(pm),
             merge_transpositions: false,
+            approximation: None,
+            approx_error: P::zero(),
         })
     }
 
@@ -64,6 +71,16 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         self.merge_transpositions = merges
     }
 
+    // applied to the dmg rvs at the end of every round
+    pub fn set_approximation(&mut self, approx: Option<Approximation<P>>) {
+        self.approximation = approx
+    }
+
+    // an upper bound on how far (in total variation) any dmg rv is from the exact one
+    pub fn get_approx_error(&self) -> &P {
+        &self.approx_error
+    }
+
     pub fn simulate_n_rounds(&mut self, n: u8) -> ResultCSE {
         if self.round_num == 0 {
             self.register_timing(CombatTiming::EncounterBegin);
@@ -73,6 +90,7 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
             self.register_timing(CombatTiming::BeginRound(self.round_num.into()));
             self.simulate_round()?;
             self.register_timing(CombatTiming::EndRound(self.round_num.into()));
+            self.handle_approximation()?;
             self.handle_merges();
         }
         Ok(())
@@ -96,6 +114,17 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         }
     }
 
+    fn handle_approximation(&mut self) -> ResultCSE {
+        if let Some(approx) = &self.approximation {
+            for pcs in self.cs_rv.get_states_mut() {
+                let moved = pcs.approximate_dmg(approx)?;
+                let error = pcs.get_prob().checked_mul(&moved).ok_or(RVError::Overflow)?;
+                self.approx_error = self.approx_error.checked_add(&error).ok_or(RVError::Overflow)?;
+            }
+        }
+        Ok(())
+    }
+
     fn register_timing(&mut self, ct: CombatTiming) {
         let event = ct.into();
         for pcs in self.cs_rv.get_states_mut() {
@@ -125,7 +154,7 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
     fn simulate_turn(&mut self, pid: ParticipantId) -> ResultCSE {
         let mut finished_pcs = Vec::new();
         for pcs in self.cs_rv.get_states() {
-            let new_pcs = self.finish_turn(pcs.clone(), pid)?;
+            let new_pcs = self.start_turn(pcs.clone(), pid)?;
             finished_pcs.extend(new_pcs.into_iter());
         }
         self.cs_rv = finished_pcs.into();
@@ -158,6 +187,50 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         true
     }
 
+    // someone at zero hp makes a death save instead of taking their turn
+    fn start_turn(&self, mut pcs: ProbCombatState<'pm, P>, pid: ParticipantId) -> ResultVS<'pm, P> {
+        if self.is_combat_over(&mut pcs) || pcs.get_health(pid) != Health::ZeroHP {
+            return self.finish_turn(pcs, pid);
+        }
+        if pcs.get_death_saves(pid).is_stable() {
+            return Ok(vec!(pcs));
+        }
+        let mut results = Vec::new();
+        for pcs in self.handle_death_save(pcs, pid)? {
+            if pcs.get_health(pid) < Health::ZeroHP {
+                results.extend(self.finish_turn(pcs, pid)?);
+            } else {
+                results.push(pcs);
+            }
+        }
+        Ok(results)
+    }
+
+    fn handle_death_save(&self, pcs: ProbCombatState<'pm, P>, pid: ParticipantId) -> ResultVS<'pm, P> {
+        let ce_rv = DeathSaveResult::get_rv().map_keys(CombatEvent::DeathSave);
+        let children = pcs.split(ce_rv)?;
+        let mut results = Vec::with_capacity(children.len());
+        for mut child in children {
+            if let CombatEvent::DeathSave(dsr) = child.get_last_event().unwrap() {
+                match dsr {
+                    DeathSaveResult::CritFail => child.add_death_save_failures(pid, 2),
+                    DeathSaveResult::Fail => child.add_death_save_failures(pid, 1),
+                    DeathSaveResult::Pass => child.add_death_save_success(pid),
+                    DeathSaveResult::CritPass => {
+                        // back up with 1 hp
+                        let heal = VecRandVar::new_constant(-1).unwrap();
+                        results.extend(child.add_dmg(&heal, pid, false)?);
+                        continue;
+                    }
+                }
+                results.push(child);
+            } else {
+                return Err(CSError::UnknownEvent(child.get_last_event().unwrap()));
+            }
+        }
+        Ok(results)
+    }
+
     fn finish_turn(&self, mut pcs: ProbCombatState<'pm, P>, pid: ParticipantId) -> ResultVS<'pm, P> {
         if self.is_combat_over(&mut pcs) || pcs.is_dead(pid) {
             return Ok(vec!(pcs));
@@ -249,6 +322,10 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         if co.req_target && so.target.is_none() {
             return false; // invalid target
         }
+        let cm = pcs.get_cm(pid);
+        if cm.is_incapacitated() {
+            return false; // can't take actions
+        }
         let rm = pcs.get_rm(pid);
         if rm.has_resource(ResourceName::AN(an)) && rm.get_current(ResourceName::AN(an)) == 0 {
             return false; // lacks action-specific resource
@@ -257,7 +334,11 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         if rm.has_resource(at.into()) && rm.get_current(at.into()) == 0 {
             return false; // lacks action type resource
         }
-        let cm = pcs.get_cm(pid);
+        if let CombatAction::HealFromPool(rn) = co.action {
+            if rm.get_current(rn) == 0 {
+                return false; // empty healing pool
+            }
+        }
         if let ActionName::CastSpell(sn) = an {
             if !participant.has_spells() {
                 return false; // no spells
@@ -329,23 +410,38 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                     pcs.push(CombatEvent::Attack(pid, target_pid));
                     Ok(HandledAction::Children(self.handle_attack(pcs, atk, pid, target_pid)?))
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, an))
                 }
             },
             CombatAction::SelfHeal(de) => {
                 let heal: VecRandVar<P> = de.get_heal_rv()?;
-                Ok(HandledAction::Children(pcs.add_dmg(&heal, pid, self.is_dead_at_zero(pid))))
+                Ok(HandledAction::Children(pcs.add_dmg(&heal, pid, self.is_dead_at_zero(pid))?))
             },
             CombatAction::GainResource(rn, aa) => {
                 pcs.get_rm_mut(pid).gain(*rn, *aa);
                 Ok(HandledAction::InPlace(pcs))
             },
+            CombatAction::GainTempHP(amount) => {
+                if let Some(Target::Participant(target_pid)) = so.target {
+                    pcs.add_temp_hp(target_pid, *amount);
+                } else {
+                    pcs.add_temp_hp(pid, *amount);
+                }
+                Ok(HandledAction::InPlace(pcs))
+            },
+            CombatAction::HealFromPool(rn) => {
+                if let Target::Participant(target_pid) = so.target.unwrap() {
+                    Ok(HandledAction::Children(pcs.heal_from_pool(pid, *rn, target_pid, self.is_dead_at_zero(target_pid))?))
+                } else {
+                    Err(CSError::InvalidTarget(pid, an))
+                }
+            },
             CombatAction::ApplyBasicCondition(cn) => {
                 if let Target::Participant(target_pid) = so.target.unwrap() {
-                    pcs.apply_default_condition(target_pid, *cn);
+                    pcs.apply_default_condition(target_pid, *cn)?;
                     Ok(HandledAction::InPlace(pcs))
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, an))
                 }
             },
             CombatAction::ApplyComplexCondition(cn, cond) => {
@@ -353,7 +449,7 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                     pcs.apply_complex_condition(target_pid, *cn, cond.clone());
                     Ok(HandledAction::InPlace(pcs))
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, an))
                 }
             },
             CombatAction::CastSpell => {
@@ -371,14 +467,14 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         if let ActionName::CastSpell(sn) = so.action_name {
             spell_name = sn;
         } else {
-            return Err(CSError::InvalidAction);
+            return Err(CSError::InvalidAction(pid, so.action_name));
         }
         let caster = self.get_participant(pid);
         let spell = caster.get_spell_manager().unwrap().get(&spell_name).unwrap();
-        let spend_slot = so.spell_slot.ok_or(CSError::InvalidAction)?;
+        let spend_slot = so.spell_slot.ok_or(CSError::InvalidAction(pid, so.action_name))?;
         pcs.spend_spell_slot(pid, spend_slot);
         if spell.concentration {
-            pcs.apply_default_condition(pid, ConditionName::Concentration);
+            pcs.apply_default_condition(pid, ConditionName::Concentration)?;
         }
         match &spell.effect {
             SpellEffect::SpellAttack(atk) => {
@@ -386,7 +482,7 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                     pcs.push(CombatEvent::Attack(pid, target_pid));
                     Ok(HandledAction::Children(self.handle_attack(pcs, atk, pid, target_pid)?))
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, so.action_name))
                 }
             },
             SpellEffect::SaveDamage(sds) => {
@@ -395,7 +491,7 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                     pcs.push(CombatEvent::ForceSave(pid, target_pid, sds.save.ability));
                     Ok(HandledAction::Children(self.handle_save_dmg(pcs, sds, pid, target_pid)?))
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, so.action_name))
                 }
             }
             SpellEffect::ApplyCondition(cn, cond) => {
@@ -403,12 +499,20 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                     if let Target::Participant(target_pid) = so.target.unwrap() {
                         pcs.apply_complex_condition(target_pid, *cn, cond.clone());
                     } else {
-                        return Err(CSError::InvalidTarget);
+                        return Err(CSError::InvalidTarget(pid, so.action_name));
                     }
                 } else {
                     pcs.apply_complex_condition(pid, *cn, cond.clone());
                 }
                 Ok(HandledAction::InPlace(pcs))
+            },
+            SpellEffect::Heal(de) => {
+                if let Target::Participant(target_pid) = so.target.unwrap() {
+                    let heal: VecRandVar<P> = de.get_heal_rv()?;
+                    Ok(HandledAction::Children(pcs.heal(&heal, target_pid, self.is_dead_at_zero(target_pid))?))
+                } else {
+                    Err(CSError::InvalidTarget(pid, so.action_name))
+                }
             }
         }
     }
@@ -423,27 +527,27 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                 if let Target::Participant(target_pid) = so.target.unwrap() {
                     Ok(HandledAction::Children(self.handle_shove_prone(pcs, pid, target_pid)?))
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, an))
                 }
             },
             ActionName::FavoredFoeUse => {
                 if let Target::Participant(target_pid) = so.target.unwrap() {
                     let co = self.participants.get_participant(pid).participant.get_action_manager().get(&ActionName::FavoredFoeApply);
                     if co.is_none() {
-                        Err(CSError::ActionNotHandled)
+                        Err(CSError::ActionNotHandled(pid, an))
                     } else {
                         if let CombatAction::ApplyComplexCondition(cn, cond) = &co.unwrap().action {
                             pcs.apply_complex_condition(target_pid, *cn, cond.clone());
                             Ok(HandledAction::InPlace(pcs))
                         } else {
-                            Err(CSError::ActionNotHandled)
+                            Err(CSError::ActionNotHandled(pid, an))
                         }
                     }
                 } else {
-                    Err(CSError::InvalidTarget)
+                    Err(CSError::InvalidTarget(pid, an))
                 }
             },
-            _ => Err(CSError::ActionNotHandled),
+            _ => Err(CSError::ActionNotHandled(pid, an)),
         }
     }
 
@@ -455,13 +559,13 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         pcs.push(CombatEvent::SkillContest(shover_pid, SkillName::Athletics, target_pid, t_skill));
         let contest = SkillContest::build(shover.as_ref(), target.as_ref(), SkillName::Athletics, t_skill);
         let ce_skc = contest.result.map_keys(|cr| CombatEvent::SkCR(cr));
-        let children = pcs.split(ce_skc);
+        let children = pcs.split(ce_skc)?;
         let mut results = Vec::with_capacity(children.len());
         for mut child in children {
             if let CombatEvent::SkCR(cr) = child.get_last_event().unwrap() {
                 match cr {
                     ContestResult::InitiatorWins => {
-                        child.apply_default_condition(target_pid, ConditionName::Prone);
+                        child.apply_default_condition(target_pid, ConditionName::Prone)?;
                         results.push(child);
                     }
                     ContestResult::DefenderWins => results.push(child)
@@ -476,28 +580,33 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
     fn handle_save_dmg(&self, pcs: ProbCombatState<'pm, P>, sds: &SaveDmgSpell, atker_pid: ParticipantId, target_pid: ParticipantId) -> ResultVS<'pm, P> {
         let target = self.get_participant(target_pid);
         let dead_at_zero = self.is_dead_at_zero(target_pid);
-        // TODO: check conditions for adv/disadv
-        let save_mod = pcs.get_cm(target_pid).get_save_mod(sds.save.ability);
-        let ce_rv = sds.save.make_save_at(target.as_ref(), save_mod);
-        let children = pcs.split(ce_rv);
+        let target_cm = pcs.get_cm(target_pid);
+        let ce_rv = if target_cm.auto_fails_save(sds.save.ability) {
+            MapRandVar::from_map(BTreeMap::from([(CombatEvent::SaveResult(BinaryOutcome::Fail), P::one())]))?
+        } else {
+            let save_mod = target_cm.get_save_mod(sds.save.ability);
+            sds.save.make_save_at(target.as_ref(), save_mod)
+        };
+        let prev_health = pcs.get_health(target_pid);
+        let defenses = &self.get_dmg_defenses(&pcs, target_pid);
+        let children = pcs.split(ce_rv)?;
         let mut results = Vec::with_capacity(children.len());
-        let resist = target.get_resistances();
         for child in children {
             if let CombatEvent::SaveResult(sr) = child.get_last_event().unwrap() {
                 match sr {
                     BinaryOutcome::Fail => {
                         // TODO: implement something similar to handle_successful_attack for triggers and such
-                        let v = child.add_dmg(&sds.dmg.get_base_dmg(resist, vec!(), HashSet::new())?, target_pid, dead_at_zero);
+                        let v = child.add_dmg(&sds.dmg.get_base_dmg(defenses, vec!(), HashSet::new())?, target_pid, dead_at_zero)?;
                         results.extend(v.into_iter());
                     },
                     BinaryOutcome::Pass => {
                         let fail_dmg: VecRandVar<P>;
                         if sds.half_dmg {
-                            fail_dmg = sds.dmg.get_half_base_dmg(resist)?;
+                            fail_dmg = sds.dmg.get_half_base_dmg(defenses)?;
                         } else {
                             fail_dmg = VecRandVar::new_constant(0).unwrap();
                         }
-                        let v = child.add_dmg(&fail_dmg, target_pid, dead_at_zero);
+                        let v = child.add_dmg(&fail_dmg, target_pid, dead_at_zero)?;
                         results.extend(v.into_iter());
                     },
                 }
@@ -505,11 +614,7 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                 return Err(CSError::UnknownEvent(child.get_last_event().unwrap()));
             }
         }
-        let mut health = Health::ZeroHP;
-        if dead_at_zero {
-            health = Health::Dead;
-        }
-        Ok(self.handle_on_kill_triggers(results, atker_pid, target_pid, health)?)
+        self.handle_on_kill_triggers(results, atker_pid, target_pid, prev_health)
     }
 
     fn handle_attack(&self, pcs: ProbCombatState<'pm, P>, atk: &impl Attack, atker_pid: ParticipantId, target_pid: ParticipantId) -> ResultVS<'pm, P> {
@@ -519,16 +624,20 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         let target_cm = pcs.get_state().get_cm(target_pid);
         let roll_type = atk_cm.overall_atk_mod(target_cm, atk.get_atk_range());
         let target_ac = target.get_ac() + target_cm.get_ac_boost();
-        let ce_rv: MapRandVar<CombatEvent, P> = atk.get_ce_rv(roll_type, target_ac)?;
+        let mut ce_rv: MapRandVar<CombatEvent, P> = atk.get_ce_rv(roll_type, target_ac)?;
+        if target_cm.auto_crits(atk.get_atk_range()) {
+            ce_rv = ce_rv.map_keys(|ce| if ce == AttackResult::Hit.into() { AttackResult::Crit.into() } else { ce });
+        }
         // TODO: handle AC triggers
-        let children = pcs.split(ce_rv);
+        let prev_health = pcs.get_health(target_pid);
+        let defenses = &self.get_dmg_defenses(&pcs, target_pid);
+        let children = pcs.split(ce_rv)?;
         let mut results = Vec::with_capacity(children.len());
-        let resist = target.get_resistances();
         for child in children {
             if let CombatEvent::AR(ar) = child.get_last_event().unwrap() {
                 match ar {
                     AttackResult::Miss => {
-                        let v = child.add_dmg(&atk.get_miss_dmg(resist, vec!(), HashSet::new())?, target_pid, dead_at_zero);
+                        let v = child.add_dmg(&atk.get_miss_dmg(defenses, vec!(), HashSet::new())?, target_pid, dead_at_zero)?;
                         results.extend(v.into_iter());
                     },
                     _ => {
@@ -540,28 +649,51 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                 return Err(CSError::UnknownEvent(child.get_last_event().unwrap()));
             }
         }
-        let mut health = Health::ZeroHP;
-        if dead_at_zero {
-            health = Health::Dead;
-        }
-        Ok(self.handle_on_kill_triggers(results, atker_pid, target_pid, health)?)
+        self.handle_on_kill_triggers(results, atker_pid, target_pid, prev_health)
     }
 
     fn handle_successful_attack(&self, mut pcs: ProbCombatState<'pm, P>, atk: &impl Attack, ar: AttackResult, atker_pid: ParticipantId, target_pid: ParticipantId) -> ResultVS<'pm, P> {
         let dead_at_zero = self.is_dead_at_zero(target_pid);
-        let resist = self.get_participant(target_pid).get_resistances();
+        let mut defenses = self.get_dmg_defenses(&pcs, target_pid);
         let ti = TriggerInfo::new(TriggerType::SuccessfulAttack, TriggerContext::AR(ar));
         let mut bonus_dmg = self.handle_triggers(&mut pcs, atker_pid, ti, true)?.unwrap_or(Vec::new());
         let target_cm = pcs.get_state().get_cm(target_pid);
         let (dmg_feats, dmg_terms) = target_cm.overall_dmg_mods(atker_pid);
         bonus_dmg.extend(dmg_terms.into_iter());
         pcs.remove_condition_by_lifetime(target_pid, &ConditionLifetime::OnHitByAtk(atker_pid));
-        Ok(pcs.add_dmg(&atk.get_ar_dmg(ar, resist, bonus_dmg, dmg_feats)?, target_pid, dead_at_zero))
+        let mut reductions = pcs.get_cm(target_pid).get_dmg_reductions();
+        reductions.extend(self.handle_was_hit_triggers(&mut pcs, ar, atk.get_atk_range(), target_pid)?);
+        for dr in reductions {
+            if !(atk.is_magical() && dr.nonmagical_only()) {
+                defenses.add_reduction(dr);
+            }
+        }
+        let dmg = atk.get_ar_dmg(ar, &defenses, bonus_dmg, dmg_feats)?;
+        if ar == AttackResult::Crit {
+            Ok(pcs.add_crit_dmg(&dmg, target_pid, dead_at_zero)?)
+        } else {
+            Ok(pcs.add_dmg(&dmg, target_pid, dead_at_zero)?)
+        }
+    }
+
+    // the target's own defenses, plus any their conditions give them (petrified)
+    fn get_dmg_defenses(&self, pcs: &ProbCombatState<'pm, P>, target_pid: ParticipantId) -> DamageDefenses {
+        let mut defenses = self.get_participant(target_pid).get_dmg_defenses().clone();
+        if pcs.get_cm(target_pid).resists_all_dmg() {
+            defenses.resist_all();
+        }
+        defenses
     }
 
-    fn handle_on_kill_triggers(&self, mut results: Vec<ProbCombatState<'pm, P>>, atker_pid: ParticipantId, target_pid: ParticipantId, health: Health) -> ResultVS<'pm, P> {
+    // only a target that was up counts as a kill, finishing off a downed target
+    // with failed death saves doesn't fire the triggers again
+    fn handle_on_kill_triggers(&self, mut results: Vec<ProbCombatState<'pm, P>>, atker_pid: ParticipantId, target_pid: ParticipantId, prev_health: Health) -> ResultVS<'pm, P> {
+        if prev_health >= Health::ZeroHP {
+            return Ok(results);
+        }
         for pcs in results.iter_mut() {
-            if pcs.get_last_event().unwrap() == CombatEvent::HP(target_pid, health) {
+            let last_event = pcs.get_last_event().unwrap();
+            if last_event == CombatEvent::HP(target_pid, Health::ZeroHP) || last_event == CombatEvent::HP(target_pid, Health::Dead) {
                 self.handle_triggers(pcs, atker_pid, TriggerType::OnKill.into(), false)?;
                 let death_notices = pcs.get_cm(target_pid).get_death_notices();
                 for pid in death_notices {
@@ -584,22 +716,71 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
                 if tm.has_manual_triggers(ti) {
                     response.extend(self.get_strategy(pid).choose_triggers(ti, pcs.get_state()).into_iter());
                 }
-                let cost = self.validate_trigger_cost(pcs, pid, &response);
-                if cost.is_some() {
-                    pcs.spend_resource_cost(pid, cost.unwrap());
-                    self.resolve_add_resource_triggers(pcs, pid, &response);
-                    self.resolve_give_cond_triggers(pcs, pid, &response);
-                    if get_bonus_dmg {
-                        bonus_dmg = Some(self.resolve_dmg_bonus_triggers(&response));
-                    }
-                } else {
-                    return Err(CSError::InvalidTriggerResponse);
+                self.resolve_triggers(pcs, pid, &response)?;
+                if get_bonus_dmg {
+                    bonus_dmg = Some(self.resolve_dmg_bonus_triggers(&response));
                 }
             }
         }
         Ok(bonus_dmg)
     }
 
+    // the target of an attack can react to getting hit (uncanny dodge, deflect missiles, etc.)
+    fn handle_was_hit_triggers(&self, pcs: &mut ProbCombatState<'pm, P>, ar: AttackResult, dist: AttackDistance, target_pid: ParticipantId) -> Result<Vec<DamageReduction>, CSError> {
+        let mut reductions = Vec::new();
+        let ti = TriggerInfo::new(TriggerType::WasHit, TriggerContext::AR(ar));
+        // these are all reactions
+        if pcs.get_cm(target_pid).is_incapacitated() {
+            return Ok(reductions);
+        }
+        if self.get_participant(target_pid).has_triggers() {
+            let tm = self.get_participant(target_pid).get_trigger_manager().unwrap();
+            if tm.has_triggers(ti) {
+                let mut response = tm.get_auto_responses(ti);
+                if tm.has_manual_triggers(ti) {
+                    response.extend(self.get_strategy(target_pid).choose_triggers(ti, pcs.get_state()));
+                }
+                response.retain(|tr| match tr.action {
+                    TriggerAction::ReduceDamage(ad, _) => ad.applies_to(&dist),
+                    _ => true,
+                });
+                // the reaction might already be used up, which isn't an error here
+                let response = self.affordable_responses(pcs, target_pid, response);
+                self.resolve_triggers(pcs, target_pid, &response)?;
+                for tr in response {
+                    if let TriggerAction::ReduceDamage(_, dr) = tr.action {
+                        reductions.push(dr);
+                    }
+                }
+            }
+        }
+        Ok(reductions)
+    }
+
+    fn resolve_triggers(&self, pcs: &mut ProbCombatState<'pm, P>, pid: ParticipantId, response: &Vec<TriggerResponse>) -> ResultCSE {
+        let cost = self.validate_trigger_cost(pcs, pid, response);
+        if cost.is_some() {
+            pcs.spend_resource_cost(pid, cost.unwrap());
+            self.resolve_add_resource_triggers(pcs, pid, response);
+            self.resolve_give_cond_triggers(pcs, pid, response);
+            self.resolve_temp_hp_triggers(pcs, pid, response);
+            Ok(())
+        } else {
+            Err(CSError::InvalidTriggerResponse(pid))
+        }
+    }
+
+    fn affordable_responses(&self, pcs: &ProbCombatState<'pm, P>, pid: ParticipantId, response: Vec<TriggerResponse>) -> Vec<TriggerResponse> {
+        let mut affordable = Vec::with_capacity(response.len());
+        for tr in response {
+            affordable.push(tr);
+            if self.validate_trigger_cost(pcs, pid, &affordable).is_none() {
+                affordable.pop();
+            }
+        }
+        affordable
+    }
+
     fn resolve_add_resource_triggers(&self, pcs: &mut ProbCombatState<'pm, P>, pid: ParticipantId, response: &Vec<TriggerResponse>) {
         for tr in response {
             if let TriggerAction::AddResource(rn, amount) = tr.action {
@@ -616,6 +797,14 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
         }
     }
 
+    fn resolve_temp_hp_triggers(&self, pcs: &mut ProbCombatState<'pm, P>, pid: ParticipantId, response: &Vec<TriggerResponse>) {
+        for tr in response {
+            if let TriggerAction::GainTempHP(amount) = tr.action {
+                pcs.add_temp_hp(pid, amount);
+            }
+        }
+    }
+
     fn resolve_dmg_bonus_triggers(&self, response: &Vec<TriggerResponse>) -> Vec<DamageTerm> {
         let mut v = Vec::with_capacity(response.len());
         for tr in response {
@@ -647,44 +836,52 @@ impl<'sm, 'pm, P: RVProb> EncounterSimulator<'sm, 'pm, P> {
 mod tests {
     use std::collections::HashSet;
     use std::rc::Rc;
-    use num::{BigRational, One, Rational64};
+    use num::{BigRational, One, Rational64, Zero};
 
     use character_builder::Character;
     use character_builder::classes::{ChooseSubClass, ClassName};
     use character_builder::classes::ranger::HorizonWalkerRanger;
+    use character_builder::classes::rogue::ScoutRogue;
     use character_builder::classes::wizard::ConjurationWizard;
     use character_builder::equipment::{Armor, Equipment, OffHand, Weapon};
-    use character_builder::feature::AbilityScoreIncrease;
-    use character_builder::feature::feats::GreatWeaponMaster;
+    use character_builder::feature::{AbilityScoreIncrease, LayOnHands};
+    use character_builder::feature::feats::{GreatWeaponMaster, HeavyArmorMaster};
     use character_builder::feature::fighting_style::{FightingStyle, FightingStyles};
+    use character_builder::spellcasting::first_lvl_spells::HealingWordSpell;
     use character_builder::spellcasting::fourth_lvl_spells::GreaterInvisibilitySpell;
-    use character_builder::spellcasting::third_lvl_spells::HasteSpell;
+    use character_builder::spellcasting::third_lvl_spells::{FireBallSpell, HasteSpell};
     use combat_core::ability_scores::{Ability, AbilityScores};
-    use combat_core::actions::{ActionName, AttackType};
+    use combat_core::actions::{ActionName, ActionType, AttackType};
     use combat_core::attack::{Attack, AttackResult};
     use combat_core::attack::basic_attack::BasicAttack;
     use combat_core::combat_event::{CombatEvent, CombatTiming, RoundId};
-    use combat_core::conditions::ConditionName;
-    use combat_core::D20RollType;
-    use combat_core::damage::{DamageDice, DamageType};
-    use combat_core::health::Health;
+    use combat_core::conditions::{AttackDistance, ConditionName};
+    use combat_core::{CCError, D20RollType};
+    use combat_core::damage::{DamageDefenses, DamageDice, DamageType};
+    use combat_core::health::{DeathSaveResult, Health};
     use combat_core::participant::{Participant, ParticipantId, ParticipantManager};
     use combat_core::resources::{ResourceActionType, ResourceName};
     use combat_core::spells::SpellSlot;
     use combat_core::strategy::basic_atk_str::BasicAtkStrBuilder;
     use combat_core::strategy::basic_strategies::DoNothingBuilder;
     use combat_core::strategy::favored_foe_str::FavoredFoeStrBldr;
+    use combat_core::strategy::fireball_str::FireBallStrBuilder;
     use combat_core::strategy::greater_invis_str::GreaterInvisStrBuilder;
     use combat_core::strategy::gwm_str::GWMStrBldr;
     use combat_core::strategy::haste_str::HasteStrBuilder;
+    use combat_core::strategy::heal_str::HealStrBuilder;
     use combat_core::strategy::linear_str::{LinearStrategyBuilder, PairStrBuilder};
     use combat_core::strategy::second_wind_str::SecondWindStrBuilder;
     use combat_core::strategy::StrategyManager;
     use rand_var::num_rand_var::NumRandVar;
-    use rand_var::vec_rand_var::{VRV64, VRVBig};
+    use rand_var::vec_rand_var::{VecRandVar, VRV64, VRVBig, VRVF64};
     use rand_var::rand_var::RandVar;
+    use rand_var::rand_var::approximation::Approximation;
+    use rand_var::rand_var::prob_type::Prob;
 
-    use crate::encounter_simulator::{EncounterSimulator, ES64, ESBig};
+    use crate::combat_result_rv::CombatResultRV;
+    use crate::combat_state_rv::prob_combat_state::ProbCombatState;
+    use crate::encounter_simulator::{EncounterSimulator, ES64, ESBig, ESF64};
     use crate::monster::Monster;
     use crate::player::Player;
     use crate::target_dummy::TargetDummy;
@@ -878,12 +1075,49 @@ mod tests {
             let dmg_rv = cs_rv.get_dmg(orc_pid);
             let atk_dmg: VRV64 = fighter
                 .get_weapon_attack().unwrap()
-                .get_attack_dmg_rv(D20RollType::Normal, orc.get_ac(), orc.get_resistances()).unwrap()
+                .get_attack_dmg_rv(D20RollType::Normal, orc.get_ac(), orc.get_dmg_defenses()).unwrap()
                 .cap_ub(orc.get_max_hp()).unwrap();
             assert_eq!(atk_dmg, dmg_rv);
         }
     }
 
+    #[test]
+    fn fighter_vs_orc_stats_f64() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
+        let orc = TargetDummy::new(15, 13);
+
+        let player = Player::from(fighter.clone());
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.add_enemy(Box::new(orc.clone())).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let mut em: ESF64 = EncounterSimulator::new(&sm).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+
+        {
+            let cs_rv = em.get_state_rv();
+            assert_eq!(7, cs_rv.len());
+
+            let orc_pid = ParticipantId(1);
+            let dmg_rv = cs_rv.get_dmg(orc_pid);
+            let atk_dmg: VRVF64 = fighter
+                .get_weapon_attack().unwrap()
+                .get_attack_dmg_rv(D20RollType::Normal, orc.get_ac(), orc.get_dmg_defenses()).unwrap()
+                .cap_ub(orc.get_max_hp()).unwrap();
+            assert_eq!(atk_dmg.lower_bound(), dmg_rv.lower_bound());
+            assert_eq!(atk_dmg.upper_bound(), dmg_rv.upper_bound());
+            for x in atk_dmg.get_keys() {
+                assert!(atk_dmg.pdf(x).approx_eq(&dmg_rv.pdf(x)));
+            }
+        }
+    }
+
     #[test]
     fn fighter_vs_orc_merged() {
         let mut fighter = get_test_fighter_lvl_0();
@@ -912,11 +1146,53 @@ mod tests {
         let dmg_rv = cs_rv.get_dmg(orc_pid);
         let wa = fighter.get_weapon_attack().unwrap();
         let atk_dmg: VRV64 = wa
-            .get_attack_dmg_rv(D20RollType::Normal, orc.get_ac(), orc.get_resistances()).unwrap()
+            .get_attack_dmg_rv(D20RollType::Normal, orc.get_ac(), orc.get_dmg_defenses()).unwrap()
             .cap_ub(orc.get_max_hp()).unwrap();
         assert_eq!(atk_dmg, dmg_rv);
     }
 
+    #[test]
+    fn fighter_vs_dummy_approx() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
+        let dummy = TargetDummy::new(isize::MAX, 13);
+
+        let player = Player::from(fighter.clone());
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.add_enemy(Box::new(dummy)).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let dummy_pid = ParticipantId(1);
+        let mut exact: ES64 = EncounterSimulator::new(&sm).unwrap();
+        exact.set_do_merges(true);
+        exact.simulate_n_rounds(2).unwrap();
+        let exact_dmg = exact.get_state_rv().get_dmg(dummy_pid);
+        assert_eq!(Rational64::zero(), *exact.get_approx_error());
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.set_do_merges(true);
+        em.set_approximation(Some(Approximation::Prune(Rational64::new(1, 1000))));
+        em.simulate_n_rounds(2).unwrap();
+        let approx_dmg = em.get_state_rv().get_dmg(dummy_pid);
+        let error = *em.get_approx_error();
+        assert!(error > Rational64::zero());
+        assert!(error < Rational64::new(1, 20));
+        assert!(exact_dmg.total_variation(&approx_dmg) <= error);
+        assert!(approx_dmg.upper_bound() < exact_dmg.upper_bound());
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.set_do_merges(true);
+        em.set_approximation(Some(Approximation::Bucket(10)));
+        em.simulate_n_rounds(2).unwrap();
+        let bucketed_dmg = em.get_state_rv().get_dmg(dummy_pid);
+        assert!(exact_dmg.total_variation(&bucketed_dmg) <= *em.get_approx_error());
+    }
+
     #[test]
     fn fighter_vs_orc_strategy() {
         let mut fighter = get_test_fighter_lvl_0();
@@ -941,7 +1217,7 @@ mod tests {
 
         {
             let cs_rv = em.get_state_rv();
-            assert_eq!(59, cs_rv.len());
+            assert_eq!(76, cs_rv.len());
 
             let branch = cs_rv.get_pcs(16);
             let full_log = branch.get_state().get_logs().get_all_events();
@@ -970,6 +1246,423 @@ mod tests {
         }
     }
 
+    #[test]
+    fn death_save_test() {
+        // every hit drops the player, but only a hit while they're down is massive damage
+        let player = TargetDummy::new(10, 10);
+        let ba = BasicAttack::new(100, DamageType::Slashing, 9, DamageDice::D4, 1);
+        let orc = Monster::new(15, 13, 2, ba, 1);
+
+        let mut pm = ParticipantManager::new();
+        pm.add_enemy(Box::new(orc)).unwrap();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let player_pid = ParticipantId(1);
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+        {
+            let cs_rv = em.get_state_rv();
+            let mut revived = Rational64::zero();
+            for pcs in cs_rv.get_states() {
+                assert!(pcs.is_alive(player_pid));
+                // only a natural 20 gets them back up
+                if pcs.get_health(player_pid) == Health::Bloodied {
+                    assert!(pcs.get_state().get_logs().get_all_events().contains(&CombatEvent::DeathSave(DeathSaveResult::CritPass)));
+                    assert_eq!(&VRV64::new_constant(9).unwrap(), pcs.get_dmg(player_pid));
+                    assert!(!pcs.get_cm(player_pid).has_condition(&ConditionName::Unconscious));
+                    revived += pcs.get_prob();
+                } else if pcs.get_health(player_pid) == Health::ZeroHP {
+                    assert!(pcs.get_cm(player_pid).has_condition(&ConditionName::Unconscious));
+                    assert!(pcs.get_cm(player_pid).has_condition(&ConditionName::Prone));
+                }
+            }
+            assert_eq!(Rational64::new(19, 400), revived);
+        }
+
+        em.simulate_n_rounds(1).unwrap();
+        let cr_rv: CombatResultRV<Rational64> = em.get_state_rv().clone().into();
+        // the orc has advantage against them while they're down (or prone after getting back up)
+        assert_eq!(Rational64::new(2998523, 3200000), cr_rv.get_death_prob(player_pid));
+    }
+
+    #[test]
+    fn temp_hp_test() {
+        let dummy = TargetDummy::new(20, 10);
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(dummy)).unwrap();
+        pm.compile();
+
+        let pid = ParticipantId(0);
+        let mut pcs: ProbCombatState<Rational64> = ProbCombatState::new(&pm);
+        pcs.add_temp_hp(pid, 5);
+        // temp hp don't stack
+        pcs.add_temp_hp(pid, 3);
+        assert_eq!(5, pcs.get_temp_hp(pid));
+
+        let d8: VRV64 = DamageDice::D8.get_rv();
+        let results = pcs.add_dmg(&d8, pid, false).unwrap();
+        assert_eq!(5, results.len());
+        let mut total = Rational64::zero();
+        for pcs in results {
+            total += pcs.get_prob();
+            if pcs.get_temp_hp(pid) > 0 {
+                assert_eq!(&Rational64::new(1, 8), pcs.get_prob());
+                assert_eq!(&VRV64::new_constant(0).unwrap(), pcs.get_dmg(pid));
+            } else {
+                assert_eq!(&Rational64::new(1, 2), pcs.get_prob());
+                let leftover: VRV64 = VecRandVar::new_dice(4).unwrap().add_const(-1);
+                assert_eq!(&leftover, pcs.get_dmg(pid));
+            }
+        }
+        assert_eq!(Rational64::one(), total);
+    }
+
+    #[test]
+    fn uncanny_dodge_test() {
+        let name = String::from("RogueMan");
+        let equipment = Equipment::new(
+            Armor::studded_leather(),
+            Weapon::dagger(),
+            OffHand::Weapon(Weapon::dagger())
+        );
+        let mut rogue = Character::new(name, get_str_based(), equipment);
+        rogue.level_up(ClassName::Rogue, vec!()).unwrap();
+        rogue.level_up_basic().unwrap();
+        rogue.level_up(ClassName::Rogue, vec!(Box::new(ChooseSubClass(Rc::new(ScoutRogue))))).unwrap();
+        rogue.level_up(ClassName::Rogue, vec!(Box::new(AbilityScoreIncrease::from(Ability::DEX)))).unwrap();
+        rogue.level_up_basic().unwrap();
+        let player = Player::from(rogue);
+        // always hits for 3-6 (or 4-10 on a crit)
+        let ba = BasicAttack::new(100, DamageType::Slashing, 2, DamageDice::D4, 1);
+        let orc = Monster::new(15, 13, 2, ba, 2);
+
+        let mut pm = ParticipantManager::new();
+        pm.add_enemy(Box::new(orc)).unwrap();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+        let cr_rv: CombatResultRV<Rational64> = em.get_state_rv().clone().into();
+        let dmg = cr_rv.get_dmg(ParticipantId(1));
+        // a halved 3 is the least you can take from a hit
+        assert!(dmg.pdf(1) > Rational64::zero());
+        // only the first hit gets halved, since there's only one reaction
+        assert_eq!(5 + 10, dmg.upper_bound());
+    }
+
+    #[test]
+    fn heavy_armor_master_test() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(HeavyArmorMaster))).unwrap();
+        let player = Player::from(fighter);
+        let ba = BasicAttack::new(100, DamageType::Slashing, 2, DamageDice::D4, 1);
+        let orc = Monster::new(15, 13, 2, ba, 1);
+
+        let mut pm = ParticipantManager::new();
+        pm.add_enemy(Box::new(orc)).unwrap();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+        let cr_rv: CombatResultRV<Rational64> = em.get_state_rv().clone().into();
+        let dmg = cr_rv.get_dmg(ParticipantId(1));
+        assert_eq!(0, dmg.lower_bound());
+        assert_eq!(7, dmg.upper_bound());
+    }
+
+    #[test]
+    fn heavy_armor_master_magic_test() {
+        let fire_atk = BasicAttack::new(100, DamageType::Fire, 2, DamageDice::D4, 1);
+        let mut magic_atk = BasicAttack::new(100, DamageType::Piercing, 2, DamageDice::D4, 1);
+        magic_atk.set_magical(true);
+        // neither of these is reduced, so a crit can still do 2d4+2
+        for atk in [fire_atk, magic_atk] {
+            let mut fighter = get_test_fighter_lvl_0();
+            fighter.level_up(ClassName::Fighter, vec!(Box::new(HeavyArmorMaster))).unwrap();
+            let player = Player::from(fighter);
+            let orc = Monster::new(15, 13, 2, atk, 1);
+
+            let mut pm = ParticipantManager::new();
+            pm.add_enemy(Box::new(orc)).unwrap();
+            pm.add_player(Box::new(player)).unwrap();
+            pm.compile();
+
+            let mut sm = StrategyManager::new(&pm).unwrap();
+            sm.add_participant(BasicAtkStrBuilder).unwrap();
+            sm.add_participant(DoNothingBuilder).unwrap();
+
+            let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+            em.simulate_n_rounds(1).unwrap();
+            let cr_rv: CombatResultRV<Rational64> = em.get_state_rv().clone().into();
+            let dmg = cr_rv.get_dmg(ParticipantId(1));
+            assert_eq!(0, dmg.lower_bound());
+            assert_eq!(10, dmg.upper_bound());
+        }
+    }
+
+    #[test]
+    fn healing_word_test() {
+        let dummy = TargetDummy::new(10, 10);
+        let ba = BasicAttack::new(100, DamageType::Slashing, 9, DamageDice::D4, 1);
+        let orc = Monster::new(15, 13, 2, ba, 1);
+        let mut wizard = get_test_fighter_lvl_0();
+        wizard.level_up(ClassName::Wizard, vec!(Box::new(HealingWordSpell(Ability::WIS)))).unwrap();
+
+        let mut pm = ParticipantManager::new();
+        pm.add_enemy(Box::new(orc)).unwrap();
+        pm.add_player(Box::new(dummy)).unwrap();
+        pm.add_player(Box::new(Player::from(wizard))).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+        sm.add_participant(HealStrBuilder).unwrap();
+
+        let dummy_pid = ParticipantId(1);
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+        for pcs in em.get_state_rv().get_states() {
+            assert_ne!(Health::ZeroHP, pcs.get_health(dummy_pid));
+            // healed for 1d4 + 1 from max damage
+            assert!(pcs.get_dmg(dummy_pid).upper_bound() <= 8);
+        }
+        // any hit gets healed, even after a natural 20 on the death save
+        let cr_rv: CombatResultRV<Rational64> = em.get_state_rv().clone().into();
+        let slots = cr_rv.get_resource_rv(ParticipantId(2), ResourceName::SS(SpellSlot::First)).unwrap();
+        assert_eq!(Rational64::new(19, 20), slots.pdf(1));
+    }
+
+    #[test]
+    fn lay_on_hands_test() {
+        let dummy = TargetDummy::new(10, 10);
+        let ba = BasicAttack::new(100, DamageType::Slashing, 9, DamageDice::D4, 1);
+        let orc = Monster::new(15, 13, 2, ba, 1);
+        let mut paladin = get_test_fighter_lvl_0();
+        paladin.level_up(ClassName::Fighter, vec!(Box::new(LayOnHands(5)))).unwrap();
+
+        let mut pm = ParticipantManager::new();
+        pm.add_enemy(Box::new(orc)).unwrap();
+        pm.add_player(Box::new(dummy)).unwrap();
+        pm.add_player(Box::new(Player::from(paladin))).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+        sm.add_participant(HealStrBuilder).unwrap();
+
+        let dummy_pid = ParticipantId(1);
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+        let cr_rv: CombatResultRV<Rational64> = em.get_state_rv().clone().into();
+        // the whole pool goes into getting them back up, unless they got up on their own with 1 hp
+        let dmg = cr_rv.get_dmg(dummy_pid);
+        assert_eq!(Rational64::new(361, 400), dmg.pdf(5));
+        assert_eq!(Rational64::new(19, 400), dmg.pdf(4));
+        let pool = cr_rv.get_resource_rv(ParticipantId(2), ResourceName::LayOnHands).unwrap();
+        assert_eq!(Rational64::new(19, 20), pool.pdf(0));
+    }
+
+    #[test]
+    fn stunned_test() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
+        let dummy = TargetDummy::new(isize::MAX, 14);
+
+        let player = Player::from(fighter);
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.add_enemy(Box::new(dummy)).unwrap();
+        pm.compile();
+        let fighter_pid = ParticipantId(0);
+        let dummy_pid = ParticipantId(1);
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        {
+            let pcs = em.cs_rv.get_pcs_mut(0);
+            pcs.apply_default_condition(fighter_pid, ConditionName::Concentration).unwrap();
+            pcs.apply_default_condition(fighter_pid, ConditionName::Stunned).unwrap();
+            // being incapacitated ends concentration
+            assert!(!pcs.get_cm(fighter_pid).has_condition(&ConditionName::Concentration));
+        }
+        em.simulate_n_rounds(1).unwrap();
+
+        let cs_rv = em.get_state_rv();
+        assert_eq!(1, cs_rv.len());
+        let pcs = cs_rv.get_pcs(0);
+        assert_eq!(&VRV64::new_constant(0).unwrap(), pcs.get_dmg(dummy_pid));
+        assert!(pcs.get_rm(fighter_pid).get_current(ResourceName::Movement) == 0);
+        assert!(pcs.get_cm(fighter_pid).auto_fails_save(Ability::STR));
+        assert!(!pcs.get_cm(fighter_pid).auto_fails_save(Ability::CON));
+    }
+
+    #[test]
+    fn shared_speed_lock_test() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
+        let player = Player::from(fighter);
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.compile();
+        let pid = ParticipantId(0);
+        let is_locked = |pcs: &ProbCombatState<Rational64>| pcs.get_rm(pid).get_resource(ResourceName::Movement).unwrap().is_locked();
+
+        let mut pcs: ProbCombatState<Rational64> = ProbCombatState::new(&pm);
+        pcs.apply_default_condition(pid, ConditionName::Grappled).unwrap();
+        let hp = pcs.get_max_hp(pid);
+        let pcs = pcs.add_dmg(&VRV64::new_constant(hp).unwrap(), pid, false).unwrap().pop().unwrap();
+        assert!(pcs.get_cm(pid).has_condition(&ConditionName::Unconscious));
+        assert!(is_locked(&pcs));
+
+        // waking up doesn't free them from the grapple
+        let mut pcs = pcs.heal(&VRV64::new_constant(-1).unwrap(), pid, false).unwrap().pop().unwrap();
+        assert!(!pcs.get_cm(pid).has_condition(&ConditionName::Unconscious));
+        assert!(is_locked(&pcs));
+        pcs.remove_condition(pid, ConditionName::Grappled, ActionType::FreeAction);
+        assert!(!is_locked(&pcs));
+    }
+
+    #[test]
+    fn exhaustion_test() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
+        let player = Player::from(fighter);
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.compile();
+        let pid = ParticipantId(0);
+        let is_locked = |pcs: &ProbCombatState<Rational64>| pcs.get_rm(pid).get_resource(ResourceName::Movement).unwrap().is_locked();
+
+        let mut pcs: ProbCombatState<Rational64> = ProbCombatState::new(&pm);
+        assert!(matches!(pcs.apply_default_condition(pid, ConditionName::Exhaustion(0)), Err(CCError::InvalidExhaustion(0))));
+        assert!(matches!(pcs.apply_default_condition(pid, ConditionName::Exhaustion(7)), Err(CCError::InvalidExhaustion(7))));
+        assert_eq!(None, pcs.get_cm(pid).get_exhaustion());
+
+        pcs.apply_default_condition(pid, ConditionName::Exhaustion(1)).unwrap();
+        pcs.apply_default_condition(pid, ConditionName::Exhaustion(5)).unwrap();
+        assert_eq!(1, pcs.get_cm(pid).len());
+        assert_eq!(Some(5), pcs.get_cm(pid).get_exhaustion());
+        assert!(is_locked(&pcs));
+        // finishing a long rest
+        pcs.apply_default_condition(pid, ConditionName::Exhaustion(4)).unwrap();
+        assert_eq!(Some(4), pcs.get_cm(pid).get_exhaustion());
+        assert!(!pcs.get_cm(pid).has_condition(&ConditionName::Exhaustion(5)));
+        assert!(!is_locked(&pcs));
+
+        assert!(pcs.is_alive(pid));
+        pcs.apply_default_condition(pid, ConditionName::Exhaustion(6)).unwrap();
+        assert_eq!(Health::Dead, pcs.get_health(pid));
+        assert_eq!(Some(CombatEvent::HP(pid, Health::Dead)), pcs.get_last_event());
+    }
+
+    #[test]
+    fn paralyzed_auto_crit_test() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)))).unwrap();
+        let dummy = TargetDummy::new(isize::MAX, 14);
+
+        let player = Player::from(fighter.clone());
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.add_enemy(Box::new(dummy.clone())).unwrap();
+        pm.compile();
+        let dummy_pid = ParticipantId(1);
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(BasicAtkStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.cs_rv.get_pcs_mut(0).apply_default_condition(dummy_pid, ConditionName::Paralyzed).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+
+        let cs_rv = em.get_state_rv();
+        assert_eq!(2, cs_rv.len());
+        let miss: Rational64 = fighter.get_weapon_attack().unwrap()
+            .get_attack_result_rv(D20RollType::Advantage, dummy.get_ac()).unwrap()
+            .pdf(AttackResult::Miss);
+        for pcs in cs_rv.get_states() {
+            let events = pcs.get_state().get_logs().get_all_events();
+            assert!(!events.contains(&CombatEvent::AR(AttackResult::Hit)));
+            if events.contains(&CombatEvent::AR(AttackResult::Crit)) {
+                assert_eq!(Rational64::one() - miss, *pcs.get_prob());
+            } else {
+                assert_eq!(miss, *pcs.get_prob());
+            }
+        }
+        // only from within 5 feet
+        assert!(cs_rv.get_pcs(0).get_cm(dummy_pid).auto_crits(AttackDistance::Within5Ft));
+        assert!(!cs_rv.get_pcs(0).get_cm(dummy_pid).auto_crits(AttackDistance::Beyond5Ft));
+        assert!(!cs_rv.get_pcs(0).get_cm(dummy_pid).auto_crits(AttackDistance::Any));
+    }
+
+    #[test]
+    fn paralyzed_save_test() {
+        let name = String::from("elaine");
+        let ability_scores = AbilityScores::new(10,14,14,16,12,8);
+        let equipment = Equipment::new(
+            Armor::no_armor(),
+            Weapon::quarterstaff(),
+            OffHand::Free,
+        );
+        let mut wizard = Character::new(name, ability_scores, equipment);
+        wizard.level_up(ClassName::Wizard, vec!()).unwrap();
+        wizard.level_up(ClassName::Wizard, vec!(Box::new(ChooseSubClass(Rc::new(ConjurationWizard))))).unwrap();
+        wizard.level_up_basic().unwrap();
+        wizard.level_up(ClassName::Wizard, vec!(Box::new(AbilityScoreIncrease::from(Ability::INT)))).unwrap();
+        wizard.level_up(ClassName::Wizard, vec!(Box::new(FireBallSpell(Ability::INT)))).unwrap();
+        let dummy = TargetDummy::new(isize::MAX, 15);
+
+        let player = Player::from(wizard);
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.add_enemy(Box::new(dummy)).unwrap();
+        pm.compile();
+        let dummy_pid = ParticipantId(1);
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(FireBallStrBuilder).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.cs_rv.get_pcs_mut(0).apply_default_condition(dummy_pid, ConditionName::Paralyzed).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+
+        // the DEX save always fails, so it's always the full 8d6
+        let cs_rv = em.get_state_rv();
+        assert_eq!(1, cs_rv.len());
+        let full_dmg: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(8);
+        assert_eq!(&full_dmg, cs_rv.get_pcs(0).get_dmg(dummy_pid));
+
+        // petrified also fails, but resists the fire
+        let mut em: ES64 = EncounterSimulator::new(&sm).unwrap();
+        em.cs_rv.get_pcs_mut(0).apply_default_condition(dummy_pid, ConditionName::Petrified).unwrap();
+        em.simulate_n_rounds(1).unwrap();
+        let cs_rv = em.get_state_rv();
+        assert_eq!(1, cs_rv.len());
+        assert_eq!(&full_dmg.half().unwrap(), cs_rv.get_pcs(0).get_dmg(dummy_pid));
+    }
+
     #[test]
     fn gwm_kill_trigger_test() {
         let mut fighter = get_test_fighter_lvl_0();
@@ -1013,6 +1706,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn downed_kill_trigger_test() {
+        let mut fighter = get_test_fighter_lvl_0();
+        fighter.level_up(ClassName::Fighter, vec!(
+            Box::new(FightingStyle(FightingStyles::GreatWeaponFighting)),
+            Box::new(GreatWeaponMaster)
+        )).unwrap();
+        let player = Player::from(fighter.clone());
+        let ally = TargetDummy::new(10, 10);
+
+        let mut pm = ParticipantManager::new();
+        pm.add_player(Box::new(player)).unwrap();
+        pm.add_player(Box::new(ally)).unwrap();
+        pm.compile();
+
+        let mut sm = StrategyManager::new(&pm).unwrap();
+        sm.add_participant(GWMStrBldr::new(false)).unwrap();
+        sm.add_participant(DoNothingBuilder).unwrap();
+        let em: ES64 = EncounterSimulator::new(&sm).unwrap();
+
+        let fighter_pid = ParticipantId(0);
+        let ally_pid = ParticipantId(1);
+        let bonus_atk = ResourceName::AN(ActionName::BonusGWMAttack);
+        let massive_dmg: VRV64 = VecRandVar::new_constant(10).unwrap();
+        let pcs: ProbCombatState<Rational64> = ProbCombatState::new(&pm);
+        let downed = pcs.add_dmg(&massive_dmg, ally_pid, false).unwrap().pop().unwrap();
+        assert_eq!(Health::ZeroHP, downed.get_health(ally_pid));
+        let dead = downed.add_dmg(&massive_dmg, ally_pid, false).unwrap();
+        assert_eq!(Some(CombatEvent::HP(ally_pid, Health::Dead)), dead[0].get_last_event());
+
+        // they were already down, so this isn't a kill
+        let results = em.handle_on_kill_triggers(dead.clone(), fighter_pid, ally_pid, Health::ZeroHP).unwrap();
+        assert_eq!(0, results[0].get_rm(fighter_pid).get_current(bonus_atk).count().unwrap());
+        let results = em.handle_on_kill_triggers(dead, fighter_pid, ally_pid, Health::Bloodied).unwrap();
+        assert_eq!(1, results[0].get_rm(fighter_pid).get_current(bonus_atk).count().unwrap());
+    }
+
     #[test]
     fn favored_foe_test() {
         let name = String::from("Jason");
@@ -1140,7 +1870,7 @@ mod tests {
                 assert_eq!(hit * conc_save.cdf_exclusive(10), pcs.get_prob().clone());
             }
             let crit: Rational64 = ba.get_ar_rv(D20RollType::Disadvantage, wizard.get_ac() as isize).unwrap().pdf(AttackResult::Crit);
-            let crit_dmg: VRV64 = ba.get_crit_dmg(&HashSet::new(), vec!(), HashSet::new()).unwrap();
+            let crit_dmg: VRV64 = ba.get_crit_dmg(&DamageDefenses::new(), vec!(), HashSet::new()).unwrap();
             assert_eq!(5, crit_dmg.lower_bound());
             assert_eq!(27, crit_dmg.upper_bound());
             let mut keep_conc = crit_dmg.cdf(21) * (Rational64::one() - conc_save.cdf_exclusive(10));
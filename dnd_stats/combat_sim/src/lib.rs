@@ -1,15 +1,22 @@
-use std::fmt::{Debug, Display};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
 use num::{BigRational, Rational64, ToPrimitive};
 
 use character_builder::{CBError, Character};
+use combat_core::actions::ActionName;
 use combat_core::CCError;
 use combat_core::combat_event::CombatEvent;
 use combat_core::participant::{ParticipantId, ParticipantManager};
+use combat_core::resources::ResourceName;
 use combat_core::strategy::{StrategyBuilder, StrategyManager};
 use combat_core::strategy::basic_strategies::RemoveCondBuilder;
-use rand_var::num_rand_var::NumRandVar;
-use rand_var::rand_var::prob_type::RVProb;
+use rand_var::num_rand_var::{NumRandVar, RVSummary};
+use rand_var::rand_var::RandVar;
+use rand_var::rand_var::prob_type::{F64Prob, RVProb};
+use rand_var::rand_var::prob_type::adaptive_rational::AdaptiveRational;
 use rand_var::RVError;
+use rand_var::vec_rand_var::VecRandVar;
 
 use crate::combat_result_rv::CombatResultRV;
 use crate::encounter_simulator::EncounterSimulator;
@@ -26,17 +33,46 @@ pub mod target_dummy;
 
 #[derive(Debug, Clone)]
 pub enum CSError {
-    ActionNotHandled,
-    InvalidTarget,
-    InvalidAction,
+    ActionNotHandled(ParticipantId, ActionName),
+    InvalidTarget(ParticipantId, ActionName),
+    InvalidAction(ParticipantId, ActionName),
     UnknownAction,
     UnknownEvent(CombatEvent),
-    InvalidTriggerResponse,
-    UncappedResource,
+    InvalidTriggerResponse(ParticipantId),
+    UncappedResource(ParticipantId, ResourceName),
     RVE(RVError),
     CCE(CCError),
     CBE(CBError),
 }
+
+impl Display for CSError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CSError::ActionNotHandled(pid, an) => write!(f, "participant {} used {:?}, which the simulator can't handle yet", pid.0, an),
+            CSError::InvalidTarget(pid, an) => write!(f, "participant {} used {:?} on an invalid target", pid.0, an),
+            CSError::InvalidAction(pid, an) => write!(f, "participant {} can't use {:?} like that (e.g.: a spell without a slot)", pid.0, an),
+            CSError::UnknownAction => write!(f, "unknown action"),
+            CSError::UnknownEvent(ce) => write!(f, "no outcome was given for {:?}", ce),
+            CSError::InvalidTriggerResponse(pid) => write!(f, "participant {} responded to a trigger with something they can't afford", pid.0),
+            CSError::UncappedResource(pid, rn) => write!(f, "{:?} of participant {} has no cap, so it has no distribution", rn, pid.0),
+            CSError::RVE(_) => write!(f, "random variable error"),
+            CSError::CCE(_) => write!(f, "combat error"),
+            CSError::CBE(_) => write!(f, "character error"),
+        }
+    }
+}
+
+impl Error for CSError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CSError::RVE(err) => Some(err),
+            CSError::CCE(err) => Some(err),
+            CSError::CBE(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl From<CBError> for CSError {
     fn from(value: CBError) -> Self {
         CSError::CBE(value)
@@ -53,12 +89,15 @@ impl From<RVError> for CSError {
     }
 }
 
-pub struct CombatSimulator<P: RVProb> {
+// defaults to exact probabilities that only pay for big integers once they're needed
+pub struct CombatSimulator<P: RVProb = AdaptiveRational> {
     cr_rv: CombatResultRV<P>,
 }
 
 pub type CS64 = CombatSimulator<Rational64>;
 pub type CSBig = CombatSimulator<BigRational>;
+pub type CSF64 = CombatSimulator<F64Prob>;
+pub type CSAdaptive = CombatSimulator<AdaptiveRational>;
 
 impl<P: RVProb> CombatSimulator<P> {
     pub fn dmg_sponge(character: Character, str_bldr: impl StrategyBuilder, dummy_ac: isize, num_rounds: u8) -> Result<Self, CSError> {
@@ -90,12 +129,25 @@ impl<P: RVProb> CombatSimulator<P> {
         &self.cr_rv
     }
 
+    // the damage dealt to the target
+    pub fn get_dmg(&self) -> VecRandVar<P> {
+        self.cr_rv.get_dmg(ParticipantId(1))
+    }
+
+    pub fn summary(&self) -> RVSummary<isize, P>
+    where
+        P: ToPrimitive
+    {
+        self.get_dmg().summary()
+    }
+
     pub fn describe(&self)
     where
         P: Display + ToPrimitive
     {
-        let dmg_rv = self.cr_rv.get_dmg(ParticipantId(1));
-        dmg_rv.print_stats_f64();
+        let dmg_rv = self.get_dmg();
+        dmg_rv.print_pdf(&|p: P| p.to_f64().unwrap());
+        print!("{}", dmg_rv.summary());
     }
 }
 
@@ -119,7 +171,7 @@ mod tests {
     use character_builder::spellcasting::third_lvl_spells::{FireBallSpell, HasteSpell};
     use combat_core::ability_scores::{Ability, AbilityScores};
     use combat_core::D20RollType;
-    use combat_core::damage::DamageType;
+    use combat_core::damage::{DamageDefenses, DamageType};
     use combat_core::participant::ParticipantId;
     use combat_core::strategy::action_surge_str::ActionSurgeStrBuilder;
     use combat_core::strategy::basic_atk_str::BasicAtkStrBuilder;
@@ -136,9 +188,11 @@ mod tests {
     use combat_core::strategy::StrategyBuilder;
     use rand_var::num_rand_var::NumRandVar;
     use rand_var::vec_rand_var::VRV64;
+    use rand_var::rand_var::prob_type::{F64Prob, Prob};
+    use rand_var::rand_var::prob_type::adaptive_rational::AdaptiveRational;
     use rand_var::rand_var::RandVar;
 
-    use crate::CombatSimulator;
+    use crate::{CombatSimulator, CSF64};
     use crate::target_dummy::TargetDummy;
 
     pub fn get_str_based() -> AbilityScores {
@@ -185,7 +239,7 @@ mod tests {
         let dummy_data = cr_rv.get_pcr(0).get_participant_data().get(1).unwrap();
 
         let dmg_rv = cr_rv.get_dmg(ParticipantId(1));
-        let atk_dmg: VRV64 = fighter.get_weapon_attack().unwrap().get_attack_dmg_rv(D20RollType::Normal, dummy_data.ac, &dummy_data.resistances).unwrap();
+        let atk_dmg: VRV64 = fighter.get_weapon_attack().unwrap().get_attack_dmg_rv(D20RollType::Normal, dummy_data.ac, &dummy_data.defenses).unwrap();
         assert_eq!(atk_dmg, dmg_rv);
 
         let cs = CombatSimulator::dmg_sponge(fighter.clone(), BasicAtkStrBuilder, 14, 2).unwrap();
@@ -240,6 +294,21 @@ mod tests {
             // correct, so I'll just take the code's word for it I guess.
             assert_eq!(Rational64::new(624639, 80000), dmg.expected_value());
         }
+
+        let cs: CSF64 = CombatSimulator::dmg_sponge(fighter.clone(), ShieldMasterStrBuilder, 14, 1).unwrap();
+        let dmg = cs.get_cr_rv().get_dmg(ParticipantId(1));
+        assert_eq!(0, dmg.lower_bound());
+        assert_eq!(21, dmg.upper_bound());
+        assert!(F64Prob(624639.0 / 80000.0).approx_eq(&dmg.expected_value()));
+
+        let cs: CombatSimulator = CombatSimulator::dmg_sponge(fighter.clone(), ShieldMasterStrBuilder, 14, 1).unwrap();
+        let dmg = cs.get_cr_rv().get_dmg(ParticipantId(1));
+        assert_eq!(AdaptiveRational::from(Rational64::new(624639, 80000)), dmg.expected_value());
+        let summary = cs.summary();
+        assert_eq!(0, summary.lower_bound);
+        assert_eq!(21, summary.upper_bound);
+        assert_eq!(dmg.expected_value(), summary.expected_value);
+        assert_eq!(dmg.median(), summary.median);
     }
 
     #[test]
@@ -282,6 +351,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn planar_warrior_immunity_test() {
+        let name = String::from("WorldHopper");
+        let ability_scores = get_dex_based();
+        let equipment = Equipment::new(
+            Armor::studded_leather(),
+            Weapon::longbow(),
+            OffHand::Free,
+        );
+        let mut ranger = Character::new(name, ability_scores, equipment);
+        ranger.level_up(ClassName::Ranger, vec!()).unwrap();
+        ranger.level_up(ClassName::Ranger, vec!(Box::new(FightingStyle(FightingStyles::Archery)))).unwrap();
+        ranger.level_up(ClassName::Ranger, vec!(Box::new(ChooseSubClass(Rc::new(HorizonWalkerRanger))))).unwrap();
+        ranger.level_up(ClassName::Ranger, vec!(Box::new(AbilityScoreIncrease::from(Ability::DEX)))).unwrap();
+        ranger.level_up_basic().unwrap();
+
+        let player_str = || {
+            let mut player_str = LinearStrategyBuilder::new();
+            player_str.add_str_bldr(Box::new(PlanarWarriorStrBldr));
+            player_str.add_str_bldr(Box::new(BasicAtkStrBuilder));
+            player_str
+        };
+
+        // planar warrior's force damage gets through, the second (piercing) attack doesn't
+        let mut defenses = DamageDefenses::new();
+        defenses.add_immunity(DamageType::Piercing);
+        let immune_dummy = TargetDummy::defended(isize::MAX, 15, defenses.clone());
+
+        let cs: CombatSimulator = CombatSimulator::vs_dummy(ranger.clone(), player_str(), immune_dummy, 1).unwrap();
+        let dmg = cs.get_cr_rv().get_dmg(ParticipantId(1));
+        assert_eq!(0, dmg.lower_bound());
+        // 2*(2D8) + 4 = 36
+        assert_eq!(36, dmg.upper_bound());
+
+        defenses.add_immunity(DamageType::Force);
+        let immune_dummy = TargetDummy::defended(isize::MAX, 15, defenses);
+        let cs: CombatSimulator = CombatSimulator::vs_dummy(ranger, player_str(), immune_dummy, 1).unwrap();
+        let dmg = cs.get_cr_rv().get_dmg(ParticipantId(1));
+        assert_eq!(0, dmg.upper_bound());
+    }
+
     #[test]
     fn great_weapon_master_test() {
         let name = String::from("Cloud");
@@ -1,45 +1,130 @@
 use std::{fs, process};
-use clap::Parser;
-use combat_sim::{CombatSimulator, CSBig};
+use std::error::Error;
+use clap::{Parser, ValueEnum};
+use rand_var::dice_notation::parse_dice;
+use rand_var::num_rand_var::NumRandVar;
+use rand_var::plot::{BarStyle, Chart, PlotKind};
+use rand_var::vec_rand_var::VRV64;
+use combat_sim::CombatSimulator;
 use combat_sim::serialization::PlayerDescription;
 
+#[derive(Debug, Copy, Clone, ValueEnum)]
+enum HistStyle {
+    Ascii,
+    Unicode,
+}
+
+impl From<HistStyle> for BarStyle {
+    fn from(value: HistStyle) -> Self {
+        match value {
+            HistStyle::Ascii => BarStyle::Ascii,
+            HistStyle::Unicode => BarStyle::Unicode,
+        }
+    }
+}
+
 #[derive(Debug, Parser)]
 struct Args {
-    #[arg(short, long)]
-    player_file: String,
+    #[arg(short, long, required_unless_present = "dice")]
+    player_file: Option<String>,
+
+    #[arg(long = "ac", required_unless_present = "dice")]
+    armor_class: Option<isize>,
 
-    #[arg(long = "ac")]
-    armor_class: isize,
+    /// print the stats of a dice expression (e.g.: "2d6+3") instead of running a simulation.
+    /// can be given more than once to compare them.
+    #[arg(short, long)]
+    dice: Vec<String>,
 
     #[arg(short, long, default_value_t = 1)]
     num_rounds: u8,
+
+    /// print a histogram of the result
+    #[arg(long, value_enum)]
+    histogram: Option<HistStyle>,
+
+    /// write a chart of the result to this svg file
+    #[arg(long)]
+    svg: Option<String>,
+
+    /// chart the cdf instead of the pdf
+    #[arg(long)]
+    cdf: bool,
 }
 
+// exit codes (2 is used by clap for bad arguments)
+const EXIT_PLAYER_FILE: i32 = 1;
+const EXIT_PLAYER_JSON: i32 = 3;
+const EXIT_CHARACTER: i32 = 4;
+const EXIT_SIMULATION: i32 = 5;
+const EXIT_DICE: i32 = 6;
+const EXIT_SVG: i32 = 7;
+
+// prints the error and everything that caused it, then exits
+fn fail(msg: &str, err: &dyn Error, hint: &str, code: i32) -> ! {
+    eprintln!("error: {}: {}", msg, err);
+    let mut source = err.source();
+    while let Some(cause) = source {
+        eprintln!("  caused by: {}", cause);
+        source = cause.source();
+    }
+    if !hint.is_empty() {
+        eprintln!("hint: {}", hint);
+    }
+    process::exit(code);
+}
+
+fn output_chart(chart: &Chart<isize>, args: &Args) {
+    if let Some(style) = args.histogram {
+        print!("{}", chart.to_text(50, style.into()));
+    }
+    if let Some(svg) = &args.svg {
+        chart.write_svg(svg, 800, 480).unwrap_or_else(|err| {
+            fail(&format!("couldn't write svg file '{}'", svg), &err, "check that the directory exists and is writable", EXIT_SVG)
+        });
+    }
+}
 
 fn main() {
     let args = Args::parse();
+    let plot_kind = if args.cdf { PlotKind::Cdf } else { PlotKind::Pdf };
+
+    if !args.dice.is_empty() {
+        let mut chart = Chart::new(plot_kind);
+        for dice in args.dice.iter() {
+            let rv: VRV64 = parse_dice(dice).unwrap_or_else(|err| {
+                fail(&format!("couldn't parse dice expression '{}'", dice), &err, "dice look like \"2d6+1d4+3\", \"4d6kh3\" or \"1d20r1\"", EXIT_DICE)
+            });
+            if args.dice.len() > 1 {
+                println!("{}:", dice);
+            }
+            rv.print_stats_f64();
+            chart.add(dice, &rv);
+        }
+        output_chart(&chart, &args);
+        return;
+    }
+    let player_file = args.player_file.clone().unwrap();
+    let armor_class = args.armor_class.unwrap();
 
-    println!("Player file: {}", args.player_file);
-    println!("armor class: {}", args.armor_class);
+    println!("Player file: {}", player_file);
+    println!("armor class: {}", armor_class);
     println!("num rounds: {}", args.num_rounds);
 
-    let player_file = fs::File::open(args.player_file).unwrap_or_else(|err| {
-        eprintln!("Problem opening player file: {}", err);
-        process::exit(1);
+    let file = fs::File::open(&player_file).unwrap_or_else(|err| {
+        fail(&format!("couldn't open player file '{}'", player_file), &err, "check the path given to --player-file", EXIT_PLAYER_FILE)
     });
-    let player_desc: PlayerDescription = serde_json::from_reader(player_file).unwrap_or_else(|err| {
-        eprintln!("Problem serializing player: {}", err);
-        process::exit(2);
+    let player_desc: PlayerDescription = serde_json::from_reader(file).unwrap_or_else(|err| {
+        fail(&format!("couldn't read player file '{}'", player_file), &err, "see the files in player_files/ for examples", EXIT_PLAYER_JSON)
     });
     let character = player_desc.get_player().unwrap_or_else(|err| {
-        eprintln!("Problem creating character: {:?}", err);
-        process::exit(3);
+        fail("couldn't build the character", &err, "check the classes, levels, feats and equipment in the player file", EXIT_CHARACTER)
     });
 
-    let combat_sim: CSBig = CombatSimulator::dmg_sponge(character, player_desc.get_str_bldr().clone(), args.armor_class, args.num_rounds).unwrap_or_else(|err| {
-        eprintln!("Problem running combat simulation: {:?}", err);
-        process::exit(4);
+    let combat_sim: CombatSimulator = CombatSimulator::dmg_sponge(character, player_desc.get_str_bldr().clone(), armor_class, args.num_rounds).unwrap_or_else(|err| {
+        fail("the combat simulation failed", &err, "check the strategy in the player file", EXIT_SIMULATION)
     });
 
     combat_sim.describe();
+    output_chart(&Chart::new(plot_kind).with("damage", &combat_sim.get_dmg()), &args);
 }
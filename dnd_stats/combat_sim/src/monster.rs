@@ -1,11 +1,10 @@
-use std::collections::HashSet;
 use std::fmt::Debug;
 
 use combat_core::ability_scores::{Ability, AbilityScores};
 use combat_core::actions::{ActionManager, ActionName, ActionType, AttackType, CombatAction, CombatOption, register_pid};
 use combat_core::attack::basic_attack::BasicAttack;
 use combat_core::conditions::ConditionManager;
-use combat_core::damage::DamageType;
+use combat_core::damage::DamageDefenses;
 use combat_core::participant::{Participant, ParticipantId};
 use combat_core::resources::{ResourceActionType, ResourceManager, ResourceName};
 use combat_core::skills::SkillManager;
@@ -16,7 +15,7 @@ pub struct Monster {
     max_hp: isize,
     ac: isize,
     prof: isize,
-    resistances: HashSet<DamageType>,
+    defenses: DamageDefenses,
     ability_scores: AbilityScores,
     skill_manager: SkillManager,
     action_manager: ActionManager,
@@ -30,7 +29,7 @@ impl Monster {
             max_hp,
             ac,
             prof,
-            resistances: HashSet::new(),
+            defenses: DamageDefenses::new(),
             ability_scores: ability_scores_by_prof(prof as u8, Ability::STR),
             skill_manager: SkillManager::new(),
             action_manager: create_basic_attack_am(ba, num_attacks),
@@ -38,6 +37,14 @@ impl Monster {
             condition_manager: ConditionManager::new(),
         }
     }
+
+    pub fn set_dmg_defenses(&mut self, defenses: DamageDefenses) {
+        self.defenses = defenses;
+    }
+
+    pub fn get_dmg_defenses_mut(&mut self) -> &mut DamageDefenses {
+        &mut self.defenses
+    }
 }
 
 // finds the first cr that gives at least a given AC
@@ -120,8 +127,8 @@ impl Participant for Monster {
         self.prof
     }
 
-    fn get_resistances(&self) -> &HashSet<DamageType> {
-        &self.resistances
+    fn get_dmg_defenses(&self) -> &DamageDefenses {
+        &self.defenses
     }
 
     fn get_ability_scores(&self) -> &AbilityScores {
@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 use std::fmt::Debug;
 
 use character_builder::Character;
@@ -7,7 +6,7 @@ use combat_core::ability_scores::AbilityScores;
 use combat_core::actions::{ActionManager, CombatAction, CombatOption, register_pid};
 use combat_core::attack::basic_attack::BasicAttack;
 use combat_core::conditions::ConditionManager;
-use combat_core::damage::DamageType;
+use combat_core::damage::DamageDefenses;
 use combat_core::damage::dice_expr::DiceExpression;
 use combat_core::participant::{Participant, ParticipantId};
 use combat_core::resources::ResourceManager;
@@ -21,7 +20,7 @@ pub struct Player {
     ac: isize,
     max_hp: isize,
     prof: isize,
-    resistances: HashSet<DamageType>,
+    defenses: DamageDefenses,
     ability_scores: AbilityScores,
     skill_manager: SkillManager,
     action_manager: ActionManager,
@@ -50,6 +49,8 @@ impl From<Character> for Player {
                 CombatAction::Attack(wa) => CombatAction::Attack(wa.into()),
                 CombatAction::SelfHeal(cde) => CombatAction::SelfHeal(cde.into()),
                 CombatAction::GainResource(rn, aa) => CombatAction::GainResource(rn, aa),
+                CombatAction::GainTempHP(amount) => CombatAction::GainTempHP(amount),
+                CombatAction::HealFromPool(rn) => CombatAction::HealFromPool(rn),
                 CombatAction::ApplyBasicCondition(cn) => CombatAction::ApplyBasicCondition(cn),
                 CombatAction::ApplyComplexCondition(cn, cond) => CombatAction::ApplyComplexCondition(cn, cond),
                 CombatAction::CastSpell => CombatAction::CastSpell,
@@ -67,7 +68,7 @@ impl From<Character> for Player {
             ac: value.get_ac() as isize,
             max_hp: value.get_max_hp(),
             prof: value.get_prof_bonus() as isize,
-            resistances: value.get_resistances().clone(),
+            defenses: value.get_dmg_defenses().clone(),
             ability_scores: value.get_ability_scores().clone(),
             skill_manager: value.get_skills().clone(),
             action_manager: am,
@@ -92,8 +93,8 @@ impl Participant for Player {
         self.prof
     }
 
-    fn get_resistances(&self) -> &HashSet<DamageType> {
-        &self.resistances
+    fn get_dmg_defenses(&self) -> &DamageDefenses {
+        &self.defenses
     }
 
     fn get_ability_scores(&self) -> &AbilityScores {
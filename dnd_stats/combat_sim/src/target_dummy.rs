@@ -4,7 +4,7 @@ use std::fmt::Debug;
 use combat_core::ability_scores::{Ability, AbilityScores};
 use combat_core::actions::{ActionManager, register_pid};
 use combat_core::conditions::ConditionManager;
-use combat_core::damage::DamageType;
+use combat_core::damage::{DamageDefenses, DamageType};
 use combat_core::participant::{Participant, ParticipantId};
 use combat_core::resources::ResourceManager;
 use combat_core::skills::SkillManager;
@@ -17,7 +17,7 @@ pub struct TargetDummy {
     max_hp: isize,
     ac: isize,
     prof: isize,
-    resistances: HashSet<DamageType>,
+    defenses: DamageDefenses,
     ability_scores: AbilityScores,
     skill_manager: SkillManager,
     action_manager: ActionManager,
@@ -32,7 +32,7 @@ impl TargetDummy {
             max_hp: hp,
             ac,
             prof: prof_by_cr(cr),
-            resistances: HashSet::new(),
+            defenses: DamageDefenses::new(),
             ability_scores: ability_scores_by_cr(cr, Ability::STR),
             skill_manager: SkillManager::new(),
             action_manager: ActionManager::new(),
@@ -42,18 +42,13 @@ impl TargetDummy {
     }
 
     pub fn resistant(hp: isize, ac: isize, resistances: HashSet<DamageType>) -> Self {
-        let cr = ac_to_cr(ac);
-        Self {
-            max_hp: hp,
-            ac,
-            prof: prof_by_cr(cr),
-            resistances,
-            ability_scores: ability_scores_by_cr(cr, Ability::STR),
-            skill_manager: SkillManager::new(),
-            action_manager: ActionManager::new(),
-            resource_manager: ResourceManager::just_action_types(),
-            condition_manager: ConditionManager::new(),
-        }
+        Self::defended(hp, ac, DamageDefenses::resistant(resistances))
+    }
+
+    pub fn defended(hp: isize, ac: isize, defenses: DamageDefenses) -> Self {
+        let mut dummy = Self::new(hp, ac);
+        dummy.defenses = defenses;
+        dummy
     }
 }
 
@@ -70,8 +65,8 @@ impl Participant for TargetDummy {
         self.prof
     }
 
-    fn get_resistances(&self) -> &HashSet<DamageType> {
-        &self.resistances
+    fn get_dmg_defenses(&self) -> &DamageDefenses {
+        &self.defenses
     }
 
     fn get_ability_scores(&self) -> &AbilityScores {
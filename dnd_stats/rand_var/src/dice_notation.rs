@@ -0,0 +1,415 @@
+use std::str::FromStr;
+
+use crate::lazy_rv::{ExprId, LazyRV};
+use crate::num_rand_var::NumRandVar;
+use crate::rand_var::prob_type::RVProb;
+use crate::rand_var::RandVar;
+use crate::rand_var::sequential::SeqIter;
+use crate::RVError;
+use crate::vec_rand_var::VecRandVar;
+
+// how many extra dice an exploding die ('!') may roll before we stop following it
+pub const DEFAULT_EXPLODE_DEPTH: u32 = 5;
+
+// limits on what a parsed expression can ask for, so a typo can't try to allocate
+// a few billion probabilities. MAX_VALUES is for the whole expression, summed over the terms.
+pub const MAX_DICE: u32 = 1000;
+pub const MAX_SIDES: isize = 10000;
+pub const MAX_VALUES: isize = 1000000;
+
+// a compare point, as in the "<2" of "2d6ro<2"
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RollCmp {
+    Eq(isize),
+    AtMost(isize),
+    AtLeast(isize),
+}
+
+impl RollCmp {
+    pub fn matches(&self, roll: isize) -> bool {
+        match self {
+            RollCmp::Eq(x) => roll == *x,
+            RollCmp::AtMost(x) => roll <= *x,
+            RollCmp::AtLeast(x) => roll >= *x,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DieModifier {
+    Reroll(RollCmp),
+    RerollOnce(RollCmp),
+    Explode,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum KeepRule {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NotationTerm {
+    Const(isize),
+    Dice {
+        num: u32,
+        sides: isize,
+        modifiers: Vec<DieModifier>,
+        keep: Option<KeepRule>,
+    },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignedTerm {
+    pub pos: usize, // where the term starts in the parsed string
+    pub negative: bool,
+    pub term: NotationTerm,
+}
+
+// standard dice notation, e.g.: "2d6+1d4+3", "4d6kh3", "1d20r1", "2d6ro<2", "1d8!"
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiceNotation {
+    terms: Vec<SignedTerm>,
+}
+
+impl DiceNotation {
+    pub fn parse(s: &str) -> Result<Self, RVError> {
+        Parser::new(s).parse_notation()
+    }
+
+    pub fn get_terms(&self) -> &Vec<SignedTerm> {
+        &self.terms
+    }
+
+    pub fn to_expr<P: RVProb>(&self, lazy: &mut LazyRV<P>) -> Result<ExprId, RVError> {
+        let mut terms = Vec::with_capacity(self.terms.len());
+        for st in self.terms.iter() {
+            let term = match &st.term {
+                NotationTerm::Const(c) => lazy.constant(*c),
+                NotationTerm::Dice { num, sides, modifiers, keep } => {
+                    if modifiers.is_empty() && keep.is_none() {
+                        let die = lazy.die(*sides)?;
+                        lazy.multiple(die, *num)
+                    } else {
+                        lazy.leaf(get_dice_rv(*num, *sides, modifiers, keep, st.pos)?)
+                    }
+                },
+            };
+            if st.negative {
+                terms.push(lazy.negate(term));
+            } else {
+                terms.push(term);
+            }
+        }
+        Ok(lazy.sum(terms))
+    }
+
+    pub fn to_rv<P: RVProb>(&self) -> Result<VecRandVar<P>, RVError> {
+        let mut lazy = LazyRV::new();
+        let id = self.to_expr(&mut lazy)?;
+        lazy.eval(id)
+    }
+}
+
+impl FromStr for DiceNotation {
+    type Err = RVError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DiceNotation::parse(s)
+    }
+}
+
+pub fn parse_dice<P: RVProb>(s: &str) -> Result<VecRandVar<P>, RVError> {
+    DiceNotation::parse(s)?.to_rv()
+}
+
+fn get_dice_rv<P: RVProb>(num: u32, sides: isize, modifiers: &[DieModifier], keep: &Option<KeepRule>, pos: usize) -> Result<VecRandVar<P>, RVError> {
+    let mut die: VecRandVar<P> = VecRandVar::new_dice(sides)?;
+    for modifier in modifiers.iter() {
+        die = match modifier {
+            DieModifier::Reroll(cmp) => reroll_forever(&die, cmp, pos)?,
+            DieModifier::RerollOnce(cmp) => die.reroll_once_on(|k| cmp.matches(*k)),
+            DieModifier::Explode => die.explode(DEFAULT_EXPLODE_DEPTH)?.0,
+        };
+    }
+    match keep {
+        None => {
+            // fits, since the parser caps num at MAX_DICE
+            die.try_multiple(num as i32)
+        },
+        Some(KeepRule::Highest(k)) => die.keep_highest(num, *k),
+        Some(KeepRule::Lowest(k)) => die.keep_lowest(num, *k),
+    }
+}
+
+// rerolling until the roll doesn't match is the same as only ever rolling the non-matching values
+fn reroll_forever<P: RVProb>(die: &VecRandVar<P>, cmp: &RollCmp, pos: usize) -> Result<VecRandVar<P>, RVError> {
+    let keys = SeqIter { items: die.get_keys().filter(|k| !cmp.matches(*k)).collect() };
+    let kept_prob: P = keys.clone().map(|k| die.pdf(k)).sum();
+    let scale = match kept_prob.reciprocal() {
+        Some(p) => p,
+        None => return Err(RVError::ParseErr(pos, String::from("every roll would be rerolled"))),
+    };
+    RandVar::build(keys, |k| die.pdf(k) * scale.clone())
+}
+
+struct Parser<'a> {
+    chars: Vec<(usize, char)>,
+    index: usize,
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.char_indices().filter(|(_, c)| !c.is_whitespace()).collect(),
+            index: 0,
+            input,
+        }
+    }
+
+    fn pos(&self) -> usize {
+        self.chars.get(self.index).map(|(i, _)| *i).unwrap_or(self.input.len())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).map(|(_, c)| c.to_ascii_lowercase())
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn err<T>(&self, msg: &str) -> Result<T, RVError> {
+        Err(RVError::ParseErr(self.pos(), String::from(msg)))
+    }
+
+    fn parse_notation(&mut self) -> Result<DiceNotation, RVError> {
+        let mut terms = Vec::new();
+        let mut num_values = 0;
+        let mut negative = false;
+        if self.eat('-') {
+            negative = true;
+        } else {
+            self.eat('+');
+        }
+        loop {
+            let term = self.parse_term(negative)?;
+            num_values += Self::num_values(&term.term);
+            if num_values > MAX_VALUES {
+                return Err(RVError::ParseErr(term.pos, String::from("the result would have too many values")));
+            }
+            terms.push(term);
+            if self.eat('+') {
+                negative = false;
+            } else if self.eat('-') {
+                negative = true;
+            } else if self.peek().is_none() {
+                break;
+            } else {
+                return self.err("expected '+', '-' or the end of the expression");
+            }
+        }
+        Ok(DiceNotation { terms })
+    }
+
+    fn parse_number(&mut self) -> Option<Result<isize, RVError>> {
+        let start = self.pos();
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(c);
+            self.index += 1;
+        }
+        if digits.is_empty() {
+            None
+        } else {
+            Some(digits.parse().map_err(|_| RVError::ParseErr(start, String::from("number is too large"))))
+        }
+    }
+
+    fn expect_number(&mut self, what: &str) -> Result<isize, RVError> {
+        match self.parse_number() {
+            Some(n) => n,
+            None => self.err(&format!("expected {}", what)),
+        }
+    }
+
+    fn parse_term(&mut self, negative: bool) -> Result<SignedTerm, RVError> {
+        let pos = self.pos();
+        let count = self.parse_number().transpose()?;
+        if !self.eat('d') {
+            return match count {
+                Some(c) => Ok(SignedTerm { pos, negative, term: NotationTerm::Const(c) }),
+                None => self.err("expected a number or dice"),
+            };
+        }
+        let num = count.unwrap_or(1);
+        if num < 1 {
+            return Err(RVError::ParseErr(pos, String::from("number of dice must be positive")));
+        }
+        if num > (MAX_DICE as isize) {
+            return Err(RVError::ParseErr(pos, format!("at most {} dice can be rolled at once", MAX_DICE)));
+        }
+        let sides_pos = self.pos();
+        let sides = self.expect_number("the number of sides")?;
+        if sides < 1 {
+            return Err(RVError::ParseErr(sides_pos, String::from("dice must have at least one side")));
+        }
+        if sides > MAX_SIDES {
+            return Err(RVError::ParseErr(sides_pos, format!("dice can have at most {} sides", MAX_SIDES)));
+        }
+        let mut modifiers = Vec::new();
+        let mut keep = None;
+        loop {
+            let mod_pos = self.pos();
+            if self.eat('k') {
+                if keep.is_some() {
+                    return Err(RVError::ParseErr(mod_pos, String::from("only one keep rule is allowed")));
+                }
+                let lowest = if self.eat('l') { true } else { self.eat('h'); false };
+                let k = self.parse_number().transpose()?.unwrap_or(1);
+                if k > num {
+                    return Err(RVError::ParseErr(mod_pos, String::from("can't keep more dice than are rolled")));
+                }
+                keep = if lowest { Some(KeepRule::Lowest(k as u32)) } else { Some(KeepRule::Highest(k as u32)) };
+            } else if self.eat('r') {
+                let once = self.eat('o');
+                let cmp = self.parse_cmp()?;
+                if once {
+                    modifiers.push(DieModifier::RerollOnce(cmp));
+                } else {
+                    modifiers.push(DieModifier::Reroll(cmp));
+                }
+            } else if self.eat('!') {
+                if sides < 2 {
+                    return Err(RVError::ParseErr(mod_pos, String::from("a one-sided die can't explode")));
+                }
+                modifiers.push(DieModifier::Explode);
+            } else {
+                break;
+            }
+        }
+        let term = NotationTerm::Dice { num: num as u32, sides, modifiers, keep };
+        Ok(SignedTerm { pos, negative, term })
+    }
+
+    // an upper bound on how many values the term's rv can take. Both caps above
+    // keep this well away from overflowing.
+    fn num_values(term: &NotationTerm) -> isize {
+        match term {
+            NotationTerm::Const(_) => 1,
+            NotationTerm::Dice { num, sides, modifiers, .. } => {
+                let mut per_die = *sides;
+                if modifiers.contains(&DieModifier::Explode) {
+                    per_die *= (DEFAULT_EXPLODE_DEPTH + 1) as isize;
+                }
+                (*num as isize) * (per_die - 1) + 1
+            },
+        }
+    }
+
+    fn parse_cmp(&mut self) -> Result<RollCmp, RVError> {
+        if self.eat('<') {
+            Ok(RollCmp::AtMost(self.expect_number("a number to compare to")?))
+        } else if self.eat('>') {
+            Ok(RollCmp::AtLeast(self.expect_number("a number to compare to")?))
+        } else {
+            self.eat('=');
+            Ok(RollCmp::Eq(self.expect_number("a number to compare to")?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{Rational64, Zero};
+
+    use crate::dice_notation::{DiceNotation, DieModifier, KeepRule, NotationTerm, parse_dice, RollCmp};
+    use crate::num_rand_var::NumRandVar;
+    use crate::rand_var::RandVar;
+    use crate::RVError;
+    use crate::vec_rand_var::{VecRandVar, VRV64};
+
+    #[test]
+    fn test_plain_sums() {
+        let rv: VRV64 = parse_dice("2d6+1d4+3").unwrap();
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let d4: VRV64 = VecRandVar::new_dice(4).unwrap();
+        assert_eq!(d6.multiple(2).add_rv(&d4).add_const(3), rv);
+
+        let rv: VRV64 = parse_dice(" d20 - 1D4 - 2 ").unwrap();
+        let d20: VRV64 = VecRandVar::new_dice(20).unwrap();
+        assert_eq!(d20.minus_rv(&d4).add_const(-2), rv);
+
+        let rv: VRV64 = parse_dice("-5").unwrap();
+        assert_eq!(VecRandVar::new_constant(-5).unwrap(), rv);
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        assert_eq!(d6.keep_highest(4, 3).unwrap(), parse_dice("4d6kh3").unwrap());
+        assert_eq!(d6.keep_highest(4, 3).unwrap(), parse_dice("4d6k3").unwrap());
+        assert_eq!(d6.keep_lowest(3, 2).unwrap(), parse_dice("3d6kl2").unwrap());
+
+        let gwf: VRV64 = VecRandVar::new_dice_reroll(6, 2).unwrap().multiple(2);
+        assert_eq!(gwf, parse_dice("2d6ro<2").unwrap());
+
+        // rerolling 1s forever is a d19 shifted up by one
+        let halfling: VRV64 = parse_dice("1d20r1").unwrap();
+        assert_eq!(VecRandVar::new_uniform(2, 20).unwrap(), halfling);
+        let rv: VRV64 = parse_dice("1d20r=1").unwrap();
+        assert_eq!(halfling, rv);
+
+        let notation = DiceNotation::parse("2d10r>9").unwrap();
+        let term = &notation.get_terms()[0].term;
+        assert_eq!(&NotationTerm::Dice { num: 2, sides: 10, modifiers: vec!(DieModifier::Reroll(RollCmp::AtLeast(9))), keep: None }, term);
+        let notation = DiceNotation::parse("4d6kl1").unwrap();
+        assert_eq!(&NotationTerm::Dice { num: 4, sides: 6, modifiers: vec!(), keep: Some(KeepRule::Lowest(1)) }, &notation.get_terms()[0].term);
+    }
+
+    #[test]
+    fn test_explode() {
+        let rv: VRV64 = parse_dice("1d8!").unwrap();
+        assert_eq!(1, rv.lower_bound());
+        assert_eq!(48, rv.upper_bound());
+        assert_eq!(Rational64::new(1, 8), rv.pdf(7));
+        assert_eq!(Rational64::zero(), rv.pdf(8));
+        assert_eq!(Rational64::new(1, 64), rv.pdf(9));
+        assert_eq!(Rational64::new(1, 512), rv.pdf(17));
+    }
+
+    fn assert_parse_err(s: &str, pos: usize) {
+        match DiceNotation::parse(s) {
+            Err(RVError::ParseErr(p, _)) => assert_eq!(pos, p),
+            other => panic!("expected a parse error for {}, got {:?}", s, other),
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_parse_err("", 0);
+        assert_parse_err("2d", 2);
+        assert_parse_err("2d6+", 4);
+        assert_parse_err("2d6 * 3", 4);
+        assert_parse_err("2d0", 2);
+        assert_parse_err("0d6", 0);
+        assert_parse_err("2147483648d6", 0);
+        assert_parse_err("1+4294967295d6", 2);
+        assert_parse_err("1001d6", 0);
+        assert_parse_err("2147483647d2", 0);
+        assert_parse_err("1d10001", 2);
+        assert_parse_err("1d9223372036854775807", 2);
+        assert_parse_err("1000d10000", 0);
+        assert_parse_err("600d1000+600d1000", 9);
+        assert!(DiceNotation::parse("1000d1000").is_ok());
+        assert_parse_err("1d6+3d6kh4", 7);
+        assert_parse_err("1d20ro<", 7);
+        assert!(parse_dice::<Rational64>("1d4r<4").is_err());
+    }
+}
@@ -0,0 +1,442 @@
+use std::cmp;
+use std::collections::HashMap;
+
+use crate::num_rand_var::NumRandVar;
+use crate::rand_var::mixture::Mixture;
+use crate::rand_var::prob_type::RVProb;
+use crate::rand_var::RandVar;
+use crate::rand_var::sequential::{Seq, SeqIter};
+use crate::RVError;
+use crate::vec_rand_var::VecRandVar;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExprId(usize);
+
+// every node is a distribution, and combining nodes treats them as independent,
+// so e.g.: Sum([a, a]) is two separate rolls of a.
+#[derive(Debug, Clone, PartialEq)]
+enum Node<P: RVProb> {
+    Const(isize),
+    Die(isize),
+    Leaf(VecRandVar<P>),
+    Sum(Vec<ExprId>), // sorted, so the order terms are added in doesn't matter
+    Scale(ExprId, isize),
+    Half(ExprId),
+    Cap(ExprId, Option<isize>, Option<isize>),
+    Max(ExprId, ExprId),
+    Mixture(Vec<(P, ExprId)>),
+}
+
+// a cheap hashable summary of a node, so insert doesn't have to compare against every node.
+// leaves and mixtures with the same key still need a full comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum NodeKey {
+    Const(isize),
+    Die(isize),
+    Leaf(isize, isize),
+    Sum(Vec<ExprId>),
+    Scale(ExprId, isize),
+    Half(ExprId),
+    Cap(ExprId, Option<isize>, Option<isize>),
+    Max(ExprId, ExprId),
+    Mixture(Vec<ExprId>),
+}
+
+impl<P: RVProb> Node<P> {
+    fn key(&self) -> NodeKey {
+        match self {
+            Node::Const(c) => NodeKey::Const(*c),
+            Node::Die(sides) => NodeKey::Die(*sides),
+            Node::Leaf(rv) => NodeKey::Leaf(rv.lower_bound(), rv.upper_bound()),
+            Node::Sum(terms) => NodeKey::Sum(terms.clone()),
+            Node::Scale(id, factor) => NodeKey::Scale(*id, *factor),
+            Node::Half(id) => NodeKey::Half(*id),
+            Node::Cap(id, lb, ub) => NodeKey::Cap(*id, *lb, *ub),
+            Node::Max(left, right) => NodeKey::Max(*left, *right),
+            Node::Mixture(parts) => NodeKey::Mixture(parts.iter().map(|(_, id)| *id).collect()),
+        }
+    }
+}
+
+// Builds up an RV symbolically and only does the convolutions when it's evaluated.
+// Identical sub-expressions share an ExprId, so they're only ever evaluated once.
+#[derive(Debug, Clone)]
+pub struct LazyRV<P: RVProb> {
+    nodes: Vec<Node<P>>,
+    cache: Vec<Option<VecRandVar<P>>>,
+    index: HashMap<NodeKey, Vec<ExprId>>,
+}
+
+impl<P: RVProb> LazyRV<P> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            cache: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    // the number of distinct sub-expressions
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn insert(&mut self, node: Node<P>) -> ExprId {
+        let same_key = self.index.entry(node.key()).or_default();
+        if let Some(id) = same_key.iter().find(|id| self.nodes[id.0] == node) {
+            return *id;
+        }
+        let id = ExprId(self.nodes.len());
+        same_key.push(id);
+        self.nodes.push(node);
+        self.cache.push(None);
+        id
+    }
+
+    fn get_node(&self, id: ExprId) -> &Node<P> {
+        self.nodes.get(id.0).unwrap()
+    }
+
+    pub fn get_const(&self, id: ExprId) -> Option<isize> {
+        match self.get_node(id) {
+            Node::Const(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    pub fn constant(&mut self, c: isize) -> ExprId {
+        self.insert(Node::Const(c))
+    }
+
+    pub fn die(&mut self, sides: isize) -> Result<ExprId, RVError> {
+        if sides < 1 {
+            return Err(RVError::InvalidBounds);
+        }
+        if sides == 1 {
+            return Ok(self.constant(1));
+        }
+        Ok(self.insert(Node::Die(sides)))
+    }
+
+    pub fn leaf(&mut self, rv: VecRandVar<P>) -> ExprId {
+        if rv.len() == 1 {
+            return self.constant(rv.lower_bound());
+        }
+        self.insert(Node::Leaf(rv))
+    }
+
+    pub fn sum(&mut self, terms: Vec<ExprId>) -> ExprId {
+        let mut flat = Vec::with_capacity(terms.len());
+        for t in terms.into_iter() {
+            match self.get_node(t) {
+                Node::Sum(children) => flat.extend(children.iter().copied()),
+                _ => flat.push(t),
+            }
+        }
+        let mut total = 0;
+        let mut others: Vec<ExprId> = Vec::with_capacity(flat.len());
+        for t in flat.into_iter() {
+            match self.get_const(t) {
+                Some(c) => total += c,
+                None => others.push(t),
+            }
+        }
+        if others.is_empty() {
+            return self.constant(total);
+        }
+        if total != 0 {
+            others.push(self.constant(total));
+        }
+        if others.len() == 1 {
+            return others[0];
+        }
+        others.sort();
+        self.insert(Node::Sum(others))
+    }
+
+    pub fn add(&mut self, left: ExprId, right: ExprId) -> ExprId {
+        self.sum(vec!(left, right))
+    }
+
+    pub fn add_const(&mut self, id: ExprId, c: isize) -> ExprId {
+        let c_id = self.constant(c);
+        self.add(id, c_id)
+    }
+
+    // num_times independent copies of id, added together
+    pub fn multiple(&mut self, id: ExprId, num_times: u32) -> ExprId {
+        self.sum(vec!(id; num_times as usize))
+    }
+
+    // multiplies every value by factor (not the same as multiple)
+    pub fn scale(&mut self, id: ExprId, factor: isize) -> ExprId {
+        if factor == 1 {
+            return id;
+        }
+        if factor == 0 {
+            return self.constant(0);
+        }
+        match self.get_node(id).clone() {
+            Node::Const(c) => self.constant(c * factor),
+            Node::Scale(inner, f) => self.scale(inner, f * factor),
+            _ => self.insert(Node::Scale(id, factor)),
+        }
+    }
+
+    pub fn negate(&mut self, id: ExprId) -> ExprId {
+        self.scale(id, -1)
+    }
+
+    // rounds like NumRandVar::half
+    pub fn half(&mut self, id: ExprId) -> ExprId {
+        match self.get_const(id) {
+            Some(c) => self.constant(c / 2),
+            None => self.insert(Node::Half(id)),
+        }
+    }
+
+    pub fn cap(&mut self, id: ExprId, lb: Option<isize>, ub: Option<isize>) -> ExprId {
+        if lb.is_none() && ub.is_none() {
+            return id;
+        }
+        let valid = match (lb, ub) {
+            (Some(l), Some(u)) => l <= u,
+            _ => true,
+        };
+        match self.get_const(id) {
+            Some(c) if valid => {
+                let c = lb.map_or(c, |l| cmp::max(c, l));
+                let c = ub.map_or(c, |u| cmp::min(c, u));
+                self.constant(c)
+            },
+            _ => self.insert(Node::Cap(id, lb, ub)),
+        }
+    }
+
+    // the larger of independent rolls of left and right
+    pub fn max(&mut self, left: ExprId, right: ExprId) -> ExprId {
+        if let (Some(l), Some(r)) = (self.get_const(left), self.get_const(right)) {
+            return self.constant(cmp::max(l, r));
+        }
+        self.insert(Node::Max(cmp::min(left, right), cmp::max(left, right)))
+    }
+
+    // weights are checked when it's evaluated, see Mixture
+    pub fn mixture(&mut self, parts: Vec<(P, ExprId)>) -> ExprId {
+        let mut merged: Vec<(P, ExprId)> = Vec::with_capacity(parts.len());
+        for (weight, id) in parts.into_iter() {
+            match merged.iter_mut().find(|(_, other)| *other == id) {
+                Some((w, _)) => *w = w.clone() + weight,
+                None => merged.push((weight, id)),
+            }
+        }
+        if merged.len() == 1 && merged[0].0.approx_eq(&P::one()) {
+            return merged[0].1;
+        }
+        merged.sort_by_key(|(_, id)| *id);
+        self.insert(Node::Mixture(merged))
+    }
+
+    pub fn eval(&mut self, id: ExprId) -> Result<VecRandVar<P>, RVError> {
+        if let Some(rv) = &self.cache[id.0] {
+            return Ok(rv.clone());
+        }
+        let rv = match self.get_node(id).clone() {
+            Node::Const(c) => VecRandVar::new_constant(c)?,
+            Node::Die(sides) => VecRandVar::new_dice(sides)?,
+            Node::Leaf(rv) => rv,
+            Node::Sum(children) => self.eval_sum(&children)?,
+            Node::Scale(inner, factor) => {
+                let rv = self.eval(inner)?;
+                let seq_iter = SeqIter { items: rv.get_keys().map(|k| k * factor).collect() };
+                RandVar::build(seq_iter, |k| rv.pdf(k / factor))?
+            },
+            Node::Half(inner) => self.eval(inner)?.half()?,
+            Node::Cap(inner, lb, ub) => {
+                let rv = self.eval(inner)?;
+                match (lb, ub) {
+                    (Some(l), Some(u)) if l > u => return Err(RVError::InvalidBounds),
+                    (Some(l), _) if l >= rv.upper_bound() => VecRandVar::new_constant(l)?,
+                    (_, Some(u)) if u <= rv.lower_bound() => VecRandVar::new_constant(u)?,
+                    _ => {
+                        let rv = match lb {
+                            Some(l) => rv.cap_lb(l)?,
+                            None => rv,
+                        };
+                        match ub {
+                            Some(u) => rv.cap_ub(u)?,
+                            None => rv,
+                        }
+                    },
+                }
+            },
+            Node::Max(left, right) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                max_rv(&left, &right)?
+            },
+            Node::Mixture(parts) => {
+                let mut mixture = Mixture::new();
+                for (weight, part) in parts.into_iter() {
+                    let rv = self.eval(part)?;
+                    mixture.add(weight, &rv)?;
+                }
+                mixture.build()?
+            },
+        };
+        self.cache[id.0] = Some(rv.clone());
+        Ok(rv)
+    }
+
+    fn eval_sum(&mut self, children: &[ExprId]) -> Result<VecRandVar<P>, RVError> {
+        let mut total_const = 0;
+        let mut rvs: Vec<VecRandVar<P>> = Vec::new();
+        // children are sorted, so repeats are next to each other
+        for group in children.chunk_by(|a, b| a == b) {
+            if let Some(c) = self.get_const(group[0]) {
+                total_const += c * (group.len() as isize);
+                continue;
+            }
+            let rv = self.eval(group[0])?;
+            if group.len() > 1 {
+                rvs.push(rv.try_multiple(group.len() as i32)?);
+            } else {
+                rvs.push(rv);
+            }
+        }
+        // always convolve the two smallest, since the cost is the product of their lengths
+        while rvs.len() > 1 {
+            rvs.sort_by_key(|rv| cmp::Reverse(rv.len()));
+            let smallest = rvs.pop().unwrap();
+            let next = rvs.pop().unwrap();
+            rvs.push(smallest.try_add_rv(&next)?);
+        }
+        match rvs.pop() {
+            Some(rv) => Ok(rv.add_const(total_const)),
+            None => VecRandVar::new_constant(total_const),
+        }
+    }
+}
+
+impl<P: RVProb> Default for LazyRV<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// P(max <= k) = P(left <= k) * P(right <= k)
+fn max_rv<P: RVProb>(left: &VecRandVar<P>, right: &VecRandVar<P>) -> Result<VecRandVar<P>, RVError> {
+    let lb = cmp::max(left.lower_bound(), right.lower_bound());
+    let ub = cmp::max(left.upper_bound(), right.upper_bound());
+    let mut v = Vec::with_capacity((ub - lb + 1) as usize);
+    for k in Seq::gen_seq(&lb, &ub) {
+        let le = left.cdf_ref(&k).checked_mul(&right.cdf_ref(&k)).ok_or(RVError::Overflow)?;
+        let lt = left.cdf_exclusive_ref(&k).checked_mul(&right.cdf_exclusive_ref(&k)).ok_or(RVError::Overflow)?;
+        v.push(le.checked_sub(&lt).ok_or(RVError::Overflow)?);
+    }
+    VecRandVar::new(lb, ub, v)
+}
+
+#[cfg(test)]
+mod tests {
+    use num::Rational64;
+
+    use crate::lazy_rv::LazyRV;
+    use crate::num_rand_var::NumRandVar;
+    use crate::rand_var::RandVar;
+    use crate::vec_rand_var::{VecRandVar, VRV64};
+
+    #[test]
+    fn test_folding() {
+        let mut lazy: LazyRV<Rational64> = LazyRV::new();
+        let three = lazy.constant(3);
+        let four = lazy.constant(4);
+        let seven = lazy.add(three, four);
+        assert_eq!(Some(7), lazy.get_const(seven));
+        let neg = lazy.scale(seven, -2);
+        assert_eq!(Some(-14), lazy.get_const(neg));
+        let half = lazy.half(neg);
+        assert_eq!(Some(-7), lazy.get_const(half));
+        let capped = lazy.cap(half, Some(0), None);
+        assert_eq!(Some(0), lazy.get_const(capped));
+        let max = lazy.max(three, seven);
+        assert_eq!(Some(7), lazy.get_const(max));
+
+        let d6 = lazy.die(6).unwrap();
+        let one = lazy.die(1).unwrap();
+        assert_eq!(Some(1), lazy.get_const(one));
+        let nested = lazy.add(d6, three);
+        let nested = lazy.add(nested, four);
+        let flat = lazy.add_const(d6, 7);
+        assert_eq!(nested, flat);
+        let d6_again = lazy.sum(vec!(d6));
+        assert_eq!(d6, d6_again);
+    }
+
+    #[test]
+    fn test_sharing() {
+        let mut lazy: LazyRV<Rational64> = LazyRV::new();
+        let d8 = lazy.die(8).unwrap();
+        let d4 = lazy.die(4).unwrap();
+        let a = lazy.sum(vec!(d8, d4, d8));
+        let b = lazy.sum(vec!(d4, d8, d8));
+        assert_eq!(a, b);
+        let len = lazy.len();
+        let d8_again = lazy.die(8).unwrap();
+        assert_eq!(d8, d8_again);
+        assert_eq!(len, lazy.len());
+
+        let d8_rv: VRV64 = VecRandVar::new_dice(8).unwrap();
+        let d4_rv: VRV64 = VecRandVar::new_dice(4).unwrap();
+        let expected = d8_rv.multiple(2).add_rv(&d4_rv);
+        assert_eq!(expected, lazy.eval(a).unwrap());
+        let doubled = lazy.multiple(a, 2);
+        assert_eq!(expected.multiple(2), lazy.eval(doubled).unwrap());
+    }
+
+    #[test]
+    fn test_eval() {
+        let mut lazy: LazyRV<Rational64> = LazyRV::new();
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let d6_id = lazy.die(6).unwrap();
+
+        let fireball = lazy.multiple(d6_id, 8);
+        let saved = lazy.half(fireball);
+        assert_eq!(d6.multiple(8).half().unwrap(), lazy.eval(saved).unwrap());
+
+        let scaled = lazy.scale(d6_id, 2);
+        let rv = lazy.eval(scaled).unwrap();
+        assert_eq!(Rational64::new(1, 6), rv.pdf(12));
+        assert_eq!(Rational64::new(0, 1), rv.pdf(11));
+
+        let adv = lazy.max(d6_id, d6_id);
+        assert_eq!(d6.max_two_trials(), lazy.eval(adv).unwrap());
+
+        // leaves with the same bounds are only shared if they're actually equal
+        let len = lazy.len();
+        let leaf = lazy.leaf(d6.clone());
+        assert_eq!(leaf, lazy.leaf(d6.clone()));
+        let other = lazy.leaf(VecRandVar::new_dice_reroll(6, 2).unwrap());
+        assert_ne!(leaf, other);
+        assert_eq!(len + 2, lazy.len());
+
+        let minus = lazy.add_const(d6_id, -4);
+        let capped = lazy.cap(minus, Some(0), Some(1));
+        assert_eq!(d6.add_const(-4).clamp(0, 1).unwrap(), lazy.eval(capped).unwrap());
+        let all_capped = lazy.cap(minus, Some(5), None);
+        assert_eq!(VecRandVar::new_constant(5).unwrap(), lazy.eval(all_capped).unwrap());
+
+        let half = Rational64::new(1, 2);
+        let zero = lazy.constant(0);
+        let mixed = lazy.mixture(vec!((half, d6_id), (half, zero)));
+        let rv = lazy.eval(mixed).unwrap();
+        assert_eq!(Rational64::new(1, 2), rv.pdf(0));
+        assert_eq!(Rational64::new(7, 4), rv.expected_value());
+        let bad = lazy.mixture(vec!((half, d6_id)));
+        assert!(lazy.eval(bad).is_err());
+    }
+}
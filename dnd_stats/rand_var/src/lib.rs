@@ -1,8 +1,15 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 
 pub mod rand_var;
 pub mod num_rand_var;
 pub mod vec_rand_var;
 pub mod map_rand_var;
+pub mod dice_notation;
+pub mod serialization;
+pub mod plot;
+pub mod lazy_rv;
 
 #[derive(Debug, Clone)]
 pub enum RVError {
@@ -10,5 +17,23 @@ pub enum RVError {
     CDFNotOne,
     NegProb,
     NoRound,
+    Overflow,
+    ParseErr(usize, String),
     Other(String),
 }
+
+impl Display for RVError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RVError::InvalidBounds => write!(f, "invalid bounds: the random variable would have no values"),
+            RVError::CDFNotOne => write!(f, "the probabilities don't add up to 1"),
+            RVError::NegProb => write!(f, "a probability was negative"),
+            RVError::NoRound => write!(f, "the key type doesn't round consistently, so it can't be halved"),
+            RVError::Overflow => write!(f, "overflow while computing probabilities (try a bigger probability type)"),
+            RVError::ParseErr(pos, msg) => write!(f, "parse error at position {}: {}", pos, msg),
+            RVError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for RVError {}
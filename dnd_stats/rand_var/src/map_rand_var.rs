@@ -1,11 +1,13 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::iter::Sum;
 use std::ops::Add;
-use num::{BigRational, FromPrimitive, Rational64};
+use num::{BigRational, FromPrimitive, Rational64, ToPrimitive};
 
 use crate::num_rand_var::NumRandVar;
-use crate::rand_var::prob_type::Prob;
+use crate::rand_var::cdf_cache::CdfCache;
+use crate::rand_var::prob_type::{checked_sum, F64Prob, Prob, Reciprocal};
 use crate::rand_var::RandVar;
+use crate::rand_var::mixture::Mixture;
 use crate::rand_var::sequential::{FlatCmp, FlatType, Nested, Pair, SeqIter};
 use crate::RVError;
 use crate::vec_rand_var::VecRandVar;
@@ -15,10 +17,12 @@ pub struct MapRandVar<K: Ord + Clone, P: Prob> {
     lower_bound: K,
     upper_bound: K,
     pdf_map: BTreeMap<K, P>,
+    cdf_cache: CdfCache<(K, P)>,
 }
 
 pub type MRV64 = MapRandVar<isize, Rational64>;
 pub type MRVBig = MapRandVar<isize, BigRational>;
+pub type MRVF64 = MapRandVar<isize, F64Prob>;
 
 impl<K: Ord + Clone, P: Prob> MapRandVar<K, P> {
     pub fn backing_map(&self) -> &BTreeMap<K, P> {
@@ -32,6 +36,30 @@ impl<K: Ord + Clone, P: Prob> MapRandVar<K, P> {
     pub fn get_ub(&self) -> &K {
         &self.upper_bound
     }
+
+    // cumulative pdf keyed like pdf_map, so cdf lookups are a binary search.
+    fn cdf_table(&self) -> &Vec<(K, P)> {
+        self.cdf_cache.get_or_build(|| {
+            let mut total = P::zero();
+            self.pdf_map.iter().map(|(k, p)| {
+                total = total.clone() + p.clone();
+                (k.clone(), total.clone())
+            }).collect()
+        })
+    }
+
+    fn cdf_table_lookup<F>(&self, is_before: F) -> P
+    where
+        F: Fn(&K) -> bool
+    {
+        let table = self.cdf_table();
+        let index = table.partition_point(|(k, _)| is_before(k));
+        if index == 0 {
+            P::zero()
+        } else {
+            table[index - 1].1.clone()
+        }
+    }
 }
 
 impl<K, P> MapRandVar<K, P>
@@ -48,7 +76,7 @@ where
         if lb > ub {
             return Err(RVError::InvalidBounds);
         }
-        if m.values().sum::<P>() != P::one() {
+        if !checked_sum(m.values())?.approx_eq(&P::one()) {
             return Err(RVError::CDFNotOne);
         }
         if m.values().any(|p| p < &P::zero()) {
@@ -57,7 +85,8 @@ where
         Ok(Self {
             lower_bound: lb.clone(),
             upper_bound: ub.clone(),
-            pdf_map: m
+            pdf_map: m,
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -152,23 +181,14 @@ where
         L: Ord + Clone,
         RV: RandVar<L, P>,
     {
-        let mut new_pdf: BTreeMap<L, P> = BTreeMap::new();
+        let mut mixture = Mixture::new();
         for k in self.get_keys() {
             match outcomes.get(&k) {
                 None => return Err(RVError::Other(String::from("every valid k must have an outcome"))),
-                Some(rv) => {
-                   for l in rv.get_keys() {
-                       let pdf_kl = self.pdf_ref(&k) * rv.pdf_ref(&l);
-                       if pdf_kl > P::zero() {
-                           new_pdf.entry(l)
-                               .and_modify(|p| *p = p.clone() + pdf_kl.clone())
-                               .or_insert(pdf_kl);
-                       }
-                   }
-                }
+                Some(rv) => mixture.add(self.pdf_ref(&k), rv)?,
             }
         }
-        MapRandVar::from_map(new_pdf)
+        mixture.build()
     }
 
     pub fn projection<L, RV>(&self, outcomes: &BTreeMap<K, RV>) -> Result<MapRandVar<Pair<K, L>, P>, RVError>
@@ -194,6 +214,77 @@ where
     }
 }
 
+impl<A, B, P> MapRandVar<Pair<A, B>, P>
+where
+    A: Ord + Clone,
+    B: Ord + Clone,
+    P: Prob + PartialOrd<P> + for<'a> Sum<&'a P>,
+{
+    pub fn marginal_first(&self) -> MapRandVar<A, P> {
+        self.map_keys(|pair| pair.0)
+    }
+
+    pub fn marginal_second(&self) -> MapRandVar<B, P> {
+        self.map_keys(|pair| pair.1)
+    }
+
+    // the distribution of the second coordinate, given that the first one is a.
+    // None if the first coordinate can't be a.
+    pub fn conditional_on_first(&self, a: &A) -> Option<MapRandVar<B, P>>
+    where
+        P: Reciprocal
+    {
+        Self::conditional(self.pdf_map.iter().filter(|(pair, _)| &pair.0 == a).map(|(pair, p)| (pair.1.clone(), p)))
+    }
+
+    // the distribution of the first coordinate, given that the second one is b.
+    // None if the second coordinate can't be b.
+    pub fn conditional_on_second(&self, b: &B) -> Option<MapRandVar<A, P>>
+    where
+        P: Reciprocal
+    {
+        Self::conditional(self.pdf_map.iter().filter(|(pair, _)| &pair.1 == b).map(|(pair, p)| (pair.0.clone(), p)))
+    }
+
+    fn conditional<'a, L, I>(iter: I) -> Option<MapRandVar<L, P>>
+    where
+        L: Ord + Clone,
+        I: Iterator<Item=(L, &'a P)>,
+        P: Reciprocal + 'a,
+    {
+        let slice: BTreeMap<L, P> = iter.filter(|(_, p)| **p > P::zero()).map(|(l, p)| (l, p.clone())).collect();
+        let total: P = slice.values().sum();
+        let scale = total.reciprocal()?;
+        MapRandVar::from_map(slice.into_iter().map(|(l, p)| (l, p * scale.clone())).collect()).ok()
+    }
+}
+
+impl<P> MapRandVar<Pair<isize, isize>, P>
+where
+    P: Prob + PartialOrd<P> + for<'a> Sum<&'a P> + FromPrimitive,
+{
+    pub fn covariance(&self) -> P {
+        let to_p = |k: isize| P::from_isize(k).unwrap();
+        let ev_xy = self.general_expected_value(|pair| to_p(pair.0) * to_p(pair.1));
+        let ev_x = self.general_expected_value(|pair| to_p(pair.0));
+        let ev_y = self.general_expected_value(|pair| to_p(pair.1));
+        ev_xy - ev_x * ev_y
+    }
+
+    // None if either coordinate is constant
+    pub fn correlation(&self) -> Option<f64>
+    where
+        P: ToPrimitive
+    {
+        let var_x = self.marginal_first().variance().to_f64().unwrap();
+        let var_y = self.marginal_second().variance().to_f64().unwrap();
+        if var_x <= 0.0 || var_y <= 0.0 {
+            return None;
+        }
+        Some(self.covariance().to_f64().unwrap() / (var_x * var_y).sqrt())
+    }
+}
+
 impl<K, P> RandVar<K, P> for MapRandVar<K, P>
 where
     K: Ord + Clone,
@@ -213,18 +304,19 @@ where
                 return Err(RVError::NegProb);
             }
             if f_k > P::zero() {
-                total = total + f_k.clone();
+                total = total.checked_add(&f_k).ok_or(RVError::Overflow)?;
                 pdf_map.insert(k, f_k);
             }
         }
-        if P::one() != total {
+        if !total.approx_eq(&P::one()) {
             return Err(RVError::CDFNotOne);
         }
 
         Ok(Self {
             lower_bound: lb,
             upper_bound: ub,
-            pdf_map
+            pdf_map,
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -252,6 +344,26 @@ where
     fn len(&self) -> usize {
         self.pdf_map.len()
     }
+
+    fn cdf_ref(&self, k: &K) -> P {
+        if &self.upper_bound <= k {
+            P::one()
+        } else if &self.lower_bound <= k {
+            self.cdf_table_lookup(|x| x <= k)
+        } else {
+            P::zero()
+        }
+    }
+
+    fn cdf_exclusive_ref(&self, k: &K) -> P {
+        if &self.upper_bound < k {
+            P::one()
+        } else if &self.lower_bound < k {
+            self.cdf_table_lookup(|x| x < k)
+        } else {
+            P::zero()
+        }
+    }
 }
 
 impl<P> NumRandVar<isize, P> for MapRandVar<isize, P>
@@ -285,7 +397,8 @@ impl<P: Prob + PartialOrd<P>> From<VecRandVar<P>> for MapRandVar<isize, P> {
         Self {
             lower_bound: lb,
             upper_bound: ub,
-            pdf_map
+            pdf_map,
+            cdf_cache: CdfCache::new(),
         }
     }
 }
@@ -300,7 +413,7 @@ impl<P: Prob> MapRandVar<isize, P> {
 mod tests {
     use std::cmp;
     use std::collections::BTreeMap;
-    use num::{BigInt, BigRational, FromPrimitive, One, Rational64};
+    use num::{BigInt, BigRational, FromPrimitive, One, Rational64, Zero};
     use crate::map_rand_var::{MapRandVar, MRV64};
     use crate::num_rand_var::NumRandVar;
     use crate::rand_var::RandVar;
@@ -362,6 +475,47 @@ mod tests {
         assert_eq!(attack_dmg, attack_outcome.map_keys(|pair| pair.1));
     }
 
+    #[test]
+    fn test_joint() {
+        let (attack_check, outcomes) = get_attack_setup();
+        let attack_outcome = attack_check.projection(&outcomes).unwrap();
+        assert_eq!(attack_check, attack_outcome.marginal_first());
+        assert_eq!(attack_check.consolidate(&outcomes).unwrap(), attack_outcome.marginal_second());
+
+        // dmg given that the attack crit
+        let crit_dmg = attack_outcome.conditional_on_first(&2).unwrap();
+        assert_eq!(outcomes.get(&2).unwrap().clone().into_mrv(), crit_dmg);
+        assert_eq!(None, attack_outcome.conditional_on_first(&3));
+        // only a hit can do 8 dmg
+        let eight_dmg = attack_outcome.conditional_on_second(&8).unwrap();
+        assert_eq!(MapRandVar::from_map(BTreeMap::from([(1, Rational64::one())])).unwrap(), eight_dmg);
+        // P(hit & 2d6 = 4) = 11/20 * 3/36, P(crit & 4d6 = 4) = 1/20 * 1/1296
+        let nine_dmg = attack_outcome.conditional_on_second(&9).unwrap();
+        assert_eq!(Rational64::new(1188, 1189), nine_dmg.pdf(1));
+        assert_eq!(None, attack_outcome.conditional_on_second(&6));
+
+        assert!(attack_outcome.covariance() > Rational64::zero());
+        assert!(attack_outcome.correlation().unwrap() > 0.5);
+    }
+
+    #[test]
+    fn test_covariance() {
+        let d6: MRV64 = VecRandVar::new_dice(6).unwrap().into_mrv();
+        let independent = d6.independent_trials_self();
+        assert_eq!(Rational64::zero(), independent.covariance());
+        assert_eq!(Some(0.0), independent.correlation());
+
+        let same = d6.map_keys(|x| Pair(x, x));
+        assert_eq!(d6.variance(), same.covariance());
+        assert!((same.correlation().unwrap() - 1.0).abs() < 1e-12);
+        let opposite = d6.map_keys(|x| Pair(x, -2 * x));
+        assert!((opposite.correlation().unwrap() + 1.0).abs() < 1e-12);
+
+        let constant = d6.map_keys(|x| Pair(x, 3));
+        assert_eq!(Rational64::zero(), constant.covariance());
+        assert_eq!(None, constant.correlation());
+    }
+
     #[test]
     fn test_4d6_drop_lowest() {
         let d6: VRVBig = VecRandVar::new_dice(6).unwrap();
@@ -501,4 +655,29 @@ mod tests {
         assert_eq!(Rational64::new(179, 180), rv.cdf(37));
         assert_eq!(Rational64::new(727,60), rv.expected_value());
     }
+
+    #[test]
+    fn test_cdf_cache() {
+        let mut pdf: BTreeMap<isize, Rational64> = BTreeMap::new();
+        pdf.insert(-4, Rational64::new(1, 6));
+        pdf.insert(0, Rational64::new(1, 3));
+        pdf.insert(7, Rational64::new(1, 4));
+        pdf.insert(8, Rational64::new(1, 12));
+        pdf.insert(20, Rational64::new(1, 6));
+
+        let rv = MapRandVar::from_map(pdf).unwrap();
+        let clone = rv.clone();
+        for x in -6..=22 {
+            let naive: Rational64 = rv.get_keys().take_while(|k| *k <= x).map(|k| rv.pdf(k)).sum();
+            assert_eq!(naive, rv.cdf(x));
+            let naive_ex: Rational64 = rv.get_keys().take_while(|k| *k < x).map(|k| rv.pdf(k)).sum();
+            assert_eq!(naive_ex, rv.cdf_exclusive(x));
+        }
+        assert!(rv.cdf_cache.is_built());
+        assert_eq!(rv, clone);
+
+        let pair_rv: MapRandVar<Pair<isize, isize>, Rational64> = rv.independent_trials_self();
+        let naive: Rational64 = pair_rv.get_keys().take_while(|k| *k <= Pair(7, 0)).map(|k| pair_rv.pdf(k)).sum();
+        assert_eq!(naive, pair_rv.cdf(Pair(7, 0)));
+    }
 }
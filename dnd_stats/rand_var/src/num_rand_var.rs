@@ -1,12 +1,12 @@
 use std::cmp;
-use std::collections::BTreeSet;
-use std::fmt::Display;
-use std::iter::Sum;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::ops::{Add, Div, Mul, Sub};
 
-use num::{One, ToPrimitive, Zero};
+use num::{FromPrimitive, One, ToPrimitive, Zero};
 
-use crate::rand_var::prob_type::{Prob, two, three};
+use crate::rand_var::prob_type::{checked_pow, Prob, Reciprocal, two, three};
 use crate::rand_var::RandVar;
 use crate::rand_var::sequential::{Seq, SeqIter};
 use crate::RVError;
@@ -22,10 +22,92 @@ where
         self.general_expected_value(|k| self.convert(k))
     }
 
+    // same as expected_value, but reports overflow instead of panicking
+    fn try_expected_value(&self) -> Result<P, RVError> {
+        self.try_general_expected_value(|k| Ok(self.convert(k)))
+    }
+
     fn variance(&self) -> P {
-        let ev = self.expected_value();
-        let sq_ev = self.general_expected_value(|k| num::pow(self.convert(k), 2));
-        sq_ev - num::pow(ev, 2)
+        // .unwrap() is fine here unless P can overflow, in which case use try_variance.
+        self.try_variance().unwrap()
+    }
+
+    fn try_variance(&self) -> Result<P, RVError> {
+        let ev = self.try_expected_value()?;
+        let sq_ev = self.try_general_expected_value(|k| checked_pow(&self.convert(k), 2))?;
+        sq_ev.checked_sub(&checked_pow(&ev, 2)?).ok_or(RVError::Overflow)
+    }
+
+    // E[(X - E[X])^n]
+    fn central_moment(&self, n: usize) -> P {
+        // .unwrap() is fine here unless P can overflow, in which case use try_central_moment.
+        self.try_central_moment(n).unwrap()
+    }
+
+    fn try_central_moment(&self, n: usize) -> Result<P, RVError> {
+        let ev = self.try_expected_value()?;
+        self.try_general_expected_value(|k| {
+            let diff = self.convert(k).checked_sub(&ev).ok_or(RVError::Overflow)?;
+            checked_pow(&diff, n)
+        })
+    }
+
+    // the third standardized moment. This is usually irrational, so it's only as exact as an f64,
+    // unlike the kurtosis. Both are None if the variance is 0 (or anything overflows).
+    fn skewness(&self) -> Option<P>
+    where
+        P: Reciprocal + ToPrimitive + FromPrimitive + PartialOrd<P>,
+    {
+        let var = self.try_variance().ok()?;
+        if var <= P::zero() {
+            return None;
+        }
+        // skewness^2 = m3^2 / var^3 is exact, so only the square root is rounded
+        let m3 = self.try_central_moment(3).ok()?;
+        let skew_sq = checked_pow(&m3, 2).ok()?.checked_mul(&checked_pow(&var, 3).ok()?.reciprocal()?)?;
+        let skew = skew_sq.to_f64()?.sqrt();
+        if m3 < P::zero() {
+            P::from_f64(-skew)
+        } else {
+            P::from_f64(skew)
+        }
+    }
+
+    // this is the plain kurtosis, so the excess kurtosis is this minus 3
+    fn kurtosis(&self) -> Option<P>
+    where
+        P: Reciprocal + PartialOrd<P>,
+    {
+        let var = self.try_variance().ok()?;
+        if var <= P::zero() {
+            return None;
+        }
+        let var_sq = checked_pow(&var, 2).ok()?;
+        self.try_central_moment(4).ok()?.checked_mul(&var_sq.reciprocal()?)
+    }
+
+    fn summary(&self) -> RVSummary<K, P>
+    where
+        P: Reciprocal + FromPrimitive + ToPrimitive + PartialOrd<P>,
+    {
+        let tenth = P::from_u8(10).unwrap().reciprocal().unwrap();
+        // rounding can make the variance a tiny bit negative
+        let variance = self.variance();
+        let variance = if variance < P::zero() { P::zero() } else { variance };
+        RVSummary {
+            lower_bound: self.lower_bound(),
+            upper_bound: self.upper_bound(),
+            expected_value: self.expected_value(),
+            std_dev: variance.to_f64().map(|v| v.sqrt()),
+            variance,
+            p10: self.quantile(tenth.clone()),
+            median: self.median(),
+            p90: self.quantile(P::one() - tenth),
+            modes: self.modes(),
+            skewness: self.skewness(),
+            kurtosis: self.kurtosis(),
+            entropy: self.entropy(),
+        }
     }
 
     fn print_stats(&self)
@@ -79,6 +161,14 @@ where
     }
 
     fn add_rv(&self, other: &impl NumRandVar<K, P>) -> Self
+    where
+        Self: Sized
+    {
+        // .unwrap() is fine here unless P can overflow, in which case use try_add_rv.
+        self.try_add_rv(other).unwrap()
+    }
+
+    fn try_add_rv(&self, other: &impl NumRandVar<K, P>) -> Result<Self, RVError>
     where
         Self: Sized
     {
@@ -101,47 +191,187 @@ where
             min_lb = lb;
             max_ub = ub;
         }
-        // .unwrap() is fine here, because if self and other are valid RVs, then this also will be.
-        RandVar::build(
-            seq_iter,
-            |x| convolution(
+        let mut pdf = BTreeMap::new();
+        for x in seq_iter.clone() {
+            let p = convolution(
                 min_lb.clone(),
                 max_ub.clone(),
                 |k1| self.pdf(k1),
                 |k2| other.pdf(k2),
-                x)).unwrap()
+                x.clone()).ok_or(RVError::Overflow)?;
+            pdf.insert(x, p);
+        }
+        RandVar::build(seq_iter, |x| pdf.get(&x).unwrap().clone())
     }
 
     fn multiple(&self, num_times: i32) -> Self
+    where
+        Self: Sized + Clone
+    {
+        // .unwrap() is fine here unless P can overflow, in which case use try_multiple.
+        self.try_multiple(num_times).unwrap()
+    }
+
+    fn try_multiple(&self, num_times: i32) -> Result<Self, RVError>
     where
         Self: Sized + Clone
     {
         if num_times == 0 {
             let seq_iter = SeqIter { items: BTreeSet::from([K::zero()]) };
-            return RandVar::build(seq_iter, |_| P::one()).unwrap();
+            return RandVar::build(seq_iter, |_| P::one());
         } else if num_times == 1 {
-            return self.clone();
+            return Ok(self.clone());
         } else if num_times == -1 {
-            return self.opposite_rv();
+            return Ok(self.opposite_rv());
         }
         let is_neg = num_times < 0;
         let pos_num = num::abs(num_times);
         let mut rv;
         if pos_num % 2 == 0 {
-            rv = self.multiple(pos_num / 2);
-            rv = rv.add_rv(&rv);
+            rv = self.try_multiple(pos_num / 2)?;
+            rv = rv.try_add_rv(&rv)?;
         } else {
-            rv = self.multiple((pos_num - 1) / 2);
-            rv = rv.add_rv(&rv);
-            rv = self.add_rv(&rv);
+            rv = self.try_multiple((pos_num - 1) / 2)?;
+            rv = rv.try_add_rv(&rv)?;
+            rv = self.try_add_rv(&rv)?;
         }
         if is_neg {
-            rv.opposite_rv()
+            Ok(rv.opposite_rv())
         } else {
-            rv
+            Ok(rv)
         }
     }
 
+    // the distribution of the k-th smallest of n independent trials, so
+    // kth_order_statistic(n, n) is the max and kth_order_statistic(n, 1) is the min.
+    fn kth_order_statistic(&self, n: u32, k: u32) -> Result<Self, RVError>
+    where
+        Self: Sized
+    {
+        if k == 0 || k > n {
+            return Err(RVError::InvalidBounds);
+        }
+        let binom = pascal_triangle::<P>(n)?.pop().unwrap();
+        // P(at least k of the n trials are <= x), where f = P(trial <= x)
+        let at_least_k = |f: P| -> Result<P, RVError> {
+            let not_f = P::one() - f.clone();
+            let mut total = P::zero();
+            for j in k..=n {
+                let term = binom[j as usize].checked_mul(&checked_pow(&f, j as usize)?).ok_or(RVError::Overflow)?;
+                let term = term.checked_mul(&checked_pow(&not_f, (n - j) as usize)?).ok_or(RVError::Overflow)?;
+                total = total.checked_add(&term).ok_or(RVError::Overflow)?;
+            }
+            Ok(total)
+        };
+        let mut pdf: BTreeMap<K, P> = BTreeMap::new();
+        for x in self.get_keys() {
+            let p = at_least_k(self.cdf_ref(&x))?.checked_sub(&at_least_k(self.cdf_exclusive_ref(&x))?).ok_or(RVError::Overflow)?;
+            pdf.insert(x, p);
+        }
+        RandVar::build(self.get_keys(), |x| pdf.get(&x).unwrap().clone())
+    }
+
+    // the sum of the k highest of n independent trials (e.g.: 4d6 drop lowest is d6.keep_highest(4, 3))
+    fn keep_highest(&self, n: u32, k: u32) -> Result<Self, RVError>
+    where
+        Self: Sized
+    {
+        if k > n {
+            return Err(RVError::InvalidBounds);
+        }
+        if k == 0 {
+            let seq_iter = SeqIter { items: BTreeSet::from([K::zero()]) };
+            return RandVar::build(seq_iter, |_| P::one());
+        }
+        let binom = pascal_triangle::<P>(n)?;
+        // Go through the values from highest to lowest, deciding how many trials land on each one.
+        // A partial state is (number of trials not yet assigned, sum of the trials assigned so far),
+        // and all assigned trials are kept until there are k of them.
+        let mut states: BTreeMap<(u32, K), P> = BTreeMap::from([((n, K::zero()), P::one())]);
+        let mut result: BTreeMap<K, P> = BTreeMap::new();
+        let values: Vec<K> = self.get_keys().collect();
+        for v in values.into_iter().rev() {
+            let p_v = self.pdf_ref(&v);
+            let below_v = self.cdf_exclusive_ref(&v);
+            let mut next_states: BTreeMap<(u32, K), P> = BTreeMap::new();
+            for ((m, sum), weight) in states.into_iter() {
+                let kept = n - m;
+                let mut v_sum = sum;
+                for j in 0..=m {
+                    if j > 0 {
+                        if p_v.is_zero() {
+                            break;
+                        }
+                        if kept + j <= k {
+                            v_sum = v_sum + v.clone();
+                        }
+                    }
+                    let j_weight = weight.checked_mul(&binom[m as usize][j as usize]).ok_or(RVError::Overflow)?;
+                    let j_weight = j_weight.checked_mul(&checked_pow(&p_v, j as usize)?).ok_or(RVError::Overflow)?;
+                    if kept + j >= k {
+                        // all the kept trials are known, the rest just have to be below v
+                        let total = j_weight.checked_mul(&checked_pow(&below_v, (m - j) as usize)?).ok_or(RVError::Overflow)?;
+                        add_checked(&mut result, v_sum.clone(), total)?;
+                    } else {
+                        add_checked(&mut next_states, (m - j, v_sum.clone()), j_weight)?;
+                    }
+                }
+            }
+            states = next_states;
+        }
+        // any states left over never assigned all their trials, so they have no probability
+        let seq_iter = SeqIter { items: result.keys().cloned().collect() };
+        RandVar::build(seq_iter, |x| result.get(&x).unwrap().clone())
+    }
+
+    // the sum of the k lowest of n independent trials
+    fn keep_lowest(&self, n: u32, k: u32) -> Result<Self, RVError>
+    where
+        Self: Sized
+    {
+        Ok(self.opposite_rv().keep_highest(n, k)?.opposite_rv())
+    }
+
+    // whenever the highest value comes up, roll again and add it, but at most max_depth extra times.
+    // The second value returned is the chance that the last allowed roll was also the highest,
+    // which is the mass that would have kept exploding without the cap (it's left on the top value).
+    fn explode(&self, max_depth: u32) -> Result<(Self, P), RVError>
+    where
+        Self: Sized + Clone
+    {
+        let max = self.upper_bound();
+        let p_max = self.pdf_ref(&max);
+        let mut rv = self.clone();
+        for _ in 0..max_depth {
+            let mut pdf: BTreeMap<K, P> = BTreeMap::new();
+            for k in self.get_keys().filter(|k| k < &max) {
+                let p = self.pdf_ref(&k);
+                pdf.insert(k, p);
+            }
+            for k in rv.get_keys() {
+                let p = p_max.checked_mul(&rv.pdf_ref(&k)).ok_or(RVError::Overflow)?;
+                let total = match pdf.get(&(max.clone() + k.clone())) {
+                    Some(old) => old.checked_add(&p).ok_or(RVError::Overflow)?,
+                    None => p,
+                };
+                pdf.insert(max.clone() + k, total);
+            }
+            let seq_iter = SeqIter { items: pdf.keys().cloned().collect() };
+            rv = RandVar::build(seq_iter, |x| pdf.get(&x).unwrap().clone())?;
+        }
+        Ok((rv, checked_pow(&p_max, (max_depth + 1) as usize)?))
+    }
+
+    // roll, then reroll num_rerolls times and keep the highest result (e.g.: a d20 with advantage is reroll_keep_max(1)).
+    // this rerolls the whole rv, so savage attacker should call it on the total damage rv, not on each die.
+    fn reroll_keep_max(&self, num_rerolls: u32) -> Self
+    where
+        Self: Sized
+    {
+        // .unwrap() is fine here, because k == n is always a valid order statistic.
+        self.kth_order_statistic(num_rerolls + 1, num_rerolls + 1).unwrap()
+    }
+
     fn minus_rv(&self, other: &impl NumRandVar<K, P>) -> Self
     where
         Self: Sized
@@ -223,16 +453,213 @@ where
         let diff_rv = self.minus_rv(other);
         P::one() - diff_rv.cdf_exclusive(K::zero())
     }
+
+    // the most that the two can disagree on the chance of any event, so 0 when they're equal
+    // and 1 when their supports are disjoint. This is half the total absolute difference in the
+    // pdfs, which is the same as the total amount that self's pdf is above other's.
+    fn total_variation(&self, other: &impl NumRandVar<K, P>) -> P
+    where
+        P: PartialOrd<P>,
+    {
+        union_keys(self, other).into_iter()
+            .map(|k| (self.pdf_ref(&k), other.pdf_ref(&k)))
+            .filter(|(p, q)| p > q)
+            .map(|(p, q)| p - q)
+            .sum()
+    }
+
+    // the largest difference between the cdfs
+    fn ks_statistic(&self, other: &impl NumRandVar<K, P>) -> P
+    where
+        P: PartialOrd<P>,
+    {
+        let mut max = P::zero();
+        for k in union_keys(self, other).into_iter() {
+            let diff = abs_diff(self.cdf_ref(&k), other.cdf_ref(&k));
+            if diff > max {
+                max = diff;
+            }
+        }
+        max
+    }
+
+    // earth mover's distance: the area between the cdfs
+    fn wasserstein(&self, other: &impl NumRandVar<K, P>) -> P
+    where
+        P: PartialOrd<P>,
+    {
+        // .unwrap() is fine here unless P can overflow, in which case use try_wasserstein.
+        self.try_wasserstein(other).unwrap()
+    }
+
+    fn try_wasserstein(&self, other: &impl NumRandVar<K, P>) -> Result<P, RVError>
+    where
+        P: PartialOrd<P>,
+    {
+        let keys: Vec<K> = union_keys(self, other).into_iter().collect();
+        let mut self_cdf = P::zero();
+        let mut other_cdf = P::zero();
+        let mut total = P::zero();
+        for pair in keys.windows(2) {
+            self_cdf = self_cdf.checked_add(&self.pdf_ref(&pair[0])).ok_or(RVError::Overflow)?;
+            other_cdf = other_cdf.checked_add(&other.pdf_ref(&pair[0])).ok_or(RVError::Overflow)?;
+            let width = self.convert(pair[1].clone()).checked_sub(&self.convert(pair[0].clone())).ok_or(RVError::Overflow)?;
+            let area = checked_abs_diff(&self_cdf, &other_cdf).and_then(|d| d.checked_mul(&width)).ok_or(RVError::Overflow)?;
+            total = total.checked_add(&area).ok_or(RVError::Overflow)?;
+        }
+        Ok(total)
+    }
+
+    // in bits. This is infinite if self can be something that other can't.
+    fn kl_divergence(&self, other: &impl NumRandVar<K, P>) -> f64
+    where
+        P: ToPrimitive,
+    {
+        let mut total = 0.0;
+        for k in self.get_keys() {
+            let p = self.pdf_ref(&k).to_f64().unwrap();
+            if p > 0.0 {
+                let q = other.pdf_ref(&k).to_f64().unwrap();
+                if q <= 0.0 {
+                    return f64::INFINITY;
+                }
+                total += p * (p / q).log2();
+            }
+        }
+        total
+    }
+
+    // in bits, so it's always between 0 and 1 (which it is exactly when the supports are disjoint)
+    fn js_divergence(&self, other: &impl NumRandVar<K, P>) -> f64
+    where
+        P: ToPrimitive,
+    {
+        // KL from each to the average of the two, which is only zero where both are
+        let kl_to_avg = |p: f64, q: f64| {
+            if p > 0.0 {
+                p * (2.0 * p / (p + q)).log2()
+            } else {
+                0.0
+            }
+        };
+        let mut total = 0.0;
+        for k in union_keys(self, other).into_iter() {
+            let p = self.pdf_ref(&k).to_f64().unwrap();
+            let q = other.pdf_ref(&k).to_f64().unwrap();
+            total += kl_to_avg(p, q) + kl_to_avg(q, p);
+        }
+        total / 2.0
+    }
+}
+
+fn union_keys<K, P, R1, R2>(rv1: &R1, rv2: &R2) -> BTreeSet<K>
+where
+    K: Ord + Clone,
+    P: Prob,
+    R1: RandVar<K, P> + ?Sized,
+    R2: RandVar<K, P> + ?Sized,
+{
+    let mut keys = rv1.get_keys().items;
+    keys.extend(rv2.get_keys());
+    keys
+}
+
+fn abs_diff<P: Prob + PartialOrd<P>>(a: P, b: P) -> P {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+fn checked_abs_diff<P: Prob + PartialOrd<P>>(a: &P, b: &P) -> Option<P> {
+    if a > b {
+        a.checked_sub(b)
+    } else {
+        b.checked_sub(a)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RVSummary<K, P> {
+    pub lower_bound: K,
+    pub upper_bound: K,
+    pub expected_value: P,
+    pub variance: P,
+    pub std_dev: Option<f64>,
+    pub p10: K,
+    pub median: K,
+    pub p90: K,
+    pub modes: Vec<K>,
+    pub skewness: Option<P>,
+    pub kurtosis: Option<P>,
+    pub entropy: f64,
+}
+
+impl<K, P> Display for RVSummary<K, P>
+where
+    K: Display,
+    P: Display + ToPrimitive,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bounds: ({}, {})", self.lower_bound, self.upper_bound)?;
+        writeln!(f, "ev  = {} ~= {}", self.expected_value, self.expected_value.to_f64().unwrap())?;
+        writeln!(f, "var = {} ~= {}", self.variance, self.variance.to_f64().unwrap())?;
+        if let Some(std_dev) = self.std_dev {
+            writeln!(f, "std.dev ~= {}", std_dev)?;
+        }
+        writeln!(f, "10th percentile = {}", self.p10)?;
+        writeln!(f, "median = {}", self.median)?;
+        writeln!(f, "90th percentile = {}", self.p90)?;
+        let modes: Vec<String> = self.modes.iter().map(|k| k.to_string()).collect();
+        writeln!(f, "mode(s) = {}", modes.join(", "))?;
+        if let Some(skew) = self.skewness.as_ref().and_then(|s| s.to_f64()) {
+            writeln!(f, "skewness ~= {}", skew)?;
+        }
+        if let Some(kurt) = self.kurtosis.as_ref().and_then(|k| k.to_f64()) {
+            writeln!(f, "kurtosis ~= {}", kurt)?;
+        }
+        writeln!(f, "entropy ~= {} bits", self.entropy)
+    }
 }
 
-fn convolution<K, P, F1, F2>(lb: K, ub: K, f1: F1, f2: F2, x: K) -> P
+fn convolution<K, P, F1, F2>(lb: K, ub: K, f1: F1, f2: F2, x: K) -> Option<P>
     where
         K: Seq + Sub<K,Output=K>,
-        P: Mul<P, Output=P> + Sum,
+        P: Prob,
         F1: Fn(K) -> P,
         F2: Fn(K) -> P,
 {
-    Seq::gen_seq(&lb, &ub).map(|y| f1(x.clone()-y.clone())*f2(y)).sum()
+    let mut total = P::zero();
+    for y in Seq::gen_seq(&lb, &ub) {
+        total = total.checked_add(&f1(x.clone()-y.clone()).checked_mul(&f2(y))?)?;
+    }
+    Some(total)
+}
+
+fn add_checked<L: Ord, P: Prob>(map: &mut BTreeMap<L, P>, key: L, p: P) -> Result<(), RVError> {
+    let total = match map.get(&key) {
+        Some(old) => old.checked_add(&p).ok_or(RVError::Overflow)?,
+        None => p,
+    };
+    map.insert(key, total);
+    Ok(())
+}
+
+// rows 0 through n of pascal's triangle, so row m, column j is (m choose j)
+fn pascal_triangle<P: Prob>(n: u32) -> Result<Vec<Vec<P>>, RVError> {
+    let mut rows: Vec<Vec<P>> = vec!(vec!(P::one()));
+    for m in 1..=(n as usize) {
+        let prev = &rows[m - 1];
+        let mut row = Vec::with_capacity(m + 1);
+        row.push(P::one());
+        for j in 1..m {
+            row.push(prev[j - 1].checked_add(&prev[j]).ok_or(RVError::Overflow)?);
+        }
+        row.push(P::one());
+        rows.push(row);
+    }
+    Ok(rows)
 }
 
 // any sane numeric type ought to have 1/10 + 2/10 == 3/10
@@ -249,11 +676,13 @@ fn sanity_check<K>() -> bool
 
 #[cfg(test)]
 mod tests {
-    use num::{BigInt, BigRational, FromPrimitive, One, Rational64, Zero};
+    use num::{BigInt, BigRational, FromPrimitive, One, Rational64, ToPrimitive, Zero};
     use crate::map_rand_var::MRV64;
     use crate::num_rand_var::{sanity_check, NumRandVar};
+    use crate::rand_var::prob_type::{F64Prob, Prob, Reciprocal};
     use crate::rand_var::RandVar;
-    use crate::vec_rand_var::{VecRandVar, VRV64, VRVBig};
+    use crate::rand_var::sequential::Nested;
+    use crate::vec_rand_var::{VecRandVar, VRV64, VRVBig, VRVF64};
 
     #[test]
     fn test_minus_d4() {
@@ -473,6 +902,163 @@ mod tests {
         assert_eq!(true, sanity_check::<BigRational>());
         //assert_eq!(false, sanity_check::<f32>()); // TODO: fix this
         assert_eq!(false, sanity_check::<f64>());
+        // F64Prob compares exactly too, approx_eq is what allows for the rounding
+        assert!(!sanity_check::<F64Prob>());
+    }
+
+    #[test]
+    fn test_f64_prob_exact() {
+        let tiny = F64Prob(1e-12);
+        assert!(!tiny.is_zero());
+        assert!(tiny > F64Prob::zero());
+        assert!(tiny.reciprocal().is_some());
+        assert!(tiny.approx_eq(&F64Prob::zero()));
+        assert_eq!(F64Prob::zero(), F64Prob(-0.0));
+
+        let third = F64Prob(1.0) / F64Prob(3.0);
+        let sum = third + third + third;
+        assert!(sum.approx_eq(&F64Prob::one()));
+        assert!(!F64Prob(0.5).approx_eq(&F64Prob(0.5 + 1e-6)));
+    }
+
+    #[test]
+    fn test_kth_order_statistic() {
+        let d20: VRV64 = VecRandVar::new_dice(20).unwrap();
+        assert_eq!(d20.max_two_trials(), d20.kth_order_statistic(2, 2).unwrap());
+        assert_eq!(d20.min_two_trials(), d20.kth_order_statistic(2, 1).unwrap());
+        assert_eq!(d20.max_three_trials(), d20.kth_order_statistic(3, 3).unwrap());
+        assert_eq!(d20, d20.kth_order_statistic(1, 1).unwrap());
+        assert!(d20.kth_order_statistic(3, 0).is_err());
+        assert!(d20.kth_order_statistic(3, 4).is_err());
+
+        // the middle of 3d8, the long way
+        let d8: MRV64 = VecRandVar::new_dice(8).unwrap().into_mrv();
+        let median = d8.independent_trials_self()
+            .independent_trials(&d8)
+            .map_keys(|triple| triple.flat_sum() - triple.flat_max() - triple.flat_min());
+        assert_eq!(median, d8.kth_order_statistic(3, 2).unwrap());
+    }
+
+    #[test]
+    fn test_keep_highest() {
+        let d6: VRVBig = VecRandVar::new_dice(6).unwrap();
+        let ability_score = d6.keep_highest(4, 3).unwrap();
+        assert_eq!(3, ability_score.lower_bound());
+        assert_eq!(18, ability_score.upper_bound());
+        assert_eq!(BigRational::new(BigInt::from_isize(15869).unwrap(), BigInt::from_isize(1296).unwrap()), ability_score.expected_value());
+
+        let d10: VRV64 = VecRandVar::new_dice(10).unwrap();
+        assert_eq!(d10.max_two_trials(), d10.keep_highest(2, 1).unwrap());
+        assert_eq!(d10.max_three_trials(), d10.keep_highest(3, 1).unwrap());
+        assert_eq!(d10.multiple(4), d10.keep_highest(4, 4).unwrap());
+        assert_eq!(VecRandVar::new_constant(0).unwrap(), d10.keep_highest(3, 0).unwrap());
+        assert!(d10.keep_highest(3, 4).is_err());
+
+        // 3d6 keep 2, the long way
+        let d6: MRV64 = VecRandVar::new_dice(6).unwrap().into_mrv();
+        let kh2 = d6.independent_trials_self()
+            .independent_trials(&d6)
+            .map_keys(|triple| triple.flat_sum() - triple.flat_min());
+        assert_eq!(kh2, d6.keep_highest(3, 2).unwrap());
+    }
+
+    #[test]
+    fn test_keep_lowest() {
+        let d12: VRV64 = VecRandVar::new_dice(12).unwrap();
+        assert_eq!(d12.min_two_trials(), d12.keep_lowest(2, 1).unwrap());
+        assert_eq!(d12.multiple(3), d12.keep_lowest(3, 3).unwrap());
+
+        // 3d4 keep 2, the long way
+        let d4: MRV64 = VecRandVar::new_dice(4).unwrap().into_mrv();
+        let kl2 = d4.independent_trials_self()
+            .independent_trials(&d4)
+            .map_keys(|triple| triple.flat_sum() - triple.flat_max());
+        assert_eq!(kl2, d4.keep_lowest(3, 2).unwrap());
+    }
+
+    #[test]
+    fn test_summary_stats() {
+        let two_d6: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(2);
+        assert_eq!(Rational64::zero(), two_d6.central_moment(3));
+        assert_eq!(Rational64::new(161, 2), two_d6.central_moment(4));
+        assert_eq!(Some(Rational64::zero()), two_d6.skewness());
+        assert_eq!(Some(Rational64::new(414, 175)), two_d6.kurtosis());
+
+        let summary = two_d6.summary();
+        assert_eq!(7, summary.median);
+        assert_eq!(vec!(7), summary.modes);
+        assert_eq!(4, summary.p10);
+        assert_eq!(10, summary.p90);
+        assert_eq!(Rational64::new(35, 6), summary.variance);
+        assert!((summary.std_dev.unwrap() - (35.0_f64 / 6.0).sqrt()).abs() < 1e-12);
+        assert_eq!(Some(Rational64::new(414, 175)), summary.kurtosis);
+
+        let exploding: VRV64 = VecRandVar::new_dice_explode(4, 3).unwrap().0;
+        assert!(exploding.skewness().unwrap() > Rational64::zero());
+        let flipped = exploding.opposite_rv();
+        assert_eq!(exploding.skewness().map(|s| -s), flipped.skewness());
+        assert_eq!(None, VRV64::new_constant(3).unwrap().kurtosis());
+        assert_eq!(None, VRV64::new_constant(3).unwrap().skewness());
+        let constant = VRV64::new_constant(3).unwrap().summary();
+        assert_eq!(Some(0.0), constant.std_dev);
+        assert_eq!(None, constant.kurtosis);
+    }
+
+    #[test]
+    fn test_distances() {
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let d8: VRV64 = VecRandVar::new_dice(8).unwrap();
+        assert_eq!(Rational64::zero(), d6.total_variation(&d6));
+        assert_eq!(Rational64::new(1, 4), d6.total_variation(&d8));
+        assert_eq!(Rational64::new(1, 4), d6.ks_statistic(&d8));
+        assert_eq!(Rational64::one(), d6.wasserstein(&d6.add_const(1)));
+        assert_eq!(Rational64::one(), d6.wasserstein(&d8));
+        assert_eq!(0.0, d6.kl_divergence(&d6));
+        assert_eq!(0.0, d6.js_divergence(&d6));
+        assert!((d6.kl_divergence(&d8) - (8.0f64 / 6.0).log2()).abs() < 1e-12);
+        assert_eq!(f64::INFINITY, d8.kl_divergence(&d6));
+
+        // disjoint supports
+        let far = d6.add_const(100);
+        assert_eq!(Rational64::one(), d6.total_variation(&far));
+        assert_eq!(Rational64::one(), d6.ks_statistic(&far));
+        assert_eq!(Rational64::from_integer(100), d6.wasserstein(&far));
+        assert_eq!(f64::INFINITY, d6.kl_divergence(&far));
+        assert!((d6.js_divergence(&far) - 1.0).abs() < 1e-12);
+    }
+
+    fn assert_approx(exact: Rational64, approx: F64Prob) {
+        assert!(F64Prob(exact.to_f64().unwrap()).approx_eq(&approx), "{} vs {}", exact, approx);
+    }
+
+    fn assert_close(exact: &VRV64, approx: &VRVF64) {
+        assert_eq!(exact.lower_bound(), approx.lower_bound());
+        assert_eq!(exact.upper_bound(), approx.upper_bound());
+        for x in exact.get_keys() {
+            assert_approx(exact.pdf(x), approx.pdf(x));
+            assert_approx(exact.cdf(x), approx.cdf(x));
+        }
+        assert_approx(exact.expected_value(), approx.expected_value());
+        assert_approx(exact.variance(), approx.variance());
+    }
+
+    #[test]
+    fn test_f64_matches_exact() {
+        let d12: VRV64 = VecRandVar::new_dice(12).unwrap();
+        let d8: VRV64 = VecRandVar::new_dice(8).unwrap();
+        let f_d12: VRVF64 = VecRandVar::new_dice(12).unwrap();
+        let f_d8: VRVF64 = VecRandVar::new_dice(8).unwrap();
+
+        assert_close(&d12.minus_rv(&d8), &f_d12.minus_rv(&f_d8));
+        assert_close(&d12.multiple(7), &f_d12.multiple(7));
+        assert_close(&d8.multiple(-3).cap_lb(-12).unwrap(), &f_d8.multiple(-3).cap_lb(-12).unwrap());
+        assert_close(&d12.add_const(3).cap_ub(10).unwrap(), &f_d12.add_const(3).cap_ub(10).unwrap());
+        assert_close(&d8.multiple(8).half().unwrap(), &f_d8.multiple(8).half().unwrap());
 
+        let d20: VRV64 = VecRandVar::new_dice(20).unwrap();
+        let f_d20: VRVF64 = VecRandVar::new_dice(20).unwrap();
+        assert_close(&d20.max_three_trials(), &f_d20.max_three_trials());
+        assert_close(&d20.min_two_trials(), &f_d20.min_two_trials());
+        assert_approx(d20.prob_ge(&d12), f_d20.prob_ge(&f_d12));
     }
 }
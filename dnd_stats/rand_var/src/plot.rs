@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use num::ToPrimitive;
+
+use crate::rand_var::prob_type::Prob;
+use crate::rand_var::RandVar;
+use crate::RVError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlotKind {
+    Pdf,
+    Cdf,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BarStyle {
+    Ascii,
+    Unicode, // uses eighth blocks, so it's smoother than Ascii
+}
+
+// used to tell series apart in text output, in order
+const MARKERS: [char; 6] = ['#', '*', '+', '=', 'o', 'x'];
+const COLORS: [&str; 6] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b"];
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+#[derive(Debug, Clone)]
+struct Series<K: Ord> {
+    label: String,
+    pdf: BTreeMap<K, f64>,
+    cdf: BTreeMap<K, f64>,
+}
+
+// One or more RVs drawn on the same axes. With more than one, a legend is added.
+#[derive(Debug, Clone)]
+pub struct Chart<K: Ord + Clone> {
+    kind: PlotKind,
+    series: Vec<Series<K>>,
+}
+
+impl<K: Ord + Clone + Display> Chart<K> {
+    pub fn new(kind: PlotKind) -> Self {
+        Self {
+            kind,
+            series: Vec::new(),
+        }
+    }
+
+    pub fn add<P, R>(&mut self, label: &str, rv: &R)
+    where
+        P: Prob + ToPrimitive,
+        R: RandVar<K, P> + ?Sized,
+    {
+        let mut pdf = BTreeMap::new();
+        let mut cdf = BTreeMap::new();
+        for k in rv.get_keys() {
+            pdf.insert(k.clone(), rv.pdf_ref(&k).to_f64().unwrap());
+            cdf.insert(k.clone(), rv.cdf_ref(&k).to_f64().unwrap());
+        }
+        self.series.push(Series {
+            label: String::from(label),
+            pdf,
+            cdf,
+        });
+    }
+
+    pub fn with<P, R>(mut self, label: &str, rv: &R) -> Self
+    where
+        P: Prob + ToPrimitive,
+        R: RandVar<K, P> + ?Sized,
+    {
+        self.add(label, rv);
+        self
+    }
+
+    fn get_keys(&self) -> Vec<K> {
+        let keys: BTreeSet<&K> = self.series.iter().flat_map(|s| s.pdf.keys()).collect();
+        keys.into_iter().cloned().collect()
+    }
+
+    fn value_at(&self, series: &Series<K>, k: &K) -> f64 {
+        match self.kind {
+            PlotKind::Pdf => *series.pdf.get(k).unwrap_or(&0.0),
+            // outside of the support, the cdf is whatever it was at the last key below k
+            PlotKind::Cdf => series.cdf.range(..=k).next_back().map(|(_, v)| *v).unwrap_or(0.0),
+        }
+    }
+
+    fn max_value(&self) -> f64 {
+        match self.kind {
+            PlotKind::Pdf => self.series.iter().flat_map(|s| s.pdf.values()).cloned().fold(0.0, f64::max),
+            PlotKind::Cdf => 1.0,
+        }
+    }
+
+    fn show_legend(&self) -> bool {
+        self.series.len() > 1
+    }
+
+    // one line per key (per series), with bars at most width characters long
+    pub fn to_text(&self, width: usize, style: BarStyle) -> String {
+        let keys = self.get_keys();
+        let key_strs: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+        let key_width = key_strs.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+        let max = self.max_value();
+
+        let mut text = String::new();
+        if self.show_legend() {
+            for (i, series) in self.series.iter().enumerate() {
+                text.push_str(&format!("{} {}\n", MARKERS[i % MARKERS.len()], series.label));
+            }
+        }
+        for (k, key_str) in keys.iter().zip(key_strs.iter()) {
+            for (i, series) in self.series.iter().enumerate() {
+                let label = if i == 0 { key_str.as_str() } else { "" };
+                let value = self.value_at(series, k);
+                let bar = render_bar(value, max, width, style);
+                if self.show_legend() {
+                    text.push_str(&format!("{:>kw$} {} |{:<w$} {:.4}\n", label, MARKERS[i % MARKERS.len()], bar, value, kw = key_width, w = width));
+                } else {
+                    text.push_str(&format!("{:>kw$} |{:<w$} {:.4}\n", label, bar, value, kw = key_width, w = width));
+                }
+            }
+        }
+        text
+    }
+
+    // a standalone svg: bars for a pdf (side by side when there are several RVs), steps for a cdf
+    pub fn to_svg(&self, width: usize, height: usize) -> String {
+        let keys = self.get_keys();
+        let (left, right, top, bottom) = (60.0, 20.0, 20.0, 40.0);
+        let plot_w = (width as f64) - left - right;
+        let plot_h = (height as f64) - top - bottom;
+        let max = if self.max_value() > 0.0 { self.max_value() } else { 1.0 };
+        let slot = plot_w / (keys.len().max(1) as f64);
+        let y_of = |v: f64| top + plot_h * (1.0 - v / max);
+
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"11\">\n", width, height, width, height);
+        svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height));
+
+        // y axis, with gridlines
+        for i in 0..=5 {
+            let v = max * (i as f64) / 5.0;
+            let y = y_of(v);
+            svg.push_str(&format!("<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"#dddddd\"/>\n", left, y, left + plot_w, y));
+            svg.push_str(&format!("<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\">{:.3}</text>\n", left - 4.0, y + 4.0, v));
+        }
+        svg.push_str(&format!("<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\"/>\n", left, top, left, top + plot_h));
+        svg.push_str(&format!("<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\"/>\n", left, top + plot_h, left + plot_w, top + plot_h));
+
+        // x axis labels, thinned out so they don't overlap
+        let label_every = (keys.len() / 20).max(1);
+        for (i, k) in keys.iter().enumerate().filter(|(i, _)| i % label_every == 0) {
+            let x = left + slot * ((i as f64) + 0.5);
+            svg.push_str(&format!("<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{}</text>\n", x, top + plot_h + 15.0, escape_xml(&k.to_string())));
+        }
+        let axis_name = match self.kind {
+            PlotKind::Pdf => "pdf",
+            PlotKind::Cdf => "cdf",
+        };
+        svg.push_str(&format!("<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{}</text>\n", left + plot_w / 2.0, (height as f64) - 8.0, axis_name));
+
+        for (s_i, series) in self.series.iter().enumerate() {
+            let color = COLORS[s_i % COLORS.len()];
+            match self.kind {
+                PlotKind::Pdf => {
+                    let bar_w = slot * 0.8 / (self.series.len() as f64);
+                    for (i, k) in keys.iter().enumerate() {
+                        let v = self.value_at(series, k);
+                        if v <= 0.0 {
+                            continue;
+                        }
+                        let x = left + slot * ((i as f64) + 0.1) + bar_w * (s_i as f64);
+                        let y = y_of(v);
+                        svg.push_str(&format!("<rect class=\"bar\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"><title>{}: {:.6}</title></rect>\n", x, y, bar_w, top + plot_h - y, color, escape_xml(&k.to_string()), v));
+                    }
+                },
+                PlotKind::Cdf => {
+                    let mut points = Vec::with_capacity(2 * keys.len());
+                    for (i, k) in keys.iter().enumerate() {
+                        let y = y_of(self.value_at(series, k));
+                        points.push(format!("{:.2},{:.2}", left + slot * (i as f64), y));
+                        points.push(format!("{:.2},{:.2}", left + slot * ((i + 1) as f64), y));
+                    }
+                    svg.push_str(&format!("<polyline class=\"step\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>\n", color, points.join(" ")));
+                },
+            }
+        }
+
+        if self.show_legend() {
+            for (s_i, series) in self.series.iter().enumerate() {
+                let y = top + 5.0 + 16.0 * (s_i as f64);
+                let x = left + plot_w - 150.0;
+                svg.push_str(&format!("<rect x=\"{:.2}\" y=\"{:.2}\" width=\"10\" height=\"10\" fill=\"{}\"/>\n", x, y, COLORS[s_i % COLORS.len()]));
+                svg.push_str(&format!("<text class=\"legend\" x=\"{:.2}\" y=\"{:.2}\">{}</text>\n", x + 14.0, y + 9.0, escape_xml(&series.label)));
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn write_svg<Q: AsRef<Path>>(&self, path: Q, width: usize, height: usize) -> Result<(), RVError> {
+        fs::write(path, self.to_svg(width, height)).map_err(|err| RVError::Other(err.to_string()))
+    }
+}
+
+fn render_bar(value: f64, max: f64, width: usize, style: BarStyle) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+    let fraction = (value / max).min(1.0);
+    match style {
+        BarStyle::Ascii => "#".repeat((fraction * (width as f64)).round() as usize),
+        BarStyle::Unicode => {
+            let eighths = (fraction * ((8 * width) as f64)).round() as usize;
+            let mut bar = "█".repeat(eighths / 8);
+            let partial = eighths % 8;
+            if partial > 0 {
+                bar.push(EIGHTHS[partial]);
+            }
+            bar
+        },
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plot::{BarStyle, Chart, PlotKind};
+    use crate::vec_rand_var::{VecRandVar, VRV64};
+
+    #[test]
+    fn test_text() {
+        let d4: VRV64 = VecRandVar::new_dice(4).unwrap();
+        let text = Chart::new(PlotKind::Pdf).with("d4", &d4).to_text(8, BarStyle::Ascii);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(4, lines.len());
+        assert_eq!("1 |######## 0.2500", lines[0]);
+
+        let text = Chart::new(PlotKind::Cdf).with("d4", &d4).to_text(8, BarStyle::Unicode);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("1 |██       0.2500", lines[0]);
+        assert_eq!("4 |████████ 1.0000", lines[3]);
+
+        // the cdf of the d4 stays at 1 past its support
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let text = Chart::new(PlotKind::Cdf).with("d4", &d4).with("d6", &d6).to_text(6, BarStyle::Ascii);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("# d4", lines[0]);
+        assert_eq!("* d6", lines[1]);
+        assert_eq!(2 + 2 * 6, lines.len());
+        assert_eq!("6 # |###### 1.0000", lines[12]);
+        assert_eq!("  * |###### 1.0000", lines[13]);
+    }
+
+    #[test]
+    fn test_svg() {
+        let d4: VRV64 = VecRandVar::new_dice(4).unwrap();
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let svg = Chart::new(PlotKind::Pdf).with("d4 <small>", &d4).with("d6", &d6).to_svg(640, 400);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(10, svg.matches("class=\"bar\"").count());
+        assert_eq!(2, svg.matches("class=\"legend\"").count());
+        assert!(svg.contains("d4 &lt;small&gt;"));
+
+        let svg = Chart::new(PlotKind::Cdf).with("d6", &d6).to_svg(640, 400);
+        assert_eq!(1, svg.matches("class=\"step\"").count());
+        assert_eq!(0, svg.matches("class=\"legend\"").count());
+
+        let path = std::env::temp_dir().join("rand_var_plot_test.svg");
+        Chart::new(PlotKind::Pdf).with("d6", &d6).write_svg(&path, 320, 200).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("<svg"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,10 +1,20 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Display;
+use num::ToPrimitive;
+use rand::Rng;
+use serde::Serialize;
+use alias_table::AliasTable;
 use rv_partition::RVPartition;
 use crate::rand_var::prob_type::{Prob, Reciprocal, two, three};
 use crate::rand_var::sequential::SeqIter;
 use crate::RVError;
+use crate::plot::{BarStyle, Chart, PlotKind};
+use crate::serialization::{csv_field, export_rows, ProbEntry, ProbStrings};
 
+pub mod alias_table;
+pub mod approximation;
+pub mod cdf_cache;
+pub mod mixture;
 pub mod sequential;
 pub mod prob_type;
 pub mod rv_partition;
@@ -63,12 +73,21 @@ where
     fn general_expected_value<F>(&self, f: F) -> P
     where
         F: Fn(K) -> P
+    {
+        // .unwrap() is fine here unless P can overflow, in which case use try_general_expected_value.
+        self.try_general_expected_value(|k| Ok(f(k))).unwrap()
+    }
+
+    fn try_general_expected_value<F>(&self, f: F) -> Result<P, RVError>
+    where
+        F: Fn(K) -> Result<P, RVError>
     {
         let mut result = P::zero();
         for k in self.get_keys() {
-            result = result + self.pdf_ref(&k) * f(k);
+            let term = self.pdf_ref(&k).checked_mul(&f(k)?).ok_or(RVError::Overflow)?;
+            result = result.checked_add(&term).ok_or(RVError::Overflow)?;
         }
-        result
+        Ok(result)
     }
 
     fn cap_lb(&self, lb: K) -> Result<Self, RVError>
@@ -145,6 +164,99 @@ where
         }
     }
 
+    // any value below lb is treated as lb, and any value above ub is treated as ub
+    fn clamp(&self, lb: K, ub: K) -> Result<Self, RVError>
+    where
+        Self: Sized + Clone,
+    {
+        if lb > ub {
+            return Err(RVError::InvalidBounds);
+        }
+        self.cap_lb(lb)?.cap_ub(ub)
+    }
+
+    // Moves the mass of every value less likely than epsilon onto the nearest value that isn't
+    // (counting by position in the keys, and preferring the one below on a tie).
+    // Returns the new RV and the total mass that was moved.
+    fn prune(&self, epsilon: P) -> Result<(Self, P), RVError>
+    where
+        P: PartialOrd<P>,
+        Self: Sized + Clone,
+    {
+        let keys: Vec<K> = self.get_keys().collect();
+        let mut kept: Vec<bool> = keys.iter().map(|k| self.pdf_ref(k) >= epsilon).collect();
+        if !kept.contains(&true) {
+            let mode = self.mode();
+            kept = keys.iter().map(|k| k == &mode).collect();
+        }
+        let kept_idx: Vec<usize> = kept.iter().enumerate().filter(|(_, b)| **b).map(|(i, _)| i).collect();
+        let mut pdf: BTreeMap<K, P> = BTreeMap::new();
+        let mut moved = P::zero();
+        // the index into kept_idx of the next kept value
+        let mut above: usize = 0;
+        for (i, k) in keys.iter().enumerate() {
+            let p = self.pdf_ref(k);
+            let to = if kept[i] {
+                above += 1;
+                i
+            } else {
+                if !p.is_zero() {
+                    moved = moved.checked_add(&p).ok_or(RVError::Overflow)?;
+                }
+                match (above.checked_sub(1).map(|j| kept_idx[j]), kept_idx.get(above)) {
+                    (Some(below), Some(&up)) => if i - below <= up - i { below } else { up },
+                    (Some(below), None) => below,
+                    (None, Some(&up)) => up,
+                    // there's always at least one kept value
+                    (None, None) => unreachable!(),
+                }
+            };
+            let total = match pdf.get(&keys[to]) {
+                Some(old) => old.checked_add(&p).ok_or(RVError::Overflow)?,
+                None => p,
+            };
+            pdf.insert(keys[to].clone(), total);
+        }
+        if moved.is_zero() {
+            return Ok((self.clone(), moved));
+        }
+        let rv = RandVar::build(SeqIter { items: pdf.keys().cloned().collect() }, |k| pdf.get(&k).unwrap().clone())?;
+        Ok((rv, moved))
+    }
+
+    // Splits the values into at most max_len runs of consecutive values, and moves all the mass
+    // of each run onto its most likely value. Returns the new RV and the total mass that was moved.
+    fn bucket(&self, max_len: usize) -> Result<(Self, P), RVError>
+    where
+        P: PartialOrd<P>,
+        Self: Sized + Clone,
+    {
+        if max_len == 0 {
+            return Err(RVError::Other(String::from("max_len must be positive")));
+        }
+        let keys: Vec<K> = self.get_keys().collect();
+        if keys.len() <= max_len {
+            return Ok((self.clone(), P::zero()));
+        }
+        let size = keys.len().div_ceil(max_len);
+        let mut pdf: BTreeMap<K, P> = BTreeMap::new();
+        let mut moved = P::zero();
+        for chunk in keys.chunks(size) {
+            let mut best = &chunk[0];
+            let mut total = P::zero();
+            for k in chunk.iter() {
+                if self.pdf_ref(k) > self.pdf_ref(best) {
+                    best = k;
+                }
+                total = total.checked_add(&self.pdf_ref(k)).ok_or(RVError::Overflow)?;
+            }
+            moved = moved.checked_add(&total).and_then(|m| m.checked_sub(&self.pdf_ref(best))).ok_or(RVError::Overflow)?;
+            pdf.insert(best.clone(), total);
+        }
+        let rv = RandVar::build(SeqIter { items: pdf.keys().cloned().collect() }, |k| pdf.get(&k).unwrap().clone())?;
+        Ok((rv, moved))
+    }
+
     fn pdf_on<F>(&self, pred: F) -> P
     where
         F: Fn(&K) -> bool,
@@ -152,25 +264,44 @@ where
         self.get_keys().filter(pred).map(|k| self.pdf(k)).sum()
     }
 
-    fn get_partition<F>(&self, pred: F) -> RVPartition<K, P, Self>
+    // the chance that pred holds, and the distribution given that it does.
+    // None if pred can't hold.
+    fn condition_on<F>(&self, pred: F) -> Result<Option<(P, Self)>, RVError>
     where
         Self: Sized,
         F: Fn(&K) -> bool,
         P: Reciprocal,
     {
-        let filter_k: BTreeSet<K> = self.get_keys().filter(pred).collect();
-        if filter_k.len() > 0 {
-            let filter_si = SeqIter { items: filter_k };
-            let prob_on: P = filter_si.clone().map(|k| self.pdf(k)).sum();
-            let prob_on_recip = prob_on.clone().reciprocal().unwrap();
-            let slice_rv = RandVar::build(filter_si, |k| self.pdf(k) * prob_on_recip.clone()).unwrap();
-            RVPartition::new(prob_on, slice_rv)
-        } else {
-            RVPartition::empty()
+        let filter_si = SeqIter { items: self.get_keys().filter(pred).collect() };
+        let mut prob_on = P::zero();
+        for k in filter_si.items.iter() {
+            prob_on = prob_on.checked_add(&self.pdf_ref(k)).ok_or(RVError::Overflow)?;
+        }
+        let prob_on_recip = match prob_on.reciprocal() {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+        let mut pdf = BTreeMap::new();
+        for k in filter_si.items.iter() {
+            pdf.insert(k.clone(), self.pdf_ref(k).checked_mul(&prob_on_recip).ok_or(RVError::Overflow)?);
+        }
+        let slice_rv = RandVar::build(filter_si, |k| pdf.get(&k).unwrap().clone())?;
+        Ok(Some((prob_on, slice_rv)))
+    }
+
+    fn get_partition<F>(&self, pred: F) -> Result<RVPartition<K, P, Self>, RVError>
+    where
+        Self: Sized,
+        F: Fn(&K) -> bool,
+        P: Reciprocal,
+    {
+        match self.condition_on(pred)? {
+            Some((prob_on, slice_rv)) => Ok(RVPartition::new(prob_on, slice_rv)),
+            None => Ok(RVPartition::empty()),
         }
     }
 
-    fn partitions<F, K2>(&self, part: F) -> BTreeMap<K2, RVPartition<K, P, Self>>
+    fn partitions<F, K2>(&self, part: F) -> Result<BTreeMap<K2, RVPartition<K, P, Self>>, RVError>
     where
         Self: Sized,
         K2: Ord + Clone,
@@ -188,12 +319,12 @@ where
                 .filter(|(_, v)| *v == &k2)
                 .map(|(k, _)| k.clone())
                 .collect();
-            let partition = self.get_partition(|k| k_set.contains(k));
+            let partition = self.get_partition(|k| k_set.contains(k))?;
             if partition.prob > P::zero() {
                 result.insert(k2, partition);
             }
         }
-        result
+        Ok(result)
     }
 
     fn reroll_once_on<F>(&self, pred: F) -> Self
@@ -217,36 +348,181 @@ where
     where
         Self: Sized
     {
-        let max_pdf = |k| {
-            two::<P>() * self.pdf_ref(&k) * self.cdf_exclusive_ref(&k) + num::pow(self.pdf_ref(&k), 2)
-        };
-        // .unwrap() is fine here, because if self is a valid RV, then this also will be.
-        RandVar::build(self.get_keys(), max_pdf).unwrap()
+        // .unwrap() is fine here unless P can overflow, in which case use try_max_two_trials.
+        self.try_max_two_trials().unwrap()
+    }
+
+    fn try_max_two_trials(&self) -> Result<Self, RVError>
+    where
+        Self: Sized
+    {
+        self.build_from_pdf_cdf(max_two_pdf)
     }
 
     fn min_two_trials(&self) -> Self
     where
         Self: Sized
     {
-        let min_pdf = |k| {
-            let max_pdf = two::<P>() * self.pdf_ref(&k) * self.cdf_exclusive_ref(&k) + num::pow(self.pdf_ref(&k), 2);
-            two::<P>() * self.pdf_ref(&k) - max_pdf
-        };
-        // .unwrap() is fine here, because if self is a valid RV, then this also will be.
-        RandVar::build(self.get_keys(), min_pdf).unwrap()
+        // .unwrap() is fine here unless P can overflow, in which case use try_min_two_trials.
+        self.try_min_two_trials().unwrap()
+    }
+
+    fn try_min_two_trials(&self) -> Result<Self, RVError>
+    where
+        Self: Sized
+    {
+        self.build_from_pdf_cdf(|pdf, cdf_ex| {
+            let max_pdf = max_two_pdf(pdf, cdf_ex)?;
+            two::<P>().checked_mul(pdf)?.checked_sub(&max_pdf)
+        })
     }
 
     fn max_three_trials(&self) -> Self
     where
         Self: Sized
     {
-        let max_pdf = |k| {
-            let x = three::<P>() * self.pdf_ref(&k) * num::pow(self.cdf_exclusive_ref(&k), 2);
-            let y = three::<P>() * num::pow(self.pdf_ref(&k), 2) * self.cdf_exclusive_ref(&k);
-            x + y + num::pow(self.pdf_ref(&k), 3)
-        };
-        // .unwrap() is fine here, because if self is a valid RV, then this also will be.
-        RandVar::build(self.get_keys(), max_pdf).unwrap()
+        // .unwrap() is fine here unless P can overflow, in which case use try_max_three_trials.
+        self.try_max_three_trials().unwrap()
+    }
+
+    fn try_max_three_trials(&self) -> Result<Self, RVError>
+    where
+        Self: Sized
+    {
+        self.build_from_pdf_cdf(|pdf, cdf_ex| {
+            let pdf_sq = pdf.checked_mul(pdf)?;
+            let x = three::<P>().checked_mul(pdf)?.checked_mul(&cdf_ex.checked_mul(cdf_ex)?)?;
+            let y = three::<P>().checked_mul(&pdf_sq)?.checked_mul(cdf_ex)?;
+            x.checked_add(&y)?.checked_add(&pdf_sq.checked_mul(pdf)?)
+        })
+    }
+
+    // builds a new RV on the same keys, where the pdf at k is f(P(X = k), P(X < k)).
+    // f should return None on overflow.
+    fn build_from_pdf_cdf<F>(&self, f: F) -> Result<Self, RVError>
+    where
+        F: Fn(&P, &P) -> Option<P>,
+        Self: Sized
+    {
+        let mut new_pdf = BTreeMap::new();
+        let mut cdf_ex = P::zero();
+        for k in self.get_keys() {
+            let pdf = self.pdf_ref(&k);
+            new_pdf.insert(k, f(&pdf, &cdf_ex).ok_or(RVError::Overflow)?);
+            cdf_ex = cdf_ex.checked_add(&pdf).ok_or(RVError::Overflow)?;
+        }
+        RandVar::build(self.get_keys(), |k| new_pdf[&k].clone())
+    }
+
+    // the smallest k such that P(X <= k) >= q
+    fn quantile(&self, q: P) -> K
+    where
+        P: PartialOrd<P>,
+    {
+        let ub = self.upper_bound();
+        self.get_keys().find(|k| self.cdf_ref(k) >= q).unwrap_or(ub)
+    }
+
+    fn median(&self) -> K
+    where
+        P: Reciprocal + PartialOrd<P>,
+    {
+        self.quantile(two::<P>().reciprocal().unwrap())
+    }
+
+    // all the values with the highest probability, in order
+    fn modes(&self) -> Vec<K>
+    where
+        P: PartialOrd<P>,
+    {
+        let mut modes = Vec::new();
+        let mut best = P::zero();
+        for k in self.get_keys() {
+            let p = self.pdf_ref(&k);
+            if p > best {
+                best = p;
+                modes = vec!(k);
+            } else if p == best && !best.is_zero() {
+                modes.push(k);
+            }
+        }
+        modes
+    }
+
+    fn mode(&self) -> K
+    where
+        P: PartialOrd<P>,
+    {
+        self.modes().swap_remove(0)
+    }
+
+    fn prob_at_least(&self, k: K) -> P {
+        P::one() - self.cdf_exclusive_ref(&k)
+    }
+
+    fn prob_at_most(&self, k: K) -> P {
+        self.cdf_ref(&k)
+    }
+
+    // in bits
+    fn entropy(&self) -> f64
+    where
+        P: ToPrimitive,
+    {
+        self.get_keys()
+            .map(|k| self.pdf_ref(&k).to_f64().unwrap())
+            .filter(|p| *p > 0.0)
+            .map(|p| -p * p.log2())
+            .sum()
+    }
+
+    // inverts the cdf, which is cached for VecRandVar and MapRandVar
+    fn sample(&self, rng: &mut impl Rng) -> K
+    where
+        P: ToPrimitive,
+    {
+        let u: f64 = rng.gen();
+        let ub = self.upper_bound();
+        self.get_keys()
+            .find(|k| !self.pdf_ref(k).is_zero() && self.cdf_ref(k).to_f64().unwrap() > u)
+            .unwrap_or(ub)
+    }
+
+    // builds an alias table, so this is much faster than calling sample n times
+    fn sample_n(&self, rng: &mut impl Rng, n: usize) -> Vec<K>
+    where
+        P: ToPrimitive,
+    {
+        // .unwrap() is fine here, because a valid RV always has some mass.
+        AliasTable::new(self.get_keys(), |k| self.pdf_ref(k)).unwrap().sample_n(rng, n)
+    }
+
+    // a header line, then one line per value with the exact and approximate pdf and cdf.
+    // keys are quoted when their Display needs it (e.g.: Pair has a comma)
+    fn to_csv(&self) -> String
+    where
+        K: Display,
+        P: ProbStrings + ToPrimitive,
+    {
+        let mut csv = String::from("key,pdf,pdf_approx,cdf,cdf_approx\n");
+        let approx = |pe: &ProbEntry| pe.approx.map(|f| f.to_string()).unwrap_or_default();
+        for row in export_rows(self) {
+            csv.push_str(&format!("{},{}/{},{},{}/{},{}\n",
+                csv_field(&row.key.to_string()),
+                row.pdf.numer, row.pdf.denom, approx(&row.pdf),
+                row.cdf.numer, row.cdf.denom, approx(&row.cdf)
+            ));
+        }
+        csv
+    }
+
+    // a list of {key, pdf, cdf}, where the probabilities are written like the serde representation
+    fn to_json(&self) -> Result<String, RVError>
+    where
+        K: Serialize,
+        P: ProbStrings + ToPrimitive,
+    {
+        serde_json::to_string(&export_rows(self)).map_err(|err| RVError::Other(err.to_string()))
     }
 
     fn print_distributions(&self)
@@ -272,17 +548,145 @@ where
             println!("{}: {}\t\t~{}", &k, self.pdf_ref(&k), f(self.pdf_ref(&k)));
         }
     }
+
+    fn print_histogram(&self, kind: PlotKind)
+    where
+        K: Display,
+        P: ToPrimitive,
+    {
+        print!("{}", Chart::new(kind).with("", self).to_text(50, BarStyle::Unicode));
+    }
+}
+
+// P(max(X1, X2) = k) = 2 * P(X = k) * P(X < k) + P(X = k)^2
+fn max_two_pdf<P: Prob>(pdf: &P, cdf_ex: &P) -> Option<P> {
+    let x = two::<P>().checked_mul(pdf)?.checked_mul(cdf_ex)?;
+    x.checked_add(&pdf.checked_mul(pdf)?)
 }
 
 #[cfg(test)]
 mod tests {
     use num::{One, Rational64, Zero};
 
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
     use crate::num_rand_var::NumRandVar;
     use crate::vec_rand_var::{VecRandVar, VRV64};
 
     use super::*;
 
+    #[test]
+    fn test_quantiles() {
+        let d20: VRV64 = VecRandVar::new_dice(20).unwrap();
+        assert_eq!(10, d20.median());
+        assert_eq!(2, d20.quantile(Rational64::new(1, 10)));
+        assert_eq!(1, d20.quantile(Rational64::zero()));
+        assert_eq!(20, d20.quantile(Rational64::one()));
+        assert_eq!(20, d20.modes().len());
+        assert_eq!(1, d20.mode());
+        assert_eq!(Rational64::new(6, 20), d20.prob_at_least(15));
+        assert_eq!(Rational64::new(14, 20), d20.prob_at_most(14));
+        assert!((d20.entropy() - 20f64.log2()).abs() < 1e-12);
+
+        let adv = d20.max_two_trials();
+        assert_eq!(15, adv.median());
+        assert_eq!(vec!(20), adv.modes());
+        assert_eq!(0.0, VRV64::new_constant(4).unwrap().entropy());
+    }
+
+    #[test]
+    fn test_sample() {
+        let two_d6: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(2);
+        let mut rng = StdRng::seed_from_u64(2023);
+        let draws: Vec<isize> = (0..10000).map(|_| two_d6.sample(&mut rng)).collect();
+        assert!(draws.iter().all(|k| (2..=12).contains(k)));
+        let mean = (draws.iter().sum::<isize>() as f64) / 10000.0;
+        assert!((mean - 7.0).abs() < 0.1);
+
+        let mut rng = StdRng::seed_from_u64(2023);
+        assert_eq!(draws[0..10], (0..10).map(|_| two_d6.sample(&mut rng)).collect::<Vec<isize>>());
+
+        let draws = two_d6.sample_n(&mut StdRng::seed_from_u64(5), 10000);
+        let mean = (draws.iter().sum::<isize>() as f64) / 10000.0;
+        assert!((mean - 7.0).abs() < 0.1);
+        assert_eq!(draws, two_d6.sample_n(&mut StdRng::seed_from_u64(5), 10000));
+
+        let constant: VRV64 = VecRandVar::new_constant(-3).unwrap();
+        assert_eq!(vec!(-3, -3, -3), constant.sample_n(&mut rng, 3));
+    }
+
+    #[test]
+    fn test_prune() {
+        let rv: VRV64 = VecRandVar::new(0, 4, vec!(
+            Rational64::new(1, 100),
+            Rational64::new(1, 2),
+            Rational64::new(1, 100),
+            Rational64::new(47, 100),
+            Rational64::new(1, 100),
+        )).unwrap();
+        let (pruned, moved) = rv.prune(Rational64::new(1, 20)).unwrap();
+        assert_eq!(Rational64::new(3, 100), moved);
+        assert_eq!(1, pruned.lower_bound());
+        assert_eq!(3, pruned.upper_bound());
+        assert_eq!(Rational64::new(52, 100), pruned.pdf(1));
+        assert_eq!(Rational64::zero(), pruned.pdf(2));
+        assert_eq!(Rational64::new(48, 100), pruned.pdf(3));
+        assert!(rv.total_variation(&pruned) <= moved);
+
+        let (same, moved) = rv.prune(Rational64::new(1, 100)).unwrap();
+        assert_eq!(rv, same);
+        assert_eq!(Rational64::zero(), moved);
+
+        // nothing is likely enough, so everything goes to the mode
+        let (mode, moved) = rv.prune(Rational64::one()).unwrap();
+        assert_eq!(VecRandVar::new_constant(1).unwrap(), mode);
+        assert_eq!(Rational64::new(1, 2), moved);
+
+        // 3 is closer to 4 than to 0, and 2 is as close to both so it goes down
+        let rv: VRV64 = VecRandVar::new(0, 4, vec!(
+            Rational64::new(1, 2),
+            Rational64::new(1, 100),
+            Rational64::new(2, 100),
+            Rational64::new(3, 100),
+            Rational64::new(44, 100),
+        )).unwrap();
+        let (pruned, moved) = rv.prune(Rational64::new(1, 20)).unwrap();
+        assert_eq!(Rational64::new(6, 100), moved);
+        assert_eq!(Rational64::new(53, 100), pruned.pdf(0));
+        assert_eq!(Rational64::new(47, 100), pruned.pdf(4));
+    }
+
+    #[test]
+    fn test_bucket() {
+        let rv: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(2);
+        let (bucketed, moved) = rv.bucket(4).unwrap();
+        // runs of 3: 2-4, 5-7, 8-10, 11-12
+        assert_eq!(vec!(Rational64::new(6, 36), Rational64::new(15, 36), Rational64::new(12, 36), Rational64::new(3, 36)),
+                   vec!(bucketed.pdf(4), bucketed.pdf(7), bucketed.pdf(8), bucketed.pdf(11)));
+        assert_eq!(Rational64::one() - Rational64::new(3 + 6 + 5 + 2, 36), moved);
+        assert!(rv.total_variation(&bucketed) <= moved);
+
+        let (same, moved) = rv.bucket(11).unwrap();
+        assert_eq!(rv, same);
+        assert_eq!(Rational64::zero(), moved);
+        assert!(rv.bucket(0).is_err());
+    }
+
+    #[test]
+    fn test_condition_on() {
+        let d20: VRV64 = VecRandVar::new_dice(20).unwrap();
+        let (prob, crit_range) = d20.condition_on(|k| *k >= 19).unwrap().unwrap();
+        assert_eq!(Rational64::new(1, 10), prob);
+        assert_eq!(VecRandVar::new_uniform(19, 20).unwrap(), crit_range);
+        assert!(d20.condition_on(|k| *k > 20).unwrap().is_none());
+
+        // a zero probability event can't be conditioned on
+        let gap: VRV64 = VecRandVar::new(1, 3, vec!(Rational64::new(1, 2), Rational64::zero(), Rational64::new(1, 2))).unwrap();
+        assert!(gap.condition_on(|k| *k == 2).unwrap().is_none());
+        assert!(gap.get_partition(|k| *k == 2).unwrap().rv.is_none());
+    }
+
     #[test]
     fn test_reroll() {
         let rv1: VRV64 = VecRandVar::new_dice_reroll(10, 2).unwrap();
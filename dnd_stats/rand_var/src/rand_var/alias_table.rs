@@ -0,0 +1,148 @@
+use num::ToPrimitive;
+use rand::Rng;
+
+use crate::rand_var::prob_type::Prob;
+use crate::rand_var::RandVar;
+use crate::rand_var::sequential::SeqIter;
+
+// Vose's alias method: O(n) to build, then O(1) for each draw.
+// Values with no probability are left out entirely so they can never be drawn,
+// and there's no table at all if nothing has any probability.
+#[derive(Debug, Clone)]
+pub struct AliasTable<K> {
+    keys: Vec<K>,
+    probs: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl<K: Ord + Clone> AliasTable<K> {
+    pub fn new<P, F>(seq_iter: SeqIter<K>, f: F) -> Option<Self>
+    where
+        P: Prob + ToPrimitive,
+        F: Fn(&K) -> P,
+    {
+        let mut keys = Vec::new();
+        let mut scaled = Vec::new();
+        for k in seq_iter {
+            let p = f(&k).to_f64().unwrap();
+            if p > 0.0 {
+                keys.push(k);
+                scaled.push(p);
+            }
+        }
+        if keys.is_empty() {
+            return None;
+        }
+        let total: f64 = scaled.iter().sum();
+        let n = keys.len();
+        for p in scaled.iter_mut() {
+            *p *= (n as f64) / total;
+        }
+
+        let mut probs = vec!(1.0; n);
+        let mut aliases: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..n).partition(|i| scaled[*i] < 1.0);
+        while let (Some(s), Some(l)) = (small.pop(), large.pop()) {
+            probs[s] = scaled[s];
+            aliases[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                small.push(l);
+            } else {
+                large.push(l);
+            }
+        }
+        // anything left over is (up to rounding) exactly 1, which the defaults already are
+        Some(Self {
+            keys,
+            probs,
+            aliases,
+        })
+    }
+
+    pub fn from_rv<P: Prob + ToPrimitive>(rv: &impl RandVar<K, P>) -> Option<Self> {
+        Self::new(rv.get_keys(), |k| rv.pdf_ref(k))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> K {
+        let i = rng.gen_range(0..self.keys.len());
+        if rng.gen::<f64>() < self.probs[i] {
+            self.keys[i].clone()
+        } else {
+            self.keys[self.aliases[i]].clone()
+        }
+    }
+
+    pub fn sample_n(&self, rng: &mut impl Rng, n: usize) -> Vec<K> {
+        (0..n).map(|_| self.sample(rng)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use num::{One, Rational64, ToPrimitive, Zero};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::num_rand_var::NumRandVar;
+    use crate::rand_var::alias_table::AliasTable;
+    use crate::rand_var::RandVar;
+    use crate::rand_var::sequential::SeqIter;
+    use crate::vec_rand_var::{VecRandVar, VRV64};
+
+    #[test]
+    fn test_alias_frequencies() {
+        // lopsided, with a gap in the middle
+        let rv: VRV64 = VecRandVar::new(0, 4, vec!(
+            Rational64::new(1, 2),
+            Rational64::new(1, 8),
+            Rational64::new(0, 1),
+            Rational64::new(1, 8),
+            Rational64::new(1, 4),
+        )).unwrap();
+        let table = AliasTable::from_rv(&rv).unwrap();
+        assert_eq!(4, table.len());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let n = 100000;
+        let mut counts: BTreeMap<isize, usize> = BTreeMap::new();
+        for k in table.sample_n(&mut rng, n) {
+            *counts.entry(k).or_insert(0) += 1;
+        }
+        assert_eq!(None, counts.get(&2));
+        for k in rv.get_keys().filter(|k| *k != 2) {
+            let freq = (*counts.get(&k).unwrap() as f64) / (n as f64);
+            assert!((freq - rv.pdf(k).to_f64().unwrap()).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_alias_deterministic() {
+        let rv: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(3);
+        let table = AliasTable::from_rv(&rv).unwrap();
+        let draws1 = table.sample_n(&mut StdRng::seed_from_u64(11), 50);
+        let draws2 = table.sample_n(&mut StdRng::seed_from_u64(11), 50);
+        assert_eq!(draws1, draws2);
+        assert!(draws1.iter().all(|k| (3..=18).contains(k)));
+    }
+
+    #[test]
+    fn test_alias_no_mass() {
+        let keys = SeqIter { items: BTreeSet::from([1, 2, 3]) };
+        assert!(AliasTable::new(keys, |_| Rational64::zero()).is_none());
+        let keys = SeqIter { items: BTreeSet::from([1, 2, 3]) };
+        let table = AliasTable::new(keys, |k| if *k == 2 { Rational64::one() } else { Rational64::zero() }).unwrap();
+        assert_eq!(1, table.len());
+        assert_eq!(vec!(2, 2, 2), table.sample_n(&mut StdRng::seed_from_u64(3), 3));
+    }
+}
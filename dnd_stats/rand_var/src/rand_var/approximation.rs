@@ -0,0 +1,24 @@
+use crate::rand_var::prob_type::Prob;
+use crate::rand_var::RandVar;
+use crate::RVError;
+
+// an opt-in way to keep RVs small, at the cost of moving some probability around
+#[derive(Debug, Clone, PartialEq)]
+pub enum Approximation<P> {
+    Prune(P),
+    Bucket(usize),
+}
+
+impl<P: Prob + PartialOrd<P>> Approximation<P> {
+    // returns the approximated RV and the mass that was moved
+    pub fn apply<K, R>(&self, rv: &R) -> Result<(R, P), RVError>
+    where
+        K: Ord + Clone,
+        R: RandVar<K, P> + Clone,
+    {
+        match self {
+            Approximation::Prune(epsilon) => rv.prune(epsilon.clone()),
+            Approximation::Bucket(max_len) => rv.bucket(*max_len),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::cell::OnceCell;
+use std::fmt::{Debug, Formatter};
+
+// A lazily built table of cumulative values, owned by a random variable.
+// It is derived entirely from the (immutable) pdf, so two RVs compare
+// equal regardless of whether either of them has built its cache yet.
+#[derive(Clone, Default)]
+pub struct CdfCache<T> {
+    table: OnceCell<Vec<T>>,
+}
+
+impl<T> CdfCache<T> {
+    pub fn new() -> Self {
+        Self {
+            table: OnceCell::new(),
+        }
+    }
+
+    pub fn get_or_build<F>(&self, f: F) -> &Vec<T>
+    where
+        F: FnOnce() -> Vec<T>
+    {
+        self.table.get_or_init(f)
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.table.get().is_some()
+    }
+}
+
+impl<T> PartialEq for CdfCache<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Debug for CdfCache<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CdfCache")
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use crate::rand_var::prob_type::{Prob, Reciprocal};
+use crate::rand_var::RandVar;
+use crate::rand_var::sequential::SeqIter;
+use crate::RVError;
+
+// A weighted combination of RVs, i.e.: pick one of them with probability equal
+// to its weight, then take its value. Outcomes with no probability are left out.
+#[derive(Debug, Clone)]
+pub struct Mixture<K: Ord + Clone, P: Prob> {
+    pdf: BTreeMap<K, P>,
+    total_weight: P,
+}
+
+impl<K, P> Mixture<K, P>
+where
+    K: Ord + Clone,
+    P: Prob + PartialOrd<P>,
+{
+    pub fn new() -> Self {
+        Self {
+            pdf: BTreeMap::new(),
+            total_weight: P::zero(),
+        }
+    }
+
+    pub fn add(&mut self, weight: P, rv: &impl RandVar<K, P>) -> Result<(), RVError> {
+        if weight < P::zero() {
+            return Err(RVError::NegProb);
+        }
+        if weight.is_zero() {
+            return Ok(());
+        }
+        self.total_weight = self.total_weight.checked_add(&weight).ok_or(RVError::Overflow)?;
+        for k in rv.get_keys() {
+            let p = weight.checked_mul(&rv.pdf_ref(&k)).ok_or(RVError::Overflow)?;
+            if p.is_zero() {
+                continue;
+            }
+            let total = match self.pdf.get(&k) {
+                Some(old) => old.checked_add(&p).ok_or(RVError::Overflow)?,
+                None => p,
+            };
+            self.pdf.insert(k, total);
+        }
+        Ok(())
+    }
+
+    pub fn with(mut self, weight: P, rv: &impl RandVar<K, P>) -> Result<Self, RVError> {
+        self.add(weight, rv)?;
+        Ok(self)
+    }
+
+    pub fn get_total_weight(&self) -> &P {
+        &self.total_weight
+    }
+
+    // the weights have to add up to one
+    pub fn build<RV: RandVar<K, P>>(self) -> Result<RV, RVError> {
+        if !self.total_weight.approx_eq(&P::one()) {
+            return Err(RVError::CDFNotOne);
+        }
+        let seq_iter = SeqIter { items: self.pdf.keys().cloned().collect() };
+        RandVar::build(seq_iter, |k| self.pdf.get(&k).unwrap().clone())
+    }
+
+    // scales the weights so they add up to one, and also returns what they added up to
+    pub fn build_normalized<RV: RandVar<K, P>>(self) -> Result<(P, RV), RVError>
+    where
+        P: Reciprocal
+    {
+        let scale = match self.total_weight.reciprocal() {
+            Some(s) => s,
+            None => return Err(RVError::Other(String::from("a mixture needs some positive weight"))),
+        };
+        let mut pdf = BTreeMap::new();
+        for (k, p) in self.pdf.iter() {
+            pdf.insert(k.clone(), p.checked_mul(&scale).ok_or(RVError::Overflow)?);
+        }
+        let seq_iter = SeqIter { items: pdf.keys().cloned().collect() };
+        let rv = RandVar::build(seq_iter, |k| pdf.get(&k).unwrap().clone())?;
+        Ok((self.total_weight, rv))
+    }
+}
+
+impl<K, P> Default for Mixture<K, P>
+where
+    K: Ord + Clone,
+    P: Prob + PartialOrd<P>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{One, Rational64, Zero};
+
+    use crate::map_rand_var::MRV64;
+    use crate::num_rand_var::NumRandVar;
+    use crate::rand_var::mixture::Mixture;
+    use crate::rand_var::RandVar;
+    use crate::RVError;
+    use crate::vec_rand_var::{VecRandVar, VRV64};
+
+    #[test]
+    fn test_mixture() {
+        // half the time a d4, otherwise a d8
+        let d4: VRV64 = VecRandVar::new_dice(4).unwrap();
+        let d8: VRV64 = VecRandVar::new_dice(8).unwrap();
+        let half = Rational64::new(1, 2);
+        let rv: VRV64 = Mixture::new()
+            .with(half, &d4).unwrap()
+            .with(half, &d8).unwrap()
+            .build().unwrap();
+        assert_eq!(Rational64::new(3, 16), rv.pdf(1));
+        assert_eq!(Rational64::new(1, 16), rv.pdf(8));
+        assert_eq!(half * d4.expected_value() + half * d8.expected_value(), rv.expected_value());
+
+        // zero weights and zero probabilities are skipped
+        let mrv: MRV64 = Mixture::new()
+            .with(Rational64::one(), &d4).unwrap()
+            .with(Rational64::zero(), &d8).unwrap()
+            .build().unwrap();
+        assert_eq!(d4.into_mrv(), mrv);
+    }
+
+    #[test]
+    fn test_mixture_weights() {
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let mut mixture = Mixture::new();
+        assert!(matches!(mixture.add(Rational64::new(-1, 2), &d6), Err(RVError::NegProb)));
+        mixture.add(Rational64::new(1, 3), &d6).unwrap();
+        assert!(matches!(mixture.clone().build::<VRV64>(), Err(RVError::CDFNotOne)));
+
+        mixture.add(Rational64::new(1, 3), &d6.add_const(6)).unwrap();
+        let (total, rv): (Rational64, VRV64) = mixture.build_normalized().unwrap();
+        assert_eq!(Rational64::new(2, 3), total);
+        assert_eq!(VecRandVar::new_dice(12).unwrap(), rv);
+
+        let empty: Mixture<isize, Rational64> = Mixture::new();
+        assert!(empty.build_normalized::<VRV64>().is_err());
+    }
+}
@@ -1,12 +1,47 @@
-use std::fmt::Debug;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
 use std::iter::Sum;
-use std::ops::{Add, Mul, Sub};
+use std::ops::{Add, Div, Mul, Sub};
 
-use num::{FromPrimitive, Integer, One, Zero};
+use num::{CheckedAdd, CheckedMul, CheckedSub, FromPrimitive, Integer, One, ToPrimitive, Zero};
 use num::rational::Ratio;
 
+use crate::RVError;
+
+pub mod adaptive_rational;
+
 // The minimum collection of traits required of the probability-like type for the RandVar and NumRandVar traits
-pub trait Prob: Zero + One + Sum + Add<Self, Output=Self> + Sub<Self, Output=Self> + Mul<Self, Output=Self> + Clone {}
+// The checked operations return None on overflow (or any other loss of the value).
+// Anything that returns a Result reports overflow as RVError::Overflow. The rest is unchecked and can panic:
+// the statistics (expected_value, variance, moments, wasserstein, ...) and the max/min of trials have try_ versions,
+// and adding RVPartitions together can't return an error. The cdf tables are safe, since building
+// an RV already checked the same partial sums.
+pub trait Prob: Zero + One + Sum + Add<Self, Output=Self> + Sub<Self, Output=Self> + Mul<Self, Output=Self> + CheckedAdd + CheckedSub + CheckedMul + PartialEq + Clone {
+    // equality up to rounding error. This is exact for the rational types
+    fn approx_eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+pub fn checked_sum<'a, P, I>(iter: I) -> Result<P, RVError>
+where
+    P: Prob + 'a,
+    I: Iterator<Item=&'a P>
+{
+    let mut total = P::zero();
+    for p in iter {
+        total = total.checked_add(p).ok_or(RVError::Overflow)?;
+    }
+    Ok(total)
+}
+
+pub fn checked_pow<P: Prob>(p: &P, exp: usize) -> Result<P, RVError> {
+    let mut total = P::one();
+    for _ in 0..exp {
+        total = total.checked_mul(p).ok_or(RVError::Overflow)?;
+    }
+    Ok(total)
+}
 
 pub fn two<K>() -> K
 where
@@ -22,7 +57,7 @@ where
     K::one() + K::one() + K::one()
 }
 
-impl<I: Integer + Clone> Prob for Ratio<I> {}
+impl<I: Integer + Clone + CheckedAdd + CheckedSub + CheckedMul> Prob for Ratio<I> {}
 
 pub trait Reciprocal {
     fn reciprocal(&self) -> Option<Self> where Self: Sized;
@@ -40,4 +75,170 @@ impl<I: Integer + Clone> Reciprocal for Ratio<I> {
 
 // The collection of traits needed for all methods in the RandomVariable and MapRandVar structs
 pub trait RVProb: Prob + PartialOrd<Self> + Ord + PartialEq<Self> + FromPrimitive + Reciprocal + for<'a> Sum<&'a Self> + Debug {}
-impl<I: Integer + Clone + Debug> RVProb for Ratio<I> where Ratio<I>: FromPrimitive {}
+impl<I: Integer + Clone + CheckedAdd + CheckedSub + CheckedMul + Debug> RVProb for Ratio<I> where Ratio<I>: FromPrimitive {}
+
+// Floating point probabilities, for when speed matters more than exactness.
+// Comparisons are exact (by f64::total_cmp), use approx_eq to allow for rounding error.
+// Values within F64_PROB_EPSILON (relative to their size, or absolutely near 0) are approx_eq.
+pub const F64_PROB_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct F64Prob(pub f64);
+
+impl F64Prob {
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+impl Ord for F64Prob {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // adding 0.0 turns -0.0 into 0.0, which total_cmp would otherwise order below it
+        (self.0 + 0.0).total_cmp(&(other.0 + 0.0))
+    }
+}
+
+impl PartialOrd for F64Prob {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for F64Prob {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for F64Prob {}
+
+impl Add for F64Prob {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        F64Prob(self.0 + rhs.0)
+    }
+}
+
+impl Sub for F64Prob {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        F64Prob(self.0 - rhs.0)
+    }
+}
+
+impl Mul for F64Prob {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        F64Prob(self.0 * rhs.0)
+    }
+}
+
+impl Div for F64Prob {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        F64Prob(self.0 / rhs.0)
+    }
+}
+
+// a float "overflows" once it is no longer finite
+impl CheckedAdd for F64Prob {
+    fn checked_add(&self, v: &Self) -> Option<Self> {
+        Some(*self + *v).filter(|p| p.0.is_finite())
+    }
+}
+
+impl CheckedSub for F64Prob {
+    fn checked_sub(&self, v: &Self) -> Option<Self> {
+        Some(*self - *v).filter(|p| p.0.is_finite())
+    }
+}
+
+impl CheckedMul for F64Prob {
+    fn checked_mul(&self, v: &Self) -> Option<Self> {
+        Some(*self * *v).filter(|p| p.0.is_finite())
+    }
+}
+
+impl Zero for F64Prob {
+    fn zero() -> Self {
+        F64Prob(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0.0
+    }
+}
+
+impl One for F64Prob {
+    fn one() -> Self {
+        F64Prob(1.0)
+    }
+}
+
+impl Sum for F64Prob {
+    fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
+        F64Prob(iter.map(|p| p.0).sum())
+    }
+}
+
+impl<'a> Sum<&'a F64Prob> for F64Prob {
+    fn sum<I: Iterator<Item=&'a F64Prob>>(iter: I) -> Self {
+        F64Prob(iter.map(|p| p.0).sum())
+    }
+}
+
+impl FromPrimitive for F64Prob {
+    fn from_i64(n: i64) -> Option<Self> {
+        Some(F64Prob(n as f64))
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(F64Prob(n as f64))
+    }
+
+    fn from_f64(n: f64) -> Option<Self> {
+        Some(F64Prob(n))
+    }
+}
+
+impl ToPrimitive for F64Prob {
+    fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.0)
+    }
+}
+
+impl Display for F64Prob {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Reciprocal for F64Prob {
+    fn reciprocal(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(F64Prob(1.0 / self.0))
+        }
+    }
+}
+
+impl Prob for F64Prob {
+    fn approx_eq(&self, other: &Self) -> bool {
+        let scale = self.0.abs().max(other.0.abs()).max(1.0);
+        (self.0 - other.0).abs() <= F64_PROB_EPSILON * scale
+    }
+}
+impl RVProb for F64Prob {}
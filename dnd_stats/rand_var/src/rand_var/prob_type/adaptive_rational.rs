@@ -0,0 +1,306 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Sub};
+
+use num::{BigInt, BigRational, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, FromPrimitive, One, Rational64, ToPrimitive, Zero};
+
+use crate::rand_var::prob_type::{Prob, Reciprocal, RVProb};
+
+// An exact probability that is a Rational64 for as long as it can be,
+// and is promoted to a BigRational whenever an operation would overflow.
+// Results that fit back into a Rational64 are demoted again, so the
+// (much faster) small representation is used wherever possible.
+#[derive(Debug, Clone)]
+pub enum AdaptiveRational {
+    Small(Rational64),
+    Big(BigRational),
+}
+
+impl AdaptiveRational {
+    pub fn is_big(&self) -> bool {
+        matches!(self, AdaptiveRational::Big(_))
+    }
+
+    pub fn to_big(&self) -> BigRational {
+        match self {
+            AdaptiveRational::Small(r) => BigRational::new(BigInt::from(*r.numer()), BigInt::from(*r.denom())),
+            AdaptiveRational::Big(r) => r.clone(),
+        }
+    }
+
+    fn from_big(r: BigRational) -> Self {
+        match (r.numer().to_i64(), r.denom().to_i64()) {
+            (Some(n), Some(d)) => AdaptiveRational::Small(Rational64::new_raw(n, d)),
+            _ => AdaptiveRational::Big(r),
+        }
+    }
+
+    fn combine<S, B>(&self, other: &Self, small_op: S, big_op: B) -> Self
+    where
+        S: Fn(&Rational64, &Rational64) -> Option<Rational64>,
+        B: Fn(BigRational, BigRational) -> BigRational,
+    {
+        if let (AdaptiveRational::Small(a), AdaptiveRational::Small(b)) = (self, other) {
+            if let Some(r) = small_op(a, b) {
+                return AdaptiveRational::Small(r);
+            }
+        }
+        Self::from_big(big_op(self.to_big(), other.to_big()))
+    }
+}
+
+impl From<Rational64> for AdaptiveRational {
+    fn from(value: Rational64) -> Self {
+        AdaptiveRational::Small(value)
+    }
+}
+
+impl From<BigRational> for AdaptiveRational {
+    fn from(value: BigRational) -> Self {
+        Self::from_big(value)
+    }
+}
+
+impl Add for AdaptiveRational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.combine(&rhs, |a, b| a.checked_add(b), |a, b| a + b)
+    }
+}
+
+impl Sub for AdaptiveRational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.combine(&rhs, |a, b| a.checked_sub(b), |a, b| a - b)
+    }
+}
+
+impl Mul for AdaptiveRational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.combine(&rhs, |a, b| a.checked_mul(b), |a, b| a * b)
+    }
+}
+
+impl Div for AdaptiveRational {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.combine(&rhs, |a, b| a.checked_div(b), |a, b| a / b)
+    }
+}
+
+// promotion means these can never overflow
+impl CheckedAdd for AdaptiveRational {
+    fn checked_add(&self, v: &Self) -> Option<Self> {
+        Some(self.clone() + v.clone())
+    }
+}
+
+impl CheckedSub for AdaptiveRational {
+    fn checked_sub(&self, v: &Self) -> Option<Self> {
+        Some(self.clone() - v.clone())
+    }
+}
+
+impl CheckedMul for AdaptiveRational {
+    fn checked_mul(&self, v: &Self) -> Option<Self> {
+        Some(self.clone() * v.clone())
+    }
+}
+
+impl Zero for AdaptiveRational {
+    fn zero() -> Self {
+        AdaptiveRational::Small(Rational64::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            AdaptiveRational::Small(r) => r.is_zero(),
+            AdaptiveRational::Big(r) => r.is_zero(),
+        }
+    }
+}
+
+impl One for AdaptiveRational {
+    fn one() -> Self {
+        AdaptiveRational::Small(Rational64::one())
+    }
+}
+
+impl Sum for AdaptiveRational {
+    fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, p| acc + p)
+    }
+}
+
+impl<'a> Sum<&'a AdaptiveRational> for AdaptiveRational {
+    fn sum<I: Iterator<Item=&'a AdaptiveRational>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, p| acc + p.clone())
+    }
+}
+
+impl Ord for AdaptiveRational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            // Ratio's comparison doesn't overflow
+            (AdaptiveRational::Small(a), AdaptiveRational::Small(b)) => a.cmp(b),
+            _ => self.to_big().cmp(&other.to_big()),
+        }
+    }
+}
+
+impl PartialOrd for AdaptiveRational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for AdaptiveRational {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for AdaptiveRational {}
+
+impl FromPrimitive for AdaptiveRational {
+    fn from_i64(n: i64) -> Option<Self> {
+        Some(AdaptiveRational::Small(Rational64::from_integer(n)))
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(Self::from_big(BigRational::from_integer(BigInt::from(n))))
+    }
+
+    fn from_f64(n: f64) -> Option<Self> {
+        BigRational::from_float(n).map(Self::from_big)
+    }
+}
+
+impl ToPrimitive for AdaptiveRational {
+    fn to_i64(&self) -> Option<i64> {
+        match self {
+            AdaptiveRational::Small(r) => r.to_i64(),
+            AdaptiveRational::Big(r) => r.to_i64(),
+        }
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        match self {
+            AdaptiveRational::Small(r) => r.to_u64(),
+            AdaptiveRational::Big(r) => r.to_u64(),
+        }
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        match self {
+            AdaptiveRational::Small(r) => r.to_f64(),
+            AdaptiveRational::Big(r) => r.to_f64(),
+        }
+    }
+}
+
+impl Display for AdaptiveRational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdaptiveRational::Small(r) => write!(f, "{}", r),
+            AdaptiveRational::Big(r) => write!(f, "{}", r),
+        }
+    }
+}
+
+impl Reciprocal for AdaptiveRational {
+    fn reciprocal(&self) -> Option<Self> {
+        match self {
+            AdaptiveRational::Small(r) => r.reciprocal().map(AdaptiveRational::Small),
+            AdaptiveRational::Big(r) => r.reciprocal().map(AdaptiveRational::Big),
+        }
+    }
+}
+
+impl Prob for AdaptiveRational {}
+impl RVProb for AdaptiveRational {}
+
+#[cfg(test)]
+mod tests {
+    use num::{BigInt, BigRational, One, Rational64, Zero};
+
+    use crate::num_rand_var::NumRandVar;
+    use crate::rand_var::prob_type::adaptive_rational::AdaptiveRational;
+    use crate::rand_var::RandVar;
+    use crate::RVError;
+    use crate::vec_rand_var::{VecRandVar, VRV64};
+
+    #[test]
+    fn test_promotion() {
+        let big = AdaptiveRational::from(Rational64::new(i64::MAX, 3));
+        let sum = big.clone() + big.clone();
+        assert!(sum.is_big());
+        let expected = BigRational::new(BigInt::from(i64::MAX) * BigInt::from(2), BigInt::from(3));
+        assert_eq!(AdaptiveRational::Big(expected), sum);
+
+        // once the value fits again, it goes back to being small
+        let diff = sum - big.clone();
+        assert!(!diff.is_big());
+        assert_eq!(big, diff);
+        assert_eq!(AdaptiveRational::zero(), big.clone() - big);
+    }
+
+    #[test]
+    fn test_rv_overflow() {
+        // the denominators grow like 1000000007^n
+        let p = Rational64::new(1, 1000000007);
+        let rv: VRV64 = VecRandVar::new(0, 1, vec!(Rational64::one() - p, p)).unwrap();
+        assert!(rv.try_multiple(2).is_ok());
+        assert!(matches!(rv.try_multiple(3), Err(RVError::Overflow)));
+
+        let p = AdaptiveRational::from(p);
+        let rv: VecRandVar<AdaptiveRational> = VecRandVar::new(0, 1, vec!(AdaptiveRational::one() - p.clone(), p.clone())).unwrap();
+        let rv3 = rv.try_multiple(3).unwrap();
+        assert_eq!(0, rv3.lower_bound());
+        assert_eq!(3, rv3.upper_bound());
+        assert!(rv3.pdf(3).is_big());
+        assert_eq!(p.clone() * p.clone() * p.clone(), rv3.pdf(3));
+        let three = AdaptiveRational::from(Rational64::from_integer(3));
+        assert_eq!(three * p, rv3.expected_value());
+    }
+
+    #[test]
+    fn test_checked_stats() {
+        let p = Rational64::new(1, 1000000007);
+        let rv: VRV64 = VecRandVar::new(0, 1, vec!(Rational64::one() - p, p)).unwrap();
+        assert!(rv.kth_order_statistic(2, 2).is_ok());
+        assert!(matches!(rv.kth_order_statistic(3, 3), Err(RVError::Overflow)));
+        assert!(matches!(rv.keep_highest(3, 1), Err(RVError::Overflow)));
+        assert!(matches!(rv.explode(2), Err(RVError::Overflow)));
+
+        // the keys are big enough that the expected value doesn't fit
+        let max = isize::MAX;
+        let rv: VRV64 = VecRandVar::new(max - 1, max, vec!(Rational64::new(1, 3), Rational64::new(2, 3))).unwrap();
+        assert!(matches!(rv.try_expected_value(), Err(RVError::Overflow)));
+        let rv: VRV64 = VecRandVar::new_dice(6).unwrap();
+        assert_eq!(Rational64::new(7, 2), rv.try_expected_value().unwrap());
+        assert_eq!(Rational64::new(35, 12), rv.try_variance().unwrap());
+
+        // squaring a 1/4000000007 mass doesn't fit in an i64
+        let p = Rational64::new(1, 4000000007);
+        let rv: VRV64 = VecRandVar::new(0, 1, vec!(Rational64::one() - p, p)).unwrap();
+        assert!(matches!(rv.try_add_rv(&rv), Err(RVError::Overflow)));
+        assert!(matches!(rv.try_max_two_trials(), Err(RVError::Overflow)));
+        assert!(matches!(rv.try_min_two_trials(), Err(RVError::Overflow)));
+        assert!(matches!(rv.try_max_three_trials(), Err(RVError::Overflow)));
+        assert!(matches!(rv.try_variance(), Err(RVError::Overflow)));
+        assert!(matches!(rv.try_central_moment(2), Err(RVError::Overflow)));
+        assert_eq!(p, rv.try_expected_value().unwrap());
+
+        let q = Rational64::new(1, 4000000009);
+        let other: VRV64 = VecRandVar::new(0, 1, vec!(Rational64::one() - q, q)).unwrap();
+        assert!(matches!(rv.try_wasserstein(&other), Err(RVError::Overflow)));
+        assert!(rv.try_wasserstein(&rv).is_ok());
+    }
+}
@@ -1,10 +1,9 @@
-use std::collections::{BTreeMap, BTreeSet};
 use std::marker::PhantomData;
 use std::ops::Add;
 
+use crate::rand_var::mixture::Mixture;
 use crate::rand_var::prob_type::{Prob, Reciprocal};
 use crate::rand_var::RandVar;
-use crate::rand_var::sequential::SeqIter;
 
 pub struct RVPartition<K: Ord + Clone, P: Prob, RV: RandVar<K, P>> {
     pub prob: P,
@@ -30,7 +29,7 @@ impl<K: Ord + Clone, P: Prob, RV: RandVar<K, P>> RVPartition<K, P, RV> {
     }
 }
 
-impl<K: Ord + Clone, P: Prob + Reciprocal, RV: RandVar<K, P>> Add for RVPartition<K, P, RV> {
+impl<K: Ord + Clone, P: Prob + Reciprocal + PartialOrd<P>, RV: RandVar<K, P>> Add for RVPartition<K, P, RV> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -40,48 +39,12 @@ impl<K: Ord + Clone, P: Prob + Reciprocal, RV: RandVar<K, P>> Add for RVPartitio
         if self.rv.is_none() {
             return rhs;
         }
-        let new_prob = self.prob.clone() + rhs.prob.clone();
-        let new_prob_recip = new_prob.clone().reciprocal().unwrap();
-
-        let left_mult = self.prob * new_prob_recip.clone();
-        let left_rv = self.rv.unwrap();
-
-        let right_mult = rhs.prob * new_prob_recip;
-        let right_rv = rhs.rv.unwrap();
-
-        let mut all_k = BTreeSet::new();
-        let mut pdf_map: BTreeMap<K, P> = BTreeMap::new();
-
-        for k in left_rv.get_keys() {
-            all_k.insert(k.clone());
-            let p = left_mult.clone() * left_rv.pdf_ref(&k);
-            if pdf_map.contains_key(&k) {
-                let old_p = pdf_map.get(&k).unwrap().clone();
-                pdf_map.insert(k, old_p + p);
-            } else {
-                pdf_map.insert(k, p);
-            }
-        }
-
-        for k in right_rv.get_keys() {
-            all_k.insert(k.clone());
-            let p = right_mult.clone() * right_rv.pdf_ref(&k);
-            if pdf_map.contains_key(&k) {
-                let old_p = pdf_map.get(&k).unwrap().clone();
-                pdf_map.insert(k, old_p + p);
-            } else {
-                pdf_map.insert(k, p);
-            }
-        }
-
-        let all_k_si = SeqIter { items: all_k };
-        let new_rv = RandVar::build(all_k_si, |k| {
-            if pdf_map.contains_key(&k) {
-                pdf_map.get(&k).unwrap().clone()
-            } else {
-                P::zero()
-            }
-        }).unwrap();
+        let mut mixture = Mixture::new();
+        // .unwrap() is fine here, because both partitions have valid probabilities and RVs.
+        // (the one thing this can't report is overflow, see Prob)
+        mixture.add(self.prob, &self.rv.unwrap()).unwrap();
+        mixture.add(rhs.prob, &rhs.rv.unwrap()).unwrap();
+        let (new_prob, new_rv) = mixture.build_normalized().unwrap();
         RVPartition::new(new_prob, new_rv)
     }
 }
@@ -95,7 +58,7 @@ mod tests {
     #[test]
     fn test_partitions() {
         let d20: VRV64 = VecRandVar::new_dice(20).unwrap();
-        let mut mod_3 = d20.partitions(|p| *p % 3);
+        let mut mod_3 = d20.partitions(|p| *p % 3).unwrap();
         assert_eq!(3, mod_3.len());
         let part_0 = mod_3.remove(&0).unwrap();
         assert_eq!(Rational64::new(6, 20), part_0.prob);
@@ -3,6 +3,10 @@ use std::collections::BTreeSet;
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::Add;
 use num::Rational64;
+use serde::{Deserialize, Serialize};
+
+// #[derive(Seq, Nested)] for fieldless enums
+pub use rand_var_derive::{Nested, Seq};
 
 #[derive(Clone)]
 pub struct SeqIter<K: Ord + Clone> {
@@ -54,7 +58,7 @@ impl Seq for isize {
     }
 }
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Pair<A: Ord + Clone, B: Ord + Clone>(pub A, pub B);
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use num::{BigRational, Integer, ToPrimitive};
+use num::rational::Ratio;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+
+use crate::map_rand_var::MapRandVar;
+use crate::rand_var::prob_type::{F64Prob, Prob, RVProb};
+use crate::rand_var::prob_type::adaptive_rational::AdaptiveRational;
+use crate::rand_var::RandVar;
+use crate::RVError;
+use crate::vec_rand_var::VecRandVar;
+
+// probabilities are written as exact numerator/denominator strings,
+// since they can easily be too big for any json number.
+pub trait ProbStrings: Sized {
+    fn to_strings(&self) -> (String, String);
+    fn from_strings(numer: &str, denom: &str) -> Option<Self>;
+}
+
+impl<I> ProbStrings for Ratio<I>
+where
+    I: Integer + Clone + Display + FromStr,
+{
+    fn to_strings(&self) -> (String, String) {
+        (self.numer().to_string(), self.denom().to_string())
+    }
+
+    fn from_strings(numer: &str, denom: &str) -> Option<Self> {
+        let numer: I = numer.parse().ok()?;
+        let denom: I = denom.parse().ok()?;
+        if denom.is_zero() {
+            return None;
+        }
+        Some(Ratio::new(numer, denom))
+    }
+}
+
+impl ProbStrings for AdaptiveRational {
+    fn to_strings(&self) -> (String, String) {
+        self.to_big().to_strings()
+    }
+
+    fn from_strings(numer: &str, denom: &str) -> Option<Self> {
+        BigRational::from_strings(numer, denom).map(AdaptiveRational::from)
+    }
+}
+
+// the exact value of the float, which is always a dyadic fraction.
+// NaN and infinity have no exact value, so they're written as the raw float over 1.
+impl ProbStrings for F64Prob {
+    fn to_strings(&self) -> (String, String) {
+        match BigRational::from_float(self.0) {
+            Some(r) => r.to_strings(),
+            None => (self.0.to_string(), String::from("1")),
+        }
+    }
+
+    fn from_strings(numer: &str, denom: &str) -> Option<Self> {
+        BigRational::from_strings(numer, denom)?.to_f64().map(F64Prob)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbEntry {
+    pub numer: String,
+    pub denom: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approx: Option<f64>, // ignored when reading
+}
+
+impl ProbEntry {
+    pub fn new<P: ProbStrings + ToPrimitive>(p: &P) -> Self {
+        let (numer, denom) = p.to_strings();
+        Self {
+            numer,
+            denom,
+            approx: p.to_f64(),
+        }
+    }
+
+    pub fn get_prob<P: ProbStrings>(&self) -> Result<P, RVError> {
+        P::from_strings(&self.numer, &self.denom)
+            .ok_or_else(|| RVError::Other(format!("invalid probability: {}/{}", self.numer, self.denom)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct VecRVDescription {
+    lower_bound: isize,
+    pdf: Vec<ProbEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct MapRVEntry<K> {
+    key: K,
+    #[serde(flatten)]
+    prob: ProbEntry,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct MapRVDescription<K> {
+    pdf: Vec<MapRVEntry<K>>,
+}
+
+impl<P> Serialize for VecRandVar<P>
+where
+    P: RVProb + ProbStrings + ToPrimitive,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VecRVDescription {
+            lower_bound: self.lower_bound(),
+            pdf: self.backing_vec().iter().map(ProbEntry::new).collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, P> Deserialize<'de> for VecRandVar<P>
+where
+    P: RVProb + ProbStrings,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let desc = VecRVDescription::deserialize(deserializer)?;
+        let to_err = |err: RVError| D::Error::custom(format!("invalid random variable: {:?}", err));
+        let pdf = desc.pdf.iter()
+            .map(|pe| pe.get_prob())
+            .collect::<Result<Vec<P>, RVError>>()
+            .map_err(to_err)?;
+        let ub = desc.lower_bound + (pdf.len() as isize) - 1;
+        VecRandVar::new(desc.lower_bound, ub, pdf).map_err(to_err)
+    }
+}
+
+impl<K, P> Serialize for MapRandVar<K, P>
+where
+    K: Ord + Clone + Serialize,
+    P: Prob + ProbStrings + ToPrimitive,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MapRVDescription {
+            pdf: self.backing_map().iter().map(|(k, p)| MapRVEntry { key: k.clone(), prob: ProbEntry::new(p) }).collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, K, P> Deserialize<'de> for MapRandVar<K, P>
+where
+    K: Ord + Clone + Deserialize<'de>,
+    P: RVProb + ProbStrings,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let desc: MapRVDescription<K> = MapRVDescription::deserialize(deserializer)?;
+        let to_err = |err: RVError| D::Error::custom(format!("invalid random variable: {:?}", err));
+        let mut pdf = BTreeMap::new();
+        for entry in desc.pdf.into_iter() {
+            let p: P = entry.prob.get_prob().map_err(to_err)?;
+            if pdf.insert(entry.key, p).is_some() {
+                return Err(D::Error::custom("duplicate key in random variable"));
+            }
+        }
+        MapRandVar::from_map(pdf).map_err(to_err)
+    }
+}
+
+// one row of pdf and cdf, for exporting to other tools
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRow<K> {
+    pub key: K,
+    pub pdf: ProbEntry,
+    pub cdf: ProbEntry,
+}
+
+// quotes a csv field if it needs it, following RFC 4180
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+pub fn export_rows<K, P, R>(rv: &R) -> Vec<ExportRow<K>>
+where
+    K: Ord + Clone,
+    P: Prob + ProbStrings + ToPrimitive,
+    R: RandVar<K, P> + ?Sized,
+{
+    rv.get_keys().map(|k| {
+        ExportRow {
+            pdf: ProbEntry::new(&rv.pdf_ref(&k)),
+            cdf: ProbEntry::new(&rv.cdf_ref(&k)),
+            key: k,
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{BigInt, BigRational, Rational64};
+
+    use crate::map_rand_var::{MapRandVar, MRV64};
+    use crate::num_rand_var::NumRandVar;
+    use crate::rand_var::prob_type::F64Prob;
+    use crate::rand_var::RandVar;
+    use crate::rand_var::sequential::Pair;
+    use crate::vec_rand_var::{VecRandVar, VRV64, VRVBig, VRVF64};
+
+    use super::{csv_field, ProbEntry, ProbStrings};
+
+    #[test]
+    fn test_vec_round_trip() {
+        let rv: VRV64 = VecRandVar::new_dice(6).unwrap().multiple(2).add_const(-3);
+        let json = serde_json::to_string(&rv).unwrap();
+        assert!(json.contains("\"numer\":\"1\",\"denom\":\"36\""));
+        let rv2: VRV64 = serde_json::from_str(&json).unwrap();
+        assert_eq!(rv, rv2);
+
+        // too big for a Rational64
+        let big = BigRational::new(BigInt::from(1), BigInt::from(i64::MAX) * BigInt::from(4));
+        let rest = BigRational::new(BigInt::from(1), BigInt::from(1)) - big.clone();
+        let rv: VRVBig = VecRandVar::new(0, 1, vec!(rest, big)).unwrap();
+        let json = serde_json::to_string(&rv).unwrap();
+        let rv2: VRVBig = serde_json::from_str(&json).unwrap();
+        assert_eq!(rv, rv2);
+        assert!(serde_json::from_str::<VRV64>(&json).is_err());
+
+        let rv: VRVF64 = VecRandVar::new_dice(10).unwrap();
+        let rv2: VRVF64 = serde_json::from_str(&serde_json::to_string(&rv).unwrap()).unwrap();
+        assert_eq!(rv, rv2);
+        assert_eq!(F64Prob(0.1), rv2.pdf(3));
+    }
+
+    #[test]
+    fn test_map_round_trip() {
+        let d4: MRV64 = VecRandVar::new_dice(4).unwrap().into_mrv();
+        let pairs: MapRandVar<Pair<isize, isize>, Rational64> = d4.independent_trials_self();
+        let json = serde_json::to_string(&pairs).unwrap();
+        let pairs2: MapRandVar<Pair<isize, isize>, Rational64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(pairs, pairs2);
+
+        let rv: MapRandVar<isize, BigRational> = VecRandVar::new_dice(20).unwrap().max_two_trials().into_mrv();
+        let rv2: MapRandVar<isize, BigRational> = serde_json::from_str(&serde_json::to_string(&rv).unwrap()).unwrap();
+        assert_eq!(rv, rv2);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(serde_json::from_str::<VRV64>(r#"{"lower_bound":1,"pdf":[{"numer":"1","denom":"2"}]}"#).is_err());
+        assert!(serde_json::from_str::<VRV64>(r#"{"lower_bound":1,"pdf":[{"numer":"1","denom":"0"}]}"#).is_err());
+        assert!(serde_json::from_str::<MRV64>(r#"{"pdf":[{"key":1,"numer":"1","denom":"2"},{"key":1,"numer":"1","denom":"2"}]}"#).is_err());
+        let rv: VRV64 = serde_json::from_str(r#"{"lower_bound":1,"pdf":[{"numer":"1","denom":"4"},{"numer":"3","denom":"4","approx":0.75}]}"#).unwrap();
+        assert_eq!(Rational64::new(3, 4), rv.pdf(2));
+    }
+
+    #[test]
+    fn test_export() {
+        let rv: VRV64 = VecRandVar::new_dice(4).unwrap();
+        let csv = rv.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(Some("key,pdf,pdf_approx,cdf,cdf_approx"), lines.next());
+        assert_eq!(Some("1,1/4,0.25,1/4,0.25"), lines.next());
+        assert_eq!(Some("4,1/4,0.25,1/1,1"), lines.last());
+
+        let json = rv.to_json().unwrap();
+        let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(4, rows.as_array().unwrap().len());
+        assert_eq!("3", rows[2]["cdf"]["numer"]);
+        assert_eq!(3, rows[2]["key"]);
+
+        let d2: MRV64 = VecRandVar::new_dice(2).unwrap().into_mrv();
+        let pairs: MapRandVar<Pair<isize, isize>, Rational64> = d2.independent_trials_self();
+        let csv = pairs.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(Some("key,pdf,pdf_approx,cdf,cdf_approx"), lines.next());
+        assert_eq!(Some("\"(1, 1)\",1/4,0.25,1/4,0.25"), lines.next());
+        assert_eq!(5, csv.lines().count());
+
+        assert_eq!("1", csv_field("1"));
+        assert_eq!("\"a \"\"b\"\"\"", csv_field("a \"b\""));
+    }
+
+    #[test]
+    fn test_non_finite_f64() {
+        assert_eq!((String::from("NaN"), String::from("1")), F64Prob(f64::NAN).to_strings());
+        assert_eq!((String::from("inf"), String::from("1")), F64Prob(f64::INFINITY).to_strings());
+        assert!(ProbEntry::new(&F64Prob(f64::NAN)).get_prob::<F64Prob>().is_err());
+        assert_eq!((String::from("1"), String::from("4")), F64Prob(0.25).to_strings());
+    }
+}
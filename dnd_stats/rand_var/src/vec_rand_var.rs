@@ -1,10 +1,13 @@
+use std::collections::BTreeMap;
 use std::iter::Sum;
 
 use num::{BigRational, FromPrimitive, Rational64};
 
 use crate::map_rand_var::MapRandVar;
 use crate::num_rand_var::NumRandVar;
-use crate::rand_var::prob_type::{Prob, Reciprocal};
+use crate::rand_var::cdf_cache::CdfCache;
+use crate::rand_var::mixture::Mixture;
+use crate::rand_var::prob_type::{checked_sum, F64Prob, Prob, Reciprocal};
 use crate::rand_var::RandVar;
 use crate::rand_var::sequential::{Seq, SeqIter};
 use crate::RVError;
@@ -14,10 +17,12 @@ pub struct VecRandVar<P: Prob> {
     lower_bound: isize,
     upper_bound: isize,
     pdf_vec: Vec<P>,
+    cdf_cache: CdfCache<P>,
 }
 
 pub type VRV64 = VecRandVar<Rational64>;
 pub type VRVBig = VecRandVar<BigRational>;
+pub type VRVF64 = VecRandVar<F64Prob>;
 
 impl<P> VecRandVar<P>
 where
@@ -30,7 +35,7 @@ where
         if (ub-lb+1) != (v.len() as isize) {
             return Err(RVError::Other(String::from("vector must be of correct length")));
         }
-        if P::one() != v.iter().sum() {
+        if !checked_sum(v.iter())?.approx_eq(&P::one()) {
             return Err(RVError::CDFNotOne);
         }
         if v.iter().min().unwrap() < &P::zero() {
@@ -39,7 +44,8 @@ where
         Ok(Self {
             lower_bound: lb,
             upper_bound: ub,
-            pdf_vec: v
+            pdf_vec: v,
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -48,6 +54,44 @@ where
     }
 }
 
+impl<P> VecRandVar<P>
+where
+    P: Prob + PartialEq<P> + for<'a> Sum<&'a P> + Ord
+{
+    // the number of successes across independent trials with (possibly) different chances.
+    pub fn new_poisson_binomial(probs: &[P]) -> Result<Self, RVError> {
+        let mut v = vec!(P::one());
+        for p in probs {
+            if *p < P::zero() {
+                return Err(RVError::NegProb);
+            }
+            if *p > P::one() {
+                return Err(RVError::Other(String::from("chance of success can't be more than 1")));
+            }
+            let q = P::one() - p.clone();
+            // v[i] = chance of i successes so far
+            let mut next = Vec::with_capacity(v.len() + 1);
+            for i in 0..=v.len() {
+                let mut total = P::zero();
+                if i < v.len() {
+                    total = v[i].checked_mul(&q).ok_or(RVError::Overflow)?;
+                }
+                if i > 0 {
+                    let success = v[i-1].checked_mul(p).ok_or(RVError::Overflow)?;
+                    total = total.checked_add(&success).ok_or(RVError::Overflow)?;
+                }
+                next.push(total);
+            }
+            v = next;
+        }
+        Self::new(0, probs.len() as isize, v)
+    }
+
+    pub fn new_binomial(n: usize, p: P) -> Result<Self, RVError> {
+        Self::new_poisson_binomial(&vec!(p; n))
+    }
+}
+
 impl<P> VecRandVar<P>
 where
     P: Prob + Reciprocal + FromPrimitive
@@ -64,7 +108,8 @@ where
         Ok(Self {
             lower_bound: 1,
             upper_bound: sides,
-            pdf_vec: v
+            pdf_vec: v,
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -93,7 +138,8 @@ where
         Ok(Self {
             lower_bound: 1,
             upper_bound: sides,
-            pdf_vec: v
+            pdf_vec: v,
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -101,7 +147,8 @@ where
         Ok(Self {
             lower_bound: value,
             upper_bound: value,
-            pdf_vec: vec!(P::one())
+            pdf_vec: vec!(P::one()),
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -118,7 +165,44 @@ where
         Ok(Self {
             lower_bound: lb,
             upper_bound: ub,
-            pdf_vec: v
+            pdf_vec: v,
+            cdf_cache: CdfCache::new(),
+        })
+    }
+
+    // see NumRandVar::explode for what the second value is
+    pub fn new_dice_explode(sides: isize, max_depth: u32) -> Result<(Self, P), RVError>
+    where
+        P: PartialOrd<P>
+    {
+        Self::new_dice(sides)?.explode(max_depth)
+    }
+
+    pub fn new_dice_keep_max(sides: isize, num_rerolls: u32) -> Result<Self, RVError>
+    where
+        P: PartialOrd<P>
+    {
+        Ok(Self::new_dice(sides)?.reroll_keep_max(num_rerolls))
+    }
+
+    // e.g.: elemental adept is new_dice_clamped(sides, 2, sides)
+    pub fn new_dice_clamped(sides: isize, min_roll: isize, max_roll: isize) -> Result<Self, RVError>
+    where
+        P: PartialOrd<P>
+    {
+        Self::new_dice(sides)?.clamp(min_roll, max_roll)
+    }
+}
+
+impl<P: Prob> VecRandVar<P> {
+    // cumulative pdf, so cdf lookups are O(1) after the first one.
+    fn cdf_table(&self) -> &Vec<P> {
+        self.cdf_cache.get_or_build(|| {
+            let mut total = P::zero();
+            self.pdf_vec.iter().map(|p| {
+                total = total.clone() + p.clone();
+                total.clone()
+            }).collect()
         })
     }
 }
@@ -144,20 +228,21 @@ where
                 if f_i < P::zero() {
                     return Err(RVError::NegProb);
                 }
-                total = total + f_i.clone();
+                total = total.checked_add(&f_i).ok_or(RVError::Overflow)?;
                 pdf_vec.push(f_i);
             } else {
                 pdf_vec.push(P::zero());
             }
         }
-        if P::one() != total {
+        if !total.approx_eq(&P::one()) {
             return Err(RVError::CDFNotOne);
         }
 
         Ok(Self {
             lower_bound: lb,
             upper_bound: ub,
-            pdf_vec
+            pdf_vec,
+            cdf_cache: CdfCache::new(),
         })
     }
 
@@ -183,6 +268,26 @@ where
     fn len(&self) -> usize {
         self.pdf_vec.len()
     }
+
+    fn cdf_ref(&self, k: &isize) -> P {
+        if self.upper_bound <= *k {
+            P::one()
+        } else if self.lower_bound <= *k {
+            self.cdf_table()[(k - self.lower_bound) as usize].clone()
+        } else {
+            P::zero()
+        }
+    }
+
+    fn cdf_exclusive_ref(&self, k: &isize) -> P {
+        if self.upper_bound < *k {
+            P::one()
+        } else if self.lower_bound < *k {
+            self.cdf_table()[(k - self.lower_bound - 1) as usize].clone()
+        } else {
+            P::zero()
+        }
+    }
 }
 
 impl<P> NumRandVar<isize, P> for VecRandVar<P>
@@ -217,11 +322,88 @@ impl<P: Prob> From<MapRandVar<isize, P>> for VecRandVar<P> {
         Self {
             lower_bound: lb,
             upper_bound: ub,
-            pdf_vec
+            pdf_vec,
+            cdf_cache: CdfCache::new(),
         }
     }
 }
 
+impl<P> VecRandVar<P>
+where
+    P: Prob + PartialOrd<P> + Reciprocal + FromPrimitive
+{
+    // self is a number of successes, and each success adds an independent copy of per_success.
+    // e.g.: hits from a poisson binomial with the damage of one hit.
+    pub fn compound(&self, per_success: &Self) -> Result<Self, RVError> {
+        if self.lower_bound < 0 {
+            return Err(RVError::Other(String::from("can't have a negative number of successes")));
+        }
+        let mut mixture = Mixture::new();
+        let mut total = Self::new_constant(0)?;
+        for n in 0..=self.upper_bound {
+            if n > 0 {
+                total = total.try_add_rv(per_success)?;
+            }
+            if n >= self.lower_bound {
+                mixture.add(self.pdf(n), &total)?;
+            }
+        }
+        mixture.build()
+    }
+
+    // the first round where the running total of the per round increments reaches threshold,
+    // e.g.: how many rounds until a monster with threshold hp dies. first_round replaces
+    // per_round in round 1 if given. Rounds are only simulated up to max_rounds; anything
+    // that hasn't reached threshold by then is put on max_rounds + 1, and that mass is also
+    // returned as the second value.
+    pub fn rounds_to_reach(threshold: isize, per_round: &Self, first_round: Option<&Self>, max_rounds: usize) -> Result<(Self, P), RVError> {
+        if per_round.lower_bound < 0 || first_round.is_some_and(|rv| rv.lower_bound < 0) {
+            return Err(RVError::Other(String::from("increments can't be negative")));
+        }
+        if threshold <= 0 {
+            return Ok((Self::new_constant(0)?, P::zero()));
+        }
+        // below[i] = chance the total is i and hasn't reached threshold yet
+        let mut below = vec!(P::zero(); threshold as usize);
+        below[0] = P::one();
+        let mut pdf: BTreeMap<isize, P> = BTreeMap::new();
+        for round in 1..=max_rounds {
+            let increment = match first_round {
+                Some(rv) if round == 1 => rv,
+                _ => per_round,
+            };
+            let mut next = vec!(P::zero(); threshold as usize);
+            let mut reached = P::zero();
+            for (total, p_total) in below.iter().enumerate().filter(|(_, p)| !p.is_zero()) {
+                for (i, p_inc) in increment.pdf_vec.iter().enumerate().filter(|(_, p)| !p.is_zero()) {
+                    let p = p_total.checked_mul(p_inc).ok_or(RVError::Overflow)?;
+                    let new_total = (total as isize) + increment.lower_bound + (i as isize);
+                    if new_total >= threshold {
+                        reached = reached.checked_add(&p).ok_or(RVError::Overflow)?;
+                    } else {
+                        let j = new_total as usize;
+                        next[j] = next[j].checked_add(&p).ok_or(RVError::Overflow)?;
+                    }
+                }
+            }
+            if !reached.is_zero() {
+                pdf.insert(round as isize, reached);
+            }
+            below = next;
+            if below.iter().all(|p| p.is_zero()) {
+                break;
+            }
+        }
+        let leftover = checked_sum(below.iter())?;
+        if !leftover.is_zero() {
+            pdf.insert((max_rounds as isize) + 1, leftover.clone());
+        }
+        let seq_iter = SeqIter { items: pdf.keys().cloned().collect() };
+        let rv = RandVar::build(seq_iter, |k| pdf.get(&k).unwrap().clone())?;
+        Ok((rv, leftover))
+    }
+}
+
 impl<P: Prob + PartialOrd<P>> VecRandVar<P> {
     pub fn into_mrv(self) -> MapRandVar<isize, P> {
         self.into()
@@ -235,6 +417,77 @@ mod tests {
     use crate::rand_var::RandVar;
     use crate::vec_rand_var::{VecRandVar, VRV64};
 
+    #[test]
+    fn test_poisson_binomial() {
+        let half = Rational64::new(1, 2);
+        let rv: VRV64 = VecRandVar::new_poisson_binomial(&[half, Rational64::new(1, 3), Rational64::one()]).unwrap();
+        assert_eq!(0, rv.lower_bound());
+        assert_eq!(3, rv.upper_bound());
+        assert_eq!(Rational64::zero(), rv.pdf(0));
+        assert_eq!(Rational64::new(1, 3), rv.pdf(1));
+        assert_eq!(Rational64::new(1, 2), rv.pdf(2));
+        assert_eq!(Rational64::new(1, 6), rv.pdf(3));
+        assert_eq!(Rational64::new(11, 6), rv.expected_value());
+
+        let binom: VRV64 = VecRandVar::new_binomial(4, half).unwrap();
+        assert_eq!(Rational64::new(6, 16), binom.pdf(2));
+        assert_eq!(Rational64::new(2, 1), binom.expected_value());
+        assert_eq!(Rational64::one(), binom.variance());
+
+        let none: VRV64 = VecRandVar::new_poisson_binomial(&[]).unwrap();
+        assert_eq!(VecRandVar::new_constant(0).unwrap(), none);
+        assert!(VecRandVar::new_poisson_binomial(&[Rational64::new(3, 2)]).is_err());
+        assert!(VecRandVar::new_binomial(2, Rational64::new(-1, 2)).is_err());
+    }
+
+    #[test]
+    fn test_compound() {
+        // 2 attacks at 50%, each doing a d6: the same as branching on every attack
+        let half = Rational64::new(1, 2);
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let hits: VRV64 = VecRandVar::new_binomial(2, half).unwrap();
+        let dmg = hits.compound(&d6).unwrap();
+        let one_atk: VRV64 = VecRandVar::new_poisson_binomial(&[half]).unwrap().compound(&d6).unwrap();
+        assert_eq!(one_atk.add_rv(&one_atk), dmg);
+        assert_eq!(Rational64::new(1, 4), dmg.pdf(0));
+        assert_eq!(Rational64::new(7, 2), dmg.expected_value());
+
+        assert!(d6.add_const(-2).compound(&d6).is_err());
+    }
+
+    #[test]
+    fn test_rounds_to_reach() {
+        let d6: VRV64 = VecRandVar::new_dice(6).unwrap();
+        let (rounds, leftover) = VecRandVar::rounds_to_reach(2, &d6, None, 10).unwrap();
+        assert_eq!(Rational64::zero(), leftover);
+        assert_eq!(Rational64::new(5, 6), rounds.pdf(1));
+        assert_eq!(Rational64::new(1, 6), rounds.pdf(2));
+        assert_eq!(2, rounds.upper_bound());
+
+        // a big first round (e.g.: an alpha strike) followed by a steady 5 a round
+        let five: VRV64 = VecRandVar::new_constant(5).unwrap();
+        let (rounds, _) = VecRandVar::rounds_to_reach(20, &five, None, 10).unwrap();
+        assert_eq!(VecRandVar::new_constant(4).unwrap(), rounds);
+        let ten: VRV64 = VecRandVar::new_constant(10).unwrap();
+        let (rounds, _) = VecRandVar::rounds_to_reach(20, &five, Some(&ten), 10).unwrap();
+        assert_eq!(VecRandVar::new_constant(3).unwrap(), rounds);
+
+        // 4 rounds of 0 or 1 can't reach 5, and sometimes can't reach 3
+        let coin: VRV64 = VecRandVar::new_uniform(0, 1).unwrap();
+        let (rounds, leftover) = VecRandVar::rounds_to_reach(5, &coin, None, 4).unwrap();
+        assert_eq!(Rational64::one(), leftover);
+        assert_eq!(VecRandVar::new_constant(5).unwrap(), rounds);
+        let (rounds, leftover) = VecRandVar::rounds_to_reach(3, &coin, None, 4).unwrap();
+        assert_eq!(Rational64::new(11, 16), leftover);
+        assert_eq!(Rational64::new(1, 8), rounds.pdf(3));
+        assert_eq!(Rational64::new(3, 16), rounds.pdf(4));
+        assert_eq!(leftover, rounds.pdf(5));
+
+        let (rounds, _) = VecRandVar::rounds_to_reach(0, &d6, None, 4).unwrap();
+        assert_eq!(VecRandVar::new_constant(0).unwrap(), rounds);
+        assert!(VecRandVar::rounds_to_reach(3, &d6.add_const(-2), None, 4).is_err());
+    }
+
     #[test]
     fn test_const() {
         let rv: VRV64 = VecRandVar::new_constant(5).unwrap();
@@ -316,4 +569,52 @@ mod tests {
         assert_eq!(Rational64::new(63,10), rv.expected_value());
         assert_eq!(Rational64::new(601,100), rv.variance());
     }
+
+    #[test]
+    fn test_explode() {
+        let (d6, truncated): (VRV64, Rational64) = VecRandVar::new_dice_explode(6, 2).unwrap();
+        assert_eq!(1, d6.lower_bound());
+        assert_eq!(18, d6.upper_bound());
+        assert_eq!(Rational64::new(1, 6), d6.pdf(5));
+        assert_eq!(Rational64::zero(), d6.pdf(6));
+        assert_eq!(Rational64::new(1, 36), d6.pdf(7));
+        assert_eq!(Rational64::new(1, 216), d6.pdf(13));
+        assert_eq!(Rational64::new(1, 216), d6.pdf(18));
+        assert_eq!(d6.pdf(18), truncated);
+
+        let (d4, truncated): (VRV64, Rational64) = VecRandVar::new_dice_explode(4, 0).unwrap();
+        assert_eq!(VecRandVar::new_dice(4).unwrap(), d4);
+        assert_eq!(Rational64::new(1, 4), truncated);
+    }
+
+    #[test]
+    fn test_keep_max_and_clamp() {
+        let d8: VRV64 = VecRandVar::new_dice(8).unwrap();
+        assert_eq!(d8.max_two_trials(), VecRandVar::new_dice_keep_max(8, 1).unwrap());
+        assert_eq!(d8.max_three_trials(), VecRandVar::new_dice_keep_max(8, 2).unwrap());
+
+        // 2024 great weapon fighting
+        let gwf: VRV64 = VecRandVar::new_dice_clamped(6, 3, 6).unwrap();
+        assert_eq!(3, gwf.lower_bound());
+        assert_eq!(Rational64::new(1, 2), gwf.pdf(3));
+        assert_eq!(Rational64::new(1, 6), gwf.pdf(4));
+        let capped: VRV64 = VecRandVar::new_dice_clamped(10, 1, 5).unwrap();
+        assert_eq!(Rational64::new(6, 10), capped.pdf(5));
+        assert!(VecRandVar::<Rational64>::new_dice_clamped(6, 4, 3).is_err());
+    }
+
+    #[test]
+    fn test_cdf_cache() {
+        let rv: VRV64 = VecRandVar::new_dice(8).unwrap().multiple(3).add_const(-4);
+        let clone = rv.clone();
+        for x in -5..=25 {
+            let naive: Rational64 = rv.get_keys().take_while(|k| *k <= x).map(|k| rv.pdf(k)).sum();
+            assert_eq!(naive, rv.cdf(x));
+            let naive_ex: Rational64 = rv.get_keys().take_while(|k| *k < x).map(|k| rv.pdf(k)).sum();
+            assert_eq!(naive_ex, rv.cdf_exclusive(x));
+        }
+        assert!(rv.cdf_cache.is_built());
+        assert!(!clone.cdf_cache.is_built());
+        assert_eq!(rv, clone);
+    }
 }
@@ -0,0 +1,72 @@
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, parse_macro_input};
+
+// Only fieldless enums are supported. The order of the sequence is the order
+// the variants are declared in, so the enum should also derive Ord.
+fn get_variants(input: &DeriveInput, derive_name: &str) -> Result<Vec<Ident>, syn::Error> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => return Err(syn::Error::new_spanned(&input.ident, format!("{} can only be derived for enums", derive_name))),
+    };
+    let mut variants = Vec::new();
+    for variant in data.variants.iter() {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, format!("{} can only be derived for enums without fields", derive_name)));
+        }
+        variants.push(variant.ident.clone());
+    }
+    Ok(variants)
+}
+
+#[proc_macro_derive(Seq)]
+pub fn derive_seq(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let variants = match get_variants(&input, "Seq") {
+        Ok(v) => v,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::rand_var::rand_var::sequential::Seq for #name #ty_generics #where_clause {
+            fn gen_seq(&self, other: &Self) -> ::rand_var::rand_var::sequential::SeqIter<Self> {
+                let first = ::std::cmp::min(self, other);
+                let second = ::std::cmp::max(self, other);
+                let items = [#(#name::#variants),*]
+                    .into_iter()
+                    .filter(|v| v >= first && v <= second)
+                    .collect();
+                ::rand_var::rand_var::sequential::SeqIter { items }
+            }
+
+            fn always_convex() -> bool {
+                true
+            }
+
+            fn convex_bounds(iter: ::rand_var::rand_var::sequential::SeqIter<Self>) -> Option<(Self, Self)> {
+                ::rand_var::rand_var::sequential::always_convex_bounds(iter)
+            }
+        }
+    };
+    expanded.into()
+}
+
+#[proc_macro_derive(Nested)]
+pub fn derive_nested(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    if let Err(e) = get_variants(&input, "Nested") {
+        return e.to_compile_error().into();
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::rand_var::rand_var::sequential::Nested<Self> for #name #ty_generics #where_clause {
+            fn get_k(&self) -> Self {
+                self.clone()
+            }
+        }
+    };
+    expanded.into()
+}